@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::model::User;
+use crate::util::id::{id_de, id_ser};
+
+/// Represents the owner of the archive, from `account.js`
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    /// Unique identifier for the user
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub account_id: u64,
+    /// The screen name of the user
+    pub username: String,
+    /// The display name of the user
+    pub account_display_name: String,
+    /// When the account was created
+    pub created_at: DateTime<Utc>,
+    /// Email address of the account
+    pub email: Option<String>,
+    /// The client the account was created with
+    pub created_via: Option<String>,
+}
+
+/// Represents the public profile of the archive owner, from `profile.js`
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    /// The text fields of the profile
+    #[serde(default)]
+    pub description: ProfileDescription,
+    /// Url of the profile image
+    pub avatar_media_url: Option<String>,
+    /// Url of the profile banner
+    pub header_media_url: Option<String>,
+}
+
+/// The text fields of a profile
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ProfileDescription {
+    /// The biography of the user
+    pub bio: Option<String>,
+    /// The link in the profile
+    pub website: Option<String>,
+    /// The location the user entered
+    pub location: Option<String>,
+}
+
+impl Account {
+    /// Builds a `User` from this account and, if present, its profile.
+    /// Counts are not part of the archive and are left at zero.
+    pub fn to_user(&self, profile: Option<&Profile>) -> User {
+        let non_empty = |field: &Option<String>| field.clone().filter(|value| !value.is_empty());
+
+        let mut user = User {
+            id: self.account_id,
+            id_str: self.account_id.to_string(),
+            name: self.account_display_name.clone(),
+            screen_name: self.username.clone(),
+            created_at: self.created_at,
+            default_profile_image: true,
+            ..Default::default()
+        };
+
+        if let Some(profile) = profile {
+            user.description = non_empty(&profile.description.bio);
+            user.url = non_empty(&profile.description.website);
+            user.location = non_empty(&profile.description.location);
+            user.profile_banner_url = non_empty(&profile.header_media_url);
+
+            if let Some(avatar) = non_empty(&profile.avatar_media_url) {
+                user.profile_image_url_https = avatar;
+                user.default_profile_image = false;
+            }
+        }
+
+        user
+    }
+}
@@ -0,0 +1,189 @@
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::model::{DirectMessage, Url};
+use crate::util::id::{id_de, id_ser, ids_de, ids_ser, opt_id_de, opt_id_ser};
+
+/// Represents a direct message conversation, from `direct-messages.js`
+/// or `direct-messages-group.js`
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DmConversation {
+    /// Id of the conversation. One to one conversations are named
+    /// after both participants, like `12-34`.
+    pub conversation_id: String,
+    /// Everything that happened in the conversation, newest first
+    #[serde(default)]
+    pub messages: Vec<DmEvent>,
+}
+
+/// Something that happened in a direct message conversation
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DmEvent {
+    /// A message was sent
+    MessageCreate(DmMessage),
+    /// The archive owner was added to a group conversation
+    JoinConversation(DmJoinConversation),
+    /// Users were added to a group conversation
+    ParticipantsJoin(DmParticipantsChange),
+    /// Users left a group conversation
+    ParticipantsLeave(DmParticipantsChange),
+    /// A group conversation was renamed
+    ConversationNameUpdate(DmNameUpdate),
+    /// An event this crate does not model, like `reactionCreate`, kept
+    /// as it appears in the archive
+    #[serde(untagged)]
+    Other(Value),
+}
+
+/// A direct message
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DmMessage {
+    /// The unique id for the message
+    pub id: u64,
+    /// Id of the user who sent the message
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub sender_id: u64,
+    /// Id of the user the message was sent to. Not set in group conversations.
+    #[serde(default, deserialize_with="opt_id_de", serialize_with="opt_id_ser")]
+    pub recipient_id: Option<u64>,
+    /// Text of the message
+    #[serde(default)]
+    pub text: String,
+    /// When the message was sent
+    pub created_at: DateTime<Utc>,
+    /// Links included in the message
+    #[serde(default)]
+    pub urls: Vec<DmUrl>,
+    /// Links to attached media
+    #[serde(default)]
+    pub media_urls: Vec<String>,
+    /// Reactions to the message
+    #[serde(default)]
+    pub reactions: Vec<DmReaction>,
+}
+
+/// A link in a direct message
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DmUrl {
+    /// The t.co version of the link
+    pub url: String,
+    /// The original link
+    pub expanded: String,
+    /// The link as displayed in the message
+    pub display: String,
+}
+
+/// A reaction to a direct message
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DmReaction {
+    /// Id of the user who reacted
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub sender_id: u64,
+    /// Name of the reaction, like `like` or `funny`
+    pub reaction_key: String,
+    /// Id of the reaction
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub event_id: u64,
+    /// When the reaction was added
+    pub created_at: DateTime<Utc>,
+}
+
+/// The archive owner being added to a group conversation
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DmJoinConversation {
+    /// Id of the user who added the archive owner
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub initiating_user_id: u64,
+    /// Ids of everyone in the conversation at that time
+    #[serde(default, deserialize_with="ids_de", serialize_with="ids_ser")]
+    pub participants_snapshot: Vec<u64>,
+    /// When the archive owner was added
+    pub created_at: DateTime<Utc>,
+}
+
+/// Users joining or leaving a group conversation
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DmParticipantsChange {
+    /// Id of the user who added the others. Not set when users leave.
+    #[serde(default, deserialize_with="opt_id_de", serialize_with="opt_id_ser")]
+    pub initiating_user_id: Option<u64>,
+    /// Ids of the users who joined or left
+    #[serde(deserialize_with="ids_de", serialize_with="ids_ser")]
+    pub user_ids: Vec<u64>,
+    /// When it happened
+    pub created_at: DateTime<Utc>,
+}
+
+/// A group conversation being renamed
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DmNameUpdate {
+    /// Id of the user who renamed the conversation
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub initiating_user_id: u64,
+    /// The new name
+    pub name: String,
+    /// When the conversation was renamed
+    pub created_at: DateTime<Utc>,
+}
+
+impl DmConversation {
+    /// Iterates over the messages of the conversation, skipping other events
+    pub fn messages(&self) -> impl Iterator<Item = &DmMessage> {
+        self.messages.iter().filter_map(|event| match event {
+            DmEvent::MessageCreate(message) => Some(message),
+            _ => None,
+        })
+    }
+
+    /// Converts the messages of the conversation into `DirectMessage`s
+    pub fn direct_messages(&self) -> Vec<DirectMessage> {
+        self.messages()
+            .map(|message| message.to_direct_message(&self.conversation_id))
+            .collect()
+    }
+}
+
+impl DmMessage {
+    /// Converts this message into a `DirectMessage`. The archive does
+    /// not store link positions, so they are looked up in the text.
+    /// Attached media are only available as links and are not converted.
+    pub fn to_direct_message(&self, conversation_id: &str) -> DirectMessage {
+        let mut message = DirectMessage {
+            id: self.id,
+            conversation_id: conversation_id.to_string(),
+            sender_id: self.sender_id,
+            recipient_id: self.recipient_id,
+            created_at: self.created_at,
+            text: self.text.clone(),
+            ..Default::default()
+        };
+
+        for url in &self.urls {
+            let indices = match self.text.find(&url.url) {
+                Some(start) => {
+                    let start = self.text[..start].chars().count() as u32;
+                    vec![start, start + url.url.chars().count() as u32]
+                }
+                None => Vec::new(),
+            };
+
+            message.entities.urls.push(Url {
+                display_url: url.display.clone(),
+                expanded_url: url.expanded.clone(),
+                indices,
+                url: url.url.clone(),
+                ..Default::default()
+            });
+        }
+
+        message
+    }
+}
@@ -0,0 +1,494 @@
+//! Parser for the downloadable "Your Twitter Data" archive.
+//!
+//! Every file in the `data` directory of the archive is a JavaScript
+//! assignment like `window.YTD.tweets.part0 = [...]`, where each array
+//! element wraps the actual object in a single key such as `tweet`.
+//! Large files are split into `tweets.js`, `tweets-part1.js` and so on.
+//! The archive can be read from the unpacked directory or, with the
+//! `zip` feature, straight from the downloaded zip file.
+
+mod account;
+mod dm;
+mod social;
+mod tweet;
+
+pub use account::{Account, Profile, ProfileDescription};
+pub use dm::{
+    DmConversation,
+    DmEvent,
+    DmJoinConversation,
+    DmMessage,
+    DmNameUpdate,
+    DmParticipantsChange,
+    DmReaction,
+    DmUrl,
+};
+pub use social::{Like, Relationship};
+pub use tweet::ArchiveTweet;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "zip")]
+use std::sync::{Mutex, PoisonError};
+
+use crate::model::{Tweet, User};
+
+/// Errors that can occur while reading an archive
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be read
+    Io(io::Error),
+    /// The contents of a file could not be parsed
+    Json(serde_json::Error),
+    /// A file is not in the `window.YTD` format
+    Format(String),
+    /// The zip file could not be read
+    #[cfg(feature = "zip")]
+    Zip(zip::result::ZipError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(why) => write!(f, "io error: {}", why),
+            Error::Json(why) => write!(f, "json error: {}", why),
+            Error::Format(why) => write!(f, "format error: {}", why),
+            #[cfg(feature = "zip")]
+            Error::Zip(why) => write!(f, "zip error: {}", why),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(why) => Some(why),
+            Error::Json(why) => Some(why),
+            Error::Format(_) => None,
+            #[cfg(feature = "zip")]
+            Error::Zip(why) => Some(why),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(why: io::Error) -> Self {
+        Error::Io(why)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(why: serde_json::Error) -> Self {
+        Error::Json(why)
+    }
+}
+
+#[cfg(feature = "zip")]
+impl From<zip::result::ZipError> for Error {
+    fn from(why: zip::result::ZipError) -> Self {
+        Error::Zip(why)
+    }
+}
+
+/// Anything a zip file can be read from
+#[cfg(feature = "zip")]
+trait ReadSeek: io::Read + io::Seek + Send {}
+
+#[cfg(feature = "zip")]
+impl<T: io::Read + io::Seek + Send> ReadSeek for T {}
+
+/// Where the archive files are read from
+enum Source {
+    Dir(PathBuf),
+    //  Kept open so the central directory is only read once
+    #[cfg(feature = "zip")]
+    Zip(Mutex<zip::ZipArchive<Box<dyn ReadSeek>>>),
+}
+
+impl fmt::Debug for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Dir(dir) => f.debug_tuple("Dir").field(dir).finish(),
+            #[cfg(feature = "zip")]
+            Source::Zip(zip) => write!(f, "Zip({} files)", zip.lock().unwrap_or_else(PoisonError::into_inner).len()),
+        }
+    }
+}
+
+/// A personal data archive
+#[derive(Debug)]
+pub struct Archive {
+    source: Source,
+}
+
+impl Archive {
+    /// Opens an archive from either the unpacked directory or, with
+    /// the `zip` feature, the zip file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        #[cfg(feature = "zip")]
+        {
+            if path.is_file() {
+                return Archive::from_zip(fs::File::open(path)?);
+            }
+        }
+
+        if !path.is_dir() {
+            return Err(Error::Format(format!("{} is not an archive directory", path.display())));
+        }
+
+        Ok(Archive { source: Source::Dir(path.to_path_buf()) })
+    }
+
+    /// Opens an archive from a zip file that is already open or in memory
+    #[cfg(feature = "zip")]
+    pub fn from_zip<R: io::Read + io::Seek + Send + 'static>(reader: R) -> Result<Self, Error> {
+        let zip = zip::ZipArchive::new(Box::new(reader) as Box<dyn ReadSeek>)?;
+
+        Ok(Archive { source: Source::Zip(Mutex::new(zip)) })
+    }
+
+    /// Reads the tweets of the archive, from every part
+    pub fn tweets(&self) -> Result<Vec<ArchiveTweet>, Error> {
+        //  Older archives call the file `tweet.js`
+        let mut tweets = self.read_all("tweets", "tweet")?;
+
+        if tweets.is_empty() {
+            tweets = self.read_all("tweet", "tweet")?;
+        }
+
+        Ok(tweets)
+    }
+
+    /// Reads the account information of the archive owner
+    pub fn account(&self) -> Result<Option<Account>, Error> {
+        Ok(self.read_all("account", "account")?.into_iter().next())
+    }
+
+    /// Reads the profile of the archive owner
+    pub fn profile(&self) -> Result<Option<Profile>, Error> {
+        Ok(self.read_all("profile", "profile")?.into_iter().next())
+    }
+
+    /// Reads the tweets the archive owner liked
+    pub fn likes(&self) -> Result<Vec<Like>, Error> {
+        self.read_all("like", "like")
+    }
+
+    /// Reads the accounts following the archive owner
+    pub fn followers(&self) -> Result<Vec<Relationship>, Error> {
+        self.read_all("follower", "follower")
+    }
+
+    /// Reads the accounts the archive owner follows
+    pub fn following(&self) -> Result<Vec<Relationship>, Error> {
+        self.read_all("following", "following")
+    }
+
+    /// Reads the accounts the archive owner blocked
+    pub fn blocks(&self) -> Result<Vec<Relationship>, Error> {
+        self.read_all("block", "blocking")
+    }
+
+    /// Reads the accounts the archive owner muted
+    pub fn mutes(&self) -> Result<Vec<Relationship>, Error> {
+        self.read_all("mute", "muting")
+    }
+
+    /// Reads the one to one direct message conversations
+    pub fn direct_messages(&self) -> Result<Vec<DmConversation>, Error> {
+        self.read_all("direct-messages", "dmConversation")
+    }
+
+    /// Reads the group direct message conversations
+    pub fn group_direct_messages(&self) -> Result<Vec<DmConversation>, Error> {
+        self.read_all("direct-messages-group", "dmConversation")
+    }
+
+    /// Builds the archive owner from `account.js` and `profile.js`.
+    /// Follower and activity counts are not part of the archive and
+    /// are left at zero.
+    pub fn user(&self) -> Result<User, Error> {
+        let account = self.account()?
+                          .ok_or_else(|| Error::Format(String::from("archive has no account.js")))?;
+
+        Ok(account.to_user(self.profile()?.as_ref()))
+    }
+
+    /// Reads the tweets of the archive as `Tweet`s authored by `user`
+    pub fn model_tweets(&self) -> Result<Vec<Tweet>, Error> {
+        let user = self.user()?;
+
+        Ok(self.tweets()?
+               .into_iter()
+               .map(|tweet| tweet.into_tweet(user.clone()))
+               .collect())
+    }
+
+    /// Reads every part of a data file, unwrapping each element from its `key`
+    pub(crate) fn read_all<T: DeserializeOwned>(&self, name: &str, key: &str) -> Result<Vec<T>, Error> {
+        let mut items = Vec::new();
+
+        for part in 0.. {
+            let file = if part == 0 {
+                format!("{}.js", name)
+            } else {
+                format!("{}-part{}.js", name, part)
+            };
+
+            match self.read_file(&file)? {
+                Some(contents) => items.extend(parse(&contents, key)?),
+                None => break,
+            }
+        }
+
+        Ok(items)
+    }
+
+    fn read_file(&self, file: &str) -> Result<Option<String>, Error> {
+        match &self.source {
+            Source::Dir(dir) => {
+                let path = dir.join("data").join(file);
+
+                match fs::read_to_string(path) {
+                    Ok(contents) => Ok(Some(contents)),
+                    Err(ref why) if why.kind() == io::ErrorKind::NotFound => Ok(None),
+                    Err(why) => Err(why.into()),
+                }
+            }
+            #[cfg(feature = "zip")]
+            Source::Zip(zip) => {
+                use std::io::Read;
+
+                let mut zip = zip.lock().unwrap_or_else(PoisonError::into_inner);
+                let suffix = format!("data/{}", file);
+
+                //  Some archives nest everything in a top level directory
+                let name = zip.file_names().find(|name| *name == suffix || name.ends_with(&format!("/{}", suffix)))
+                              .map(String::from);
+
+                match name {
+                    Some(name) => {
+                        let mut contents = String::new();
+                        zip.by_name(&name)?.read_to_string(&mut contents)?;
+                        Ok(Some(contents))
+                    }
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+}
+
+/// Parses the contents of a `window.YTD` file, unwrapping each
+/// element from its `key`. Counts and ids that the archive stores as
+/// strings are converted to numbers first.
+pub fn parse<T: DeserializeOwned>(contents: &str, key: &str) -> Result<Vec<T>, Error> {
+    let json = strip_prefix(contents)?;
+    let items: Vec<Value> = serde_json::from_str(json)?;
+
+    items.into_iter()
+         .map(|mut item| {
+             let mut inner = match item.get_mut(key) {
+                 Some(inner) => inner.take(),
+                 None => item,
+             };
+
+             destringify(&mut inner);
+             serde_json::from_value(inner).map_err(Error::from)
+         })
+         .collect()
+}
+
+/// Removes the `window.YTD.<name>.part<n> =` assignment
+fn strip_prefix(contents: &str) -> Result<&str, Error> {
+    let contents = contents.trim_start_matches('\u{feff}').trim_start();
+
+    if !contents.starts_with("window.YTD.") {
+        return Err(Error::Format(String::from("missing window.YTD prefix")));
+    }
+
+    contents.find('=')
+            .map(|index| contents[index + 1..].trim().trim_end_matches(';'))
+            .ok_or_else(|| Error::Format(String::from("missing assignment")))
+}
+
+/// Keys whose values the archive stores as strings even though
+/// the API sends them as numbers
+const NUMERIC_KEYS: &[&str] = &[
+    "id",
+    "in_reply_to_status_id",
+    "in_reply_to_user_id",
+    "quoted_status_id",
+    "source_status_id",
+    "source_user_id",
+    "favorite_count",
+    "retweet_count",
+    "quote_count",
+    "reply_count",
+    "indices",
+    "display_text_range",
+    "w",
+    "h",
+    "aspect_ratio",
+    "duration_millis",
+    "bitrate",
+    "coordinates",
+];
+
+fn destringify(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if NUMERIC_KEYS.contains(&key.as_str()) {
+                    to_number(value);
+                } else if key != "place" {
+                    //  Places are stored as the API sends them and have hex ids
+                    destringify(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(destringify),
+        _ => {}
+    }
+}
+
+/// Converts strings holding numbers, including ones nested in arrays
+fn to_number(value: &mut Value) {
+    match value {
+        Value::String(s) => {
+            if let Ok(n) = s.parse::<u64>() {
+                *value = Value::from(n);
+            } else if let Ok(n) = s.parse::<f64>() {
+                *value = Value::from(n);
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(to_number),
+        Value::Object(_) => destringify(value),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const DIRECT_MESSAGES: &str = r#"window.YTD.direct_messages.part0 = [
+  {
+    "dmConversation" : {
+      "conversationId" : "12-34",
+      "messages" : [
+        {
+          "messageCreate" : {
+            "reactions" : [ ],
+            "urls" : [ {
+              "url" : "https://t.co/x",
+              "expanded" : "https://example.com",
+              "display" : "example.com"
+            } ],
+            "text" : "hi https://t.co/x",
+            "mediaUrls" : [ ],
+            "senderId" : "12",
+            "id" : "1001",
+            "recipientId" : "34",
+            "createdAt" : "2020-01-01T00:00:00.000Z"
+          }
+        },
+        {
+          "reactionCreate" : {
+            "senderId" : "34",
+            "reactionKey" : "like",
+            "eventId" : "1002",
+            "createdAt" : "2020-01-01T00:01:00.000Z"
+          }
+        }
+      ]
+    }
+  }
+]"#;
+
+    #[test]
+    fn prefix_is_stripped() {
+        assert_eq!(strip_prefix("\u{feff}window.YTD.tweets.part0 = [];\n").unwrap(), "[]");
+        assert_eq!(strip_prefix("window.YTD.like.part2 =[1]").unwrap(), "[1]");
+        assert!(matches!(strip_prefix("[]"), Err(Error::Format(_))));
+        assert!(matches!(strip_prefix("window.YTD.tweets.part0"), Err(Error::Format(_))));
+    }
+
+    #[test]
+    fn numbers_stored_as_strings_are_converted() {
+        let mut value = json!({
+            "id": "5",
+            "full_text": "123",
+            "favorite_count": "3",
+            "entities": {"hashtags": [{"text": "1", "indices": ["0", "2"]}]},
+            "coordinates": {"coordinates": ["-122.4", "37.8"]},
+            "place": {"id": "5a110d312052166f", "name": "10"}
+        });
+
+        destringify(&mut value);
+
+        assert_eq!(value, json!({
+            "id": 5,
+            "full_text": "123",
+            "favorite_count": 3,
+            "entities": {"hashtags": [{"text": "1", "indices": [0, 2]}]},
+            "coordinates": {"coordinates": [-122.4, 37.8]},
+            "place": {"id": "5a110d312052166f", "name": "10"}
+        }));
+    }
+
+    #[test]
+    fn direct_messages_keep_unknown_events() {
+        let conversations = parse::<DmConversation>(DIRECT_MESSAGES, "dmConversation").unwrap();
+        assert_eq!(conversations.len(), 1);
+
+        let conversation = &conversations[0];
+        assert_eq!(conversation.messages.len(), 2);
+        assert!(matches!(&conversation.messages[1], DmEvent::Other(other) if other.get("reactionCreate").is_some()));
+
+        let messages = conversation.direct_messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].id, 1001);
+        assert_eq!(messages[0].sender_id, 12);
+        assert_eq!(messages[0].recipient_id, Some(34));
+        assert_eq!(messages[0].entities.urls[0].indices, vec![3, 17]);
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn zip_archives_are_read_in_memory() {
+        use std::io::{Cursor, Write};
+        use zip::write::{SimpleFileOptions, ZipWriter};
+
+        let files = [
+            ("twitter-2020/data/like.js", r#"window.YTD.like.part0 = [{"like":{"tweetId":"1","fullText":"one"}}]"#),
+            ("twitter-2020/data/like-part1.js", r#"window.YTD.like.part1 = [{"like":{"tweetId":"2"}}]"#),
+            ("twitter-2020/data/direct-messages.js", DIRECT_MESSAGES),
+        ];
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in &files {
+            writer.start_file(*name, SimpleFileOptions::default()).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        let zip = writer.finish().unwrap();
+
+        let archive = Archive::from_zip(Cursor::new(zip.into_inner())).unwrap();
+
+        let likes = archive.likes().unwrap();
+        assert_eq!(likes.iter().map(|like| like.tweet_id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(likes[0].full_text.as_deref(), Some("one"));
+
+        assert_eq!(archive.direct_messages().unwrap().len(), 1);
+        assert!(archive.mutes().unwrap().is_empty());
+        assert!(matches!(archive.user(), Err(Error::Format(_))));
+    }
+}
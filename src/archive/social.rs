@@ -0,0 +1,28 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::util::id::{id_de, id_ser};
+
+/// Represents a liked tweet, from `like.js`
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Like {
+    /// Id of the liked tweet
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub tweet_id: u64,
+    /// Text of the liked tweet. Missing when the tweet was no longer available.
+    pub full_text: Option<String>,
+    /// Link to the liked tweet
+    pub expanded_url: Option<String>,
+}
+
+/// Represents another account in the social graph of the archive
+/// owner, from `follower.js`, `following.js`, `block.js` or `mute.js`
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Relationship {
+    /// Id of the other account
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub account_id: u64,
+    /// Link to the profile of the other account
+    pub user_link: Option<String>,
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::model::{Coordinates, Entity, ExtendedEntity, Place, Tweet, User};
+use crate::util::datetime::{datefmt_de, datefmt_ser};
+
+/// Represents a tweet from `tweets.js` in a personal data archive.
+///
+/// The archive only contains tweets of its owner, so there is no
+/// `user` object, and retweets are stored as plain `RT @name:` text
+/// without the retweeted tweet.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ArchiveTweet {
+    /// The unique id for the tweet
+    pub id: u64,
+    /// String version of `id`
+    pub id_str: String,
+    /// The full text of the tweet
+    #[serde(alias = "text")]
+    pub full_text: String,
+    /// When the tweet was posted
+    #[serde(deserialize_with="datefmt_de", serialize_with="datefmt_ser")]
+    pub created_at: DateTime<Utc>,
+    /// The client that posted the tweet
+    #[serde(default)]
+    pub source: String,
+    /// Whether or not the text field is truncated
+    #[serde(default)]
+    pub truncated: bool,
+    /// The part of `full_text` that is displayed, without leading mentions and trailing links
+    pub display_text_range: Option<(u32, u32)>,
+    /// If this tweet is a reply, this will contain the original tweet id
+    pub in_reply_to_status_id: Option<u64>,
+    /// Same as `in_reply_to_status_id`, but a String
+    pub in_reply_to_status_id_str: Option<String>,
+    /// If this tweet is a reply, this will contain the original author id
+    pub in_reply_to_user_id: Option<u64>,
+    /// Same as `in_reply_to_user_id`, but a String
+    pub in_reply_to_user_id_str: Option<String>,
+    /// If this tweet is a reply, contains the original user's screen name
+    pub in_reply_to_screen_name: Option<String>,
+    /// How many times this tweet had been retweeted when the archive was made
+    #[serde(default)]
+    pub retweet_count: u32,
+    /// How many times this tweet had been favorited when the archive was made
+    #[serde(default)]
+    pub favorite_count: u32,
+    /// Whether the owner favorited this tweet
+    #[serde(default)]
+    pub favorited: bool,
+    /// Whether the owner retweeted this tweet
+    #[serde(default)]
+    pub retweeted: bool,
+    /// Entities that have been parsed from the tweet
+    #[serde(default)]
+    pub entities: Entity,
+    /// If there are media entities, this field contains them all
+    pub extended_entities: Option<ExtendedEntity>,
+    /// Whether a link in this tweet (including media) is potentially sensitive
+    pub possibly_sensitive: Option<bool>,
+    /// BCP 47 language identifier corresponding to machine-detected language of tweet
+    pub lang: Option<String>,
+    /// Represents the geographic location of this tweet
+    pub coordinates: Option<Coordinates>,
+    /// The place that this tweet is associated with
+    pub place: Option<Place>,
+    /// Indicates what countries this tweet is unavailable
+    pub withheld_in_countries: Option<Vec<String>>,
+}
+
+impl ArchiveTweet {
+    /// Determine whether this is a retweet or not. The archive keeps
+    /// retweets as text only, so this checks for the `RT @` prefix.
+    pub fn is_retweet(&self) -> bool {
+        self.full_text.starts_with("RT @")
+    }
+
+    /// Converts this tweet into a `Tweet` posted by `user`
+    #[allow(deprecated)]
+    pub fn into_tweet(self, user: User) -> Tweet {
+        Tweet {
+            created_at: self.created_at,
+            id: self.id,
+            id_str: self.id_str,
+            text: self.full_text,
+            source: self.source,
+            truncated: self.truncated,
+            in_reply_to_status_id: self.in_reply_to_status_id,
+            in_reply_to_status_id_str: self.in_reply_to_status_id_str,
+            in_reply_to_user_id: self.in_reply_to_user_id,
+            in_reply_to_user_id_str: self.in_reply_to_user_id_str,
+            in_reply_to_screen_name: self.in_reply_to_screen_name,
+            user,
+            coordinates: self.coordinates,
+            place: self.place,
+            retweet_count: self.retweet_count,
+            favorite_count: Some(self.favorite_count),
+            entities: Some(self.entities),
+            extended_entities: self.extended_entities,
+            favorited: Some(self.favorited),
+            retweeted: self.retweeted,
+            possibly_sensitive: self.possibly_sensitive,
+            lang: self.lang,
+            withheld_in_countries: self.withheld_in_countries,
+            display_text_range: self.display_text_range,
+            ..Default::default()
+        }
+    }
+}
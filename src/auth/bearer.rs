@@ -0,0 +1,90 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::auth::{form_encode, percent_encode};
+
+/// Endpoint that issues app-only bearer tokens
+pub const BEARER_TOKEN_URL: &str = "https://api.twitter.com/oauth2/token";
+/// Endpoint that invalidates an app-only bearer token
+pub const INVALIDATE_TOKEN_URL: &str = "https://api.twitter.com/oauth2/invalidate_token";
+
+/// Content type of the token request bodies
+pub const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded;charset=UTF-8";
+
+impl FromStr for BearerToken {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+impl FromStr for InvalidatedToken {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// Requests an app-only bearer token with the consumer key and secret.
+///
+/// Send a `POST` to `BEARER_TOKEN_URL` with the `authorization` header,
+/// `FORM_CONTENT_TYPE` and `body`. The response parses into a `BearerToken`.
+#[derive(Clone, Debug)]
+pub struct BearerTokenRequest {
+    consumer_key: String,
+    consumer_secret: String,
+}
+
+/// The response of `oauth2/token`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BearerToken {
+    /// Always `bearer`
+    pub token_type: String,
+    /// The token to authorize app-only requests with
+    pub access_token: String,
+}
+
+/// The response of `oauth2/invalidate_token`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InvalidatedToken {
+    /// The token that is no longer valid
+    pub access_token: String,
+}
+
+impl BearerTokenRequest {
+    /// Creates a request for the app with these credentials
+    pub fn new(consumer_key: &str, consumer_secret: &str) -> Self {
+        BearerTokenRequest {
+            consumer_key: consumer_key.to_string(),
+            consumer_secret: consumer_secret.to_string(),
+        }
+    }
+
+    /// The `Authorization` header value, basic auth with the credentials
+    pub fn authorization(&self) -> String {
+        let credentials = format!("{}:{}", percent_encode(&self.consumer_key), percent_encode(&self.consumer_secret));
+        format!("Basic {}", STANDARD.encode(credentials))
+    }
+
+    /// The body to request a token with
+    pub fn body(&self) -> String {
+        form_encode(vec![("grant_type", "client_credentials")])
+    }
+
+    /// The body to invalidate `token` with, sent to `INVALIDATE_TOKEN_URL`
+    /// with the same `authorization` header
+    pub fn invalidate_body(&self, token: &str) -> String {
+        form_encode(vec![("access_token", token)])
+    }
+}
+
+impl BearerToken {
+    /// The `Authorization` header value for app-only requests
+    pub fn authorization(&self) -> String {
+        format!("Bearer {}", self.access_token)
+    }
+}
@@ -0,0 +1,118 @@
+//! Authentication for the REST and streaming APIs.
+//!
+//! `OAuth1` signs requests in the context of a user with HMAC-SHA1.
+//! App-only requests use a bearer token, requested with
+//! `BearerTokenRequest`. Requests to v2 endpoints in the context of a
+//! user can also use OAuth 2.0 tokens, which are obtained through the
+//! authorization code flow with PKCE in `pkce`.
+//!
+//! Like the rest of the crate, nothing here sends requests. The
+//! helpers build header values, URLs and form bodies, and parse the
+//! responses.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+mod bearer;
+mod oauth1;
+pub mod pkce;
+
+pub use bearer::{BearerToken, BearerTokenRequest, InvalidatedToken, BEARER_TOKEN_URL, FORM_CONTENT_TYPE, INVALIDATE_TOKEN_URL};
+pub use oauth1::{OAuth1, OAuthToken, ACCESS_TOKEN_URL, AUTHORIZE_URL, REQUEST_TOKEN_URL};
+
+/// Percent-encodes `value` as required by OAuth. Everything but
+/// letters, digits and `-._~` is encoded.
+pub fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+/// Decodes a percent-encoded form value. `+` stands for a space.
+/// Malformed escapes are kept as they are.
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let escaped = bytes.get(i + 1..i + 3)
+                                   .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                                   .and_then(|hex| std::str::from_utf8(hex).ok())
+                                   .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+                match escaped {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Builds an `application/x-www-form-urlencoded` body or query string
+pub fn form_encode<'a, I>(pairs: I) -> String
+    where I: IntoIterator<Item = (&'a str, &'a str)>
+{
+    pairs.into_iter()
+         .map(|(key, value)| format!("{}={}", percent_encode(key), percent_encode(value)))
+         .collect::<Vec<_>>()
+         .join("&")
+}
+
+/// Parses an `application/x-www-form-urlencoded` body or query string
+pub fn form_decode(body: &str) -> Vec<(String, String)> {
+    body.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.find('=') {
+            Some(at) => (percent_decode(&pair[..at]), percent_decode(&pair[at + 1..])),
+            None => (percent_decode(pair), String::new()),
+        })
+        .collect()
+}
+
+/// A random URL safe string made from `bytes` random bytes
+pub(crate) fn random_token(bytes: usize) -> String {
+    let mut buf = vec![0; bytes];
+    getrandom::getrandom(&mut buf).expect("the system random number generator is available");
+    URL_SAFE_NO_PAD.encode(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_encodes_reserved_characters() {
+        assert_eq!(percent_encode("Ladies + Gentlemen"), "Ladies%20%2B%20Gentlemen");
+        assert_eq!(percent_encode("An encoded string!"), "An%20encoded%20string%21");
+        assert_eq!(percent_encode("Dogs, Cats & Mice"), "Dogs%2C%20Cats%20%26%20Mice");
+        assert_eq!(percent_encode(":/?#[]@=*'()$"), "%3A%2F%3F%23%5B%5D%40%3D%2A%27%28%29%24");
+        assert_eq!(percent_encode("AZaz09-._~"), "AZaz09-._~");
+    }
+
+    #[test]
+    fn percent_encodes_utf8_bytes() {
+        assert_eq!(percent_encode("☃"), "%E2%98%83");
+        assert_eq!(percent_encode("é🐦"), "%C3%A9%F0%9F%90%A6");
+        assert_eq!(percent_decode("%E2%98%83+%C3%A9"), "☃ é");
+    }
+}
@@ -0,0 +1,304 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::auth::{form_decode, percent_encode, random_token};
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// Endpoint that starts the 3-legged flow with a request token
+pub const REQUEST_TOKEN_URL: &str = "https://api.twitter.com/oauth/request_token";
+/// Page the user authorizes a request token on
+pub const AUTHORIZE_URL: &str = "https://api.twitter.com/oauth/authorize";
+/// Endpoint that exchanges an authorized request token for an access token
+pub const ACCESS_TOKEN_URL: &str = "https://api.twitter.com/oauth/access_token";
+
+/// Signs requests with OAuth 1.0a and HMAC-SHA1.
+///
+/// A fresh nonce and the current time are used for each signature
+/// unless they are fixed with `nonce` and `timestamp`.
+#[derive(Clone, Debug)]
+pub struct OAuth1 {
+    consumer_key: String,
+    consumer_secret: String,
+    token: Option<String>,
+    token_secret: String,
+    callback: Option<String>,
+    verifier: Option<String>,
+    nonce: Option<String>,
+    timestamp: Option<u64>,
+}
+
+/// The token returned by `oauth/request_token` and `oauth/access_token`
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OAuthToken {
+    /// The token
+    pub token: String,
+    /// The secret to sign requests with along with the token
+    pub secret: String,
+    /// Whether the callback was accepted, for request tokens
+    pub callback_confirmed: bool,
+    /// The user the token belongs to, for access tokens
+    pub user_id: Option<u64>,
+    /// The screen name of the user, for access tokens
+    pub screen_name: Option<String>,
+}
+
+impl OAuth1 {
+    /// Signs requests in the context of the app only, for example
+    /// to get a request token
+    pub fn new(consumer_key: &str, consumer_secret: &str) -> Self {
+        OAuth1 {
+            consumer_key: consumer_key.to_string(),
+            consumer_secret: consumer_secret.to_string(),
+            token: None,
+            token_secret: String::new(),
+            callback: None,
+            verifier: None,
+            nonce: None,
+            timestamp: None,
+        }
+    }
+
+    /// Signs requests in the context of the user the token belongs to
+    pub fn token(mut self, token: &str, token_secret: &str) -> Self {
+        self.token = Some(token.to_string());
+        self.token_secret = token_secret.to_string();
+        self
+    }
+
+    /// Sets `oauth_callback`, for `oauth/request_token`
+    pub fn callback(mut self, callback: &str) -> Self {
+        self.callback = Some(callback.to_string());
+        self
+    }
+
+    /// Sets `oauth_verifier`, for `oauth/access_token`
+    pub fn verifier(mut self, verifier: &str) -> Self {
+        self.verifier = Some(verifier.to_string());
+        self
+    }
+
+    /// Uses a fixed nonce instead of a random one
+    pub fn nonce(mut self, nonce: &str) -> Self {
+        self.nonce = Some(nonce.to_string());
+        self
+    }
+
+    /// Uses a fixed timestamp, in seconds since the epoch, instead of
+    /// the current time
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Builds the `Authorization` header value for a request.
+    ///
+    /// `params` are the query and form body parameters, unencoded.
+    /// Parameters in the query of `url` are included as well. JSON
+    /// bodies are not signed.
+    pub fn authorization(&self, method: &str, url: &str, params: &[(&str, &str)]) -> String {
+        let mut oauth = self.oauth_params();
+        let base = self.base_string(method, url, params, &oauth);
+        oauth.push(("oauth_signature", self.sign(&base)));
+        oauth.sort();
+
+        let fields = oauth.iter()
+                          .map(|(key, value)| format!("{}=\"{}\"", key, percent_encode(value)))
+                          .collect::<Vec<_>>();
+
+        format!("OAuth {}", fields.join(", "))
+    }
+
+    /// The signature base string of a request. Only reproducible when
+    /// the nonce and timestamp are fixed.
+    pub fn signature_base_string(&self, method: &str, url: &str, params: &[(&str, &str)]) -> String {
+        self.base_string(method, url, params, &self.oauth_params())
+    }
+
+    /// The base64 signature of a request. Only reproducible when the
+    /// nonce and timestamp are fixed.
+    pub fn signature(&self, method: &str, url: &str, params: &[(&str, &str)]) -> String {
+        self.sign(&self.signature_base_string(method, url, params))
+    }
+
+    fn oauth_params(&self) -> Vec<(&'static str, String)> {
+        let nonce = self.nonce.clone().unwrap_or_else(|| random_token(32).replace(['-', '_'], ""));
+        let timestamp = self.timestamp.unwrap_or_else(|| {
+            SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or_default()
+        });
+
+        let mut params = vec![
+            ("oauth_consumer_key", self.consumer_key.clone()),
+            ("oauth_nonce", nonce),
+            ("oauth_signature_method", "HMAC-SHA1".to_string()),
+            ("oauth_timestamp", timestamp.to_string()),
+            ("oauth_version", "1.0".to_string()),
+        ];
+
+        if let Some(token) = &self.token {
+            params.push(("oauth_token", token.clone()));
+        }
+
+        if let Some(callback) = &self.callback {
+            params.push(("oauth_callback", callback.clone()));
+        }
+
+        if let Some(verifier) = &self.verifier {
+            params.push(("oauth_verifier", verifier.clone()));
+        }
+
+        params
+    }
+
+    fn base_string(&self, method: &str, url: &str, params: &[(&str, &str)], oauth: &[(&'static str, String)]) -> String {
+        let (base_url, query) = split_url(url);
+
+        let mut encoded = params.iter()
+                                .map(|(key, value)| (percent_encode(key), percent_encode(value)))
+                                .chain(query.iter().map(|(key, value)| (percent_encode(key), percent_encode(value))))
+                                .chain(oauth.iter().map(|(key, value)| (key.to_string(), percent_encode(value))))
+                                .collect::<Vec<_>>();
+        encoded.sort();
+
+        let joined = encoded.iter()
+                            .map(|(key, value)| format!("{}={}", key, value))
+                            .collect::<Vec<_>>()
+                            .join("&");
+
+        format!("{}&{}&{}",
+                method.to_ascii_uppercase(),
+                percent_encode(&base_url),
+                percent_encode(&joined))
+    }
+
+    fn sign(&self, base: &str) -> String {
+        let key = format!("{}&{}", percent_encode(&self.consumer_secret), percent_encode(&self.token_secret));
+        let mut mac = HmacSha1::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any size");
+        mac.update(base.as_bytes());
+        STANDARD.encode(mac.finalize().into_bytes())
+    }
+}
+
+impl OAuthToken {
+    /// Parses the form encoded body of a token response. `None` if the
+    /// token or secret is missing.
+    pub fn from_form(body: &str) -> Option<Self> {
+        let mut token = OAuthToken::default();
+        let mut has_token = false;
+        let mut has_secret = false;
+
+        for (key, value) in form_decode(body.trim()) {
+            match key.as_str() {
+                "oauth_token" => {
+                    token.token = value;
+                    has_token = true;
+                }
+                "oauth_token_secret" => {
+                    token.secret = value;
+                    has_secret = true;
+                }
+                "oauth_callback_confirmed" => token.callback_confirmed = value == "true",
+                "user_id" => token.user_id = value.parse().ok(),
+                "screen_name" => token.screen_name = Some(value),
+                _ => {}
+            }
+        }
+
+        if has_token && has_secret {
+            Some(token)
+        } else {
+            None
+        }
+    }
+
+    /// The page to send the user to for authorizing a request token
+    pub fn authorize_url(&self) -> String {
+        format!("{}?oauth_token={}", AUTHORIZE_URL, percent_encode(&self.token))
+    }
+}
+
+/// Splits a URL into the base URL of the signature and its query.
+/// The scheme and host are lowercased and default ports removed.
+fn split_url(url: &str) -> (String, Vec<(String, String)>) {
+    let url = url.split('#').next().unwrap_or_default();
+    let (base, query) = match url.find('?') {
+        Some(at) => (&url[..at], form_decode(&url[at + 1..])),
+        None => (url, Vec::new()),
+    };
+
+    let (scheme, rest) = match base.find("://") {
+        Some(at) => (base[..at].to_ascii_lowercase(), &base[at + 3..]),
+        None => return (base.to_string(), query),
+    };
+
+    let (authority, path) = match rest.find('/') {
+        Some(at) => (&rest[..at], &rest[at..]),
+        None => (rest, "/"),
+    };
+
+    let mut authority = authority.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "http" => Some(":80"),
+        "https" => Some(":443"),
+        _ => None,
+    };
+
+    if let Some(port) = default_port {
+        if authority.ends_with(port) {
+            authority.truncate(authority.len() - port.len());
+        }
+    }
+
+    (format!("{}://{}{}", scheme, authority, path), query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example from Twitter's "Creating a signature" documentation
+    fn example() -> OAuth1 {
+        OAuth1::new("xvz1evFS4wEEPTGEFPHBog", "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw")
+            .token("370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb", "LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE")
+            .nonce("kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg")
+            .timestamp(1_318_622_958)
+    }
+
+    const URL: &str = "https://api.twitter.com/1.1/statuses/update.json?include_entities=true";
+    const STATUS: (&str, &str) = ("status", "Hello Ladies + Gentlemen, a signed OAuth request!");
+
+    #[test]
+    fn signs_documented_example() {
+        let oauth = example();
+
+        assert_eq!(
+            oauth.signature_base_string("POST", URL, &[STATUS]),
+            "POST&https%3A%2F%2Fapi.twitter.com%2F1.1%2Fstatuses%2Fupdate.json&include_entities%3Dtrue\
+             %26oauth_consumer_key%3Dxvz1evFS4wEEPTGEFPHBog%26oauth_nonce%3DkYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg\
+             %26oauth_signature_method%3DHMAC-SHA1%26oauth_timestamp%3D1318622958\
+             %26oauth_token%3D370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb%26oauth_version%3D1.0\
+             %26status%3DHello%2520Ladies%2520%252B%2520Gentlemen%252C%2520a%2520signed%2520OAuth%2520request%2521"
+        );
+        assert_eq!(oauth.signature("POST", URL, &[STATUS]), "hCtSmYh+iHYCEqBWrE7C7hYmtUk=");
+        assert!(oauth
+            .authorization("POST", URL, &[STATUS])
+            .contains("oauth_signature=\"hCtSmYh%2BiHYCEqBWrE7C7hYmtUk%3D\""));
+    }
+
+    #[test]
+    fn split_url_normalizes_base() {
+        assert_eq!(
+            split_url("HTTPS://API.Twitter.com:443/1.1/statuses/show.json?id=1&trim_user=true#top"),
+            (
+                "https://api.twitter.com/1.1/statuses/show.json".to_string(),
+                vec![("id".to_string(), "1".to_string()), ("trim_user".to_string(), "true".to_string())],
+            )
+        );
+        assert_eq!(split_url("http://example.com:80").0, "http://example.com/");
+        assert_eq!(split_url("http://example.com:8080/path").0, "http://example.com:8080/path");
+        assert_eq!(split_url("https://example.com:80/").0, "https://example.com:80/");
+    }
+}
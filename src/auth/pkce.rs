@@ -0,0 +1,276 @@
+//! OAuth 2.0 authorization code flow with PKCE, for v2 endpoints in
+//! the context of a user.
+//!
+//! Send the user to `AuthorizationRequest::url`. Twitter redirects back
+//! with a `code` and the `state`, which are exchanged for a `UserToken`
+//! by posting `TokenRequest::body` to `TOKEN_URL`. Confidential clients
+//! also send basic auth with their client id and secret.
+
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::auth::{form_encode, percent_encode, random_token};
+
+/// Page the user authorizes the app on
+pub const AUTHORIZE_URL: &str = "https://twitter.com/i/oauth2/authorize";
+/// Endpoint that issues and refreshes user tokens
+pub const TOKEN_URL: &str = "https://api.twitter.com/2/oauth2/token";
+/// Endpoint that revokes user tokens
+pub const REVOKE_URL: &str = "https://api.twitter.com/2/oauth2/revoke";
+
+impl FromStr for UserToken {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+impl FromStr for OAuth2Error {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// A code verifier and its S256 challenge
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PkceChallenge {
+    verifier: String,
+    challenge: String,
+}
+
+/// The page the user is sent to for authorizing the app
+#[derive(Clone, Debug)]
+pub struct AuthorizationRequest {
+    /// The client id of the app
+    pub client_id: String,
+    /// Where Twitter redirects to, as registered for the app
+    pub redirect_uri: String,
+    /// The requested scopes, like `tweet.read` or `offline.access`
+    pub scopes: Vec<String>,
+    /// Value Twitter sends back to the redirect, to prevent CSRF
+    pub state: String,
+    /// The challenge of the verifier that is sent with the token request
+    pub code_challenge: String,
+}
+
+/// A body to post to `TOKEN_URL`
+#[derive(Clone, Debug)]
+pub enum TokenRequest {
+    /// Exchanges the code from the redirect for a token
+    AuthorizationCode {
+        /// The client id of the app
+        client_id: String,
+        /// The `code` of the redirect
+        code: String,
+        /// The redirect the code was sent to
+        redirect_uri: String,
+        /// The verifier of the challenge in the authorization request
+        code_verifier: String,
+    },
+    /// Gets a new token with the refresh token of an earlier one
+    RefreshToken {
+        /// The client id of the app
+        client_id: String,
+        /// The refresh token
+        refresh_token: String,
+    },
+}
+
+/// The response of `TOKEN_URL`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UserToken {
+    /// Always `bearer`
+    pub token_type: String,
+    /// How many seconds the token is valid for
+    pub expires_in: Option<u64>,
+    /// The token to authorize requests with
+    pub access_token: String,
+    /// The granted scopes, separated by spaces
+    pub scope: Option<String>,
+    /// Token to get a new one with, if `offline.access` was granted
+    pub refresh_token: Option<String>,
+}
+
+/// The body of a failed token request
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OAuth2Error {
+    /// The kind of error, like `invalid_request`
+    pub error: String,
+    /// Explanation of the error
+    pub error_description: Option<String>,
+}
+
+impl PkceChallenge {
+    /// Creates a random verifier
+    pub fn new() -> Self {
+        Self::from_verifier(&random_token(32))
+    }
+
+    /// Uses a known verifier, for example one that was stored between
+    /// the authorization and the token request
+    pub fn from_verifier(verifier: &str) -> Self {
+        PkceChallenge {
+            verifier: verifier.to_string(),
+            challenge: URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())),
+        }
+    }
+
+    /// The verifier, sent with the token request
+    pub fn verifier(&self) -> &str {
+        &self.verifier
+    }
+
+    /// The challenge, sent with the authorization request
+    pub fn challenge(&self) -> &str {
+        &self.challenge
+    }
+}
+
+impl Default for PkceChallenge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuthorizationRequest {
+    /// Creates a request with a random state
+    pub fn new(client_id: &str, redirect_uri: &str, challenge: &PkceChallenge) -> Self {
+        AuthorizationRequest {
+            client_id: client_id.to_string(),
+            redirect_uri: redirect_uri.to_string(),
+            scopes: Vec::new(),
+            state: random_token(16),
+            code_challenge: challenge.challenge().to_string(),
+        }
+    }
+
+    /// Adds a scope
+    pub fn scope(mut self, scope: &str) -> Self {
+        self.scopes.push(scope.to_string());
+        self
+    }
+
+    /// Uses a known state instead of a random one
+    pub fn state(mut self, state: &str) -> Self {
+        self.state = state.to_string();
+        self
+    }
+
+    /// The URL to send the user to
+    pub fn url(&self) -> String {
+        let scopes = self.scopes.join(" ");
+
+        format!("{}?{}", AUTHORIZE_URL, form_encode(vec![
+            ("response_type", "code"),
+            ("client_id", self.client_id.as_str()),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("scope", scopes.as_str()),
+            ("state", self.state.as_str()),
+            ("code_challenge", self.code_challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ]))
+    }
+}
+
+impl TokenRequest {
+    /// Exchanges the code of a redirect for a token
+    pub fn authorization_code(client_id: &str, code: &str, redirect_uri: &str, challenge: &PkceChallenge) -> Self {
+        TokenRequest::AuthorizationCode {
+            client_id: client_id.to_string(),
+            code: code.to_string(),
+            redirect_uri: redirect_uri.to_string(),
+            code_verifier: challenge.verifier().to_string(),
+        }
+    }
+
+    /// Gets a new token with a refresh token
+    pub fn refresh(client_id: &str, refresh_token: &str) -> Self {
+        TokenRequest::RefreshToken {
+            client_id: client_id.to_string(),
+            refresh_token: refresh_token.to_string(),
+        }
+    }
+
+    /// The form encoded body
+    pub fn body(&self) -> String {
+        match self {
+            TokenRequest::AuthorizationCode { client_id, code, redirect_uri, code_verifier } => form_encode(vec![
+                ("grant_type", "authorization_code"),
+                ("client_id", client_id.as_str()),
+                ("code", code.as_str()),
+                ("redirect_uri", redirect_uri.as_str()),
+                ("code_verifier", code_verifier.as_str()),
+            ]),
+            TokenRequest::RefreshToken { client_id, refresh_token } => form_encode(vec![
+                ("grant_type", "refresh_token"),
+                ("client_id", client_id.as_str()),
+                ("refresh_token", refresh_token.as_str()),
+            ]),
+        }
+    }
+}
+
+impl UserToken {
+    /// The granted scopes
+    pub fn scopes(&self) -> Vec<&str> {
+        self.scope.as_deref().map(|scope| scope.split_whitespace().collect()).unwrap_or_default()
+    }
+
+    /// The `Authorization` header value for requests as the user
+    pub fn authorization(&self) -> String {
+        format!("Bearer {}", self.access_token)
+    }
+}
+
+/// The `Authorization` header value of confidential clients
+pub fn client_authorization(client_id: &str, client_secret: &str) -> String {
+    let credentials = format!("{}:{}", percent_encode(client_id), percent_encode(client_secret));
+    format!("Basic {}", STANDARD.encode(credentials))
+}
+
+/// The body to post to `REVOKE_URL` to revoke a token
+pub fn revoke_body(client_id: &str, token: &str) -> String {
+    form_encode(vec![
+        ("token", token),
+        ("token_type_hint", "access_token"),
+        ("client_id", client_id),
+    ])
+}
+
+impl fmt::Display for OAuth2Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.error_description {
+            Some(description) => write!(f, "{}: {}", self.error, description),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+impl error::Error for OAuth2Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn s256_challenge_matches_rfc_7636() {
+        //  RFC 7636, Appendix B
+        let pkce = PkceChallenge::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk");
+        assert_eq!(pkce.challenge(), "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+    }
+
+    #[test]
+    fn random_verifiers_differ() {
+        let (a, b) = (PkceChallenge::new(), PkceChallenge::new());
+        assert_ne!(a.verifier(), b.verifier());
+        assert_eq!(a.verifier().len(), 43);
+    }
+}
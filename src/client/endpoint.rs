@@ -0,0 +1,533 @@
+use serde::de::DeserializeOwned;
+
+use crate::client::Method;
+use crate::model::v2;
+use crate::model::{SearchResults, Tweet, User};
+
+/// Describes a call to one endpoint and what it returns
+pub trait Endpoint {
+    /// The parsed body of a successful response
+    type Output: DeserializeOwned;
+
+    /// The HTTP method
+    fn method(&self) -> Method {
+        Method::Get
+    }
+
+    /// Path relative to the base URL, like `1.1/statuses/show.json`
+    fn path(&self) -> String;
+
+    /// The parameters, unencoded
+    fn params(&self) -> Vec<(&'static str, String)>;
+
+    /// Name the rate limit of the endpoint is tracked under, the
+    /// path with placeholders for ids like `/statuses/show/:id`
+    fn rate_limit_key(&self) -> &'static str;
+}
+
+/// A user given by id or by screen name
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UserRef {
+    /// The id of the user
+    Id(u64),
+    /// The screen name, without `@`
+    ScreenName(String),
+}
+
+impl UserRef {
+    fn param(&self) -> (&'static str, String) {
+        match self {
+            UserRef::Id(id) => ("user_id", id.to_string()),
+            UserRef::ScreenName(name) => ("screen_name", name.clone()),
+        }
+    }
+}
+
+impl From<u64> for UserRef {
+    fn from(id: u64) -> Self {
+        UserRef::Id(id)
+    }
+}
+
+impl From<&str> for UserRef {
+    fn from(screen_name: &str) -> Self {
+        UserRef::ScreenName(screen_name.trim_start_matches('@').to_string())
+    }
+}
+
+/// `statuses/show`, a single tweet by id
+#[derive(Clone, Debug)]
+pub struct ShowTweet {
+    /// The id of the tweet
+    pub id: u64,
+}
+
+impl Endpoint for ShowTweet {
+    type Output = Tweet;
+
+    fn path(&self) -> String {
+        "1.1/statuses/show.json".to_string()
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("id", self.id.to_string()),
+            ("tweet_mode", "extended".to_string()),
+        ]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        "/statuses/show/:id"
+    }
+}
+
+/// `statuses/lookup`, up to 100 tweets by id. Tweets that could not
+/// be found are left out.
+#[derive(Clone, Debug)]
+pub struct LookupTweets {
+    /// The ids of the tweets
+    pub ids: Vec<u64>,
+}
+
+impl Endpoint for LookupTweets {
+    type Output = Vec<Tweet>;
+
+    fn path(&self) -> String {
+        "1.1/statuses/lookup.json".to_string()
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("id", join(&self.ids)),
+            ("tweet_mode", "extended".to_string()),
+        ]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        "/statuses/lookup"
+    }
+}
+
+/// `users/show`, a single user
+#[derive(Clone, Debug)]
+pub struct ShowUser {
+    /// The user
+    pub user: UserRef,
+}
+
+impl Endpoint for ShowUser {
+    type Output = User;
+
+    fn path(&self) -> String {
+        "1.1/users/show.json".to_string()
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![self.user.param()]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        "/users/show/:id"
+    }
+}
+
+/// `statuses/user_timeline`, `statuses/home_timeline` or
+/// `statuses/mentions_timeline`, newest tweets first.
+/// Use with `MaxIdPages` to go further back.
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    user: Option<UserRef>,
+    mentions: bool,
+    count: Option<u32>,
+    since_id: Option<u64>,
+    max_id: Option<u64>,
+    include_rts: Option<bool>,
+    exclude_replies: Option<bool>,
+}
+
+impl Timeline {
+    /// Tweets posted by a user
+    pub fn user<U: Into<UserRef>>(user: U) -> Self {
+        Timeline {
+            user: Some(user.into()),
+            ..Self::home()
+        }
+    }
+
+    /// Tweets of the authenticating user and the users they follow
+    pub fn home() -> Self {
+        Timeline {
+            user: None,
+            mentions: false,
+            count: None,
+            since_id: None,
+            max_id: None,
+            include_rts: None,
+            exclude_replies: None,
+        }
+    }
+
+    /// Tweets mentioning the authenticating user
+    pub fn mentions() -> Self {
+        Timeline {
+            mentions: true,
+            ..Self::home()
+        }
+    }
+
+    /// How many tweets to return, at most 200
+    pub fn count(mut self, count: u32) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Only returns tweets with greater ids
+    pub fn since_id(mut self, since_id: Option<u64>) -> Self {
+        self.since_id = since_id;
+        self
+    }
+
+    /// Only returns tweets with ids up to and including this one
+    pub fn max_id(mut self, max_id: Option<u64>) -> Self {
+        self.max_id = max_id;
+        self
+    }
+
+    /// Whether retweets are included in user timelines
+    pub fn include_rts(mut self, include_rts: bool) -> Self {
+        self.include_rts = Some(include_rts);
+        self
+    }
+
+    /// Whether replies are left out of user and home timelines
+    pub fn exclude_replies(mut self, exclude_replies: bool) -> Self {
+        self.exclude_replies = Some(exclude_replies);
+        self
+    }
+}
+
+impl Endpoint for Timeline {
+    type Output = Vec<Tweet>;
+
+    fn path(&self) -> String {
+        match (&self.user, self.mentions) {
+            (Some(_), _) => "1.1/statuses/user_timeline.json",
+            (None, true) => "1.1/statuses/mentions_timeline.json",
+            (None, false) => "1.1/statuses/home_timeline.json",
+        }.to_string()
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+
+        if let Some(user) = &self.user {
+            params.push(user.param());
+        }
+
+        push(&mut params, "count", self.count);
+        push(&mut params, "since_id", self.since_id);
+        push(&mut params, "max_id", self.max_id);
+        push(&mut params, "include_rts", self.include_rts);
+        push(&mut params, "exclude_replies", self.exclude_replies);
+        params.push(("tweet_mode", "extended".to_string()));
+        params
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        match (&self.user, self.mentions) {
+            (Some(_), _) => "/statuses/user_timeline",
+            (None, true) => "/statuses/mentions_timeline",
+            (None, false) => "/statuses/home_timeline",
+        }
+    }
+}
+
+/// `search/tweets`, recent tweets matching a query, newest first.
+/// Use with `MaxIdPages` to go further back.
+#[derive(Clone, Debug)]
+pub struct SearchTweets {
+    query: String,
+    count: Option<u32>,
+    since_id: Option<u64>,
+    max_id: Option<u64>,
+    result_type: Option<String>,
+    lang: Option<String>,
+    until: Option<String>,
+}
+
+impl SearchTweets {
+    /// Searches for `query`
+    pub fn new(query: &str) -> Self {
+        SearchTweets {
+            query: query.to_string(),
+            count: None,
+            since_id: None,
+            max_id: None,
+            result_type: None,
+            lang: None,
+            until: None,
+        }
+    }
+
+    /// How many tweets to return, at most 100
+    pub fn count(mut self, count: u32) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Only returns tweets with greater ids
+    pub fn since_id(mut self, since_id: Option<u64>) -> Self {
+        self.since_id = since_id;
+        self
+    }
+
+    /// Only returns tweets with ids up to and including this one
+    pub fn max_id(mut self, max_id: Option<u64>) -> Self {
+        self.max_id = max_id;
+        self
+    }
+
+    /// `mixed`, `recent` or `popular`
+    pub fn result_type(mut self, result_type: &str) -> Self {
+        self.result_type = Some(result_type.to_string());
+        self
+    }
+
+    /// Only returns tweets in this language
+    pub fn lang(mut self, lang: &str) -> Self {
+        self.lang = Some(lang.to_string());
+        self
+    }
+
+    /// Only returns tweets from before this date, formatted as `YYYY-MM-DD`
+    pub fn until(mut self, until: &str) -> Self {
+        self.until = Some(until.to_string());
+        self
+    }
+}
+
+impl Endpoint for SearchTweets {
+    type Output = SearchResults;
+
+    fn path(&self) -> String {
+        "1.1/search/tweets.json".to_string()
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![("q", self.query.clone())];
+        push(&mut params, "count", self.count);
+        push(&mut params, "since_id", self.since_id);
+        push(&mut params, "max_id", self.max_id);
+        push(&mut params, "result_type", self.result_type.as_ref());
+        push(&mut params, "lang", self.lang.as_ref());
+        push(&mut params, "until", self.until.as_ref());
+        params.push(("tweet_mode", "extended".to_string()));
+        params
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        "/search/tweets"
+    }
+}
+
+/// The expansions and fields requested from a v2 endpoint
+#[derive(Clone, Debug, Default)]
+pub struct Fields {
+    /// Referenced objects to include, like `author_id`
+    pub expansions: Vec<String>,
+    /// Tweet fields, like `created_at`
+    pub tweet_fields: Vec<String>,
+    /// User fields, like `public_metrics`
+    pub user_fields: Vec<String>,
+    /// Media fields, like `variants`
+    pub media_fields: Vec<String>,
+    /// Place fields, like `geo`
+    pub place_fields: Vec<String>,
+}
+
+impl Fields {
+    /// Adds an expansion
+    pub fn expansion(mut self, expansion: &str) -> Self {
+        self.expansions.push(expansion.to_string());
+        self
+    }
+
+    /// Adds a tweet field
+    pub fn tweet_field(mut self, field: &str) -> Self {
+        self.tweet_fields.push(field.to_string());
+        self
+    }
+
+    /// Adds a user field
+    pub fn user_field(mut self, field: &str) -> Self {
+        self.user_fields.push(field.to_string());
+        self
+    }
+
+    /// Adds a media field
+    pub fn media_field(mut self, field: &str) -> Self {
+        self.media_fields.push(field.to_string());
+        self
+    }
+
+    /// Adds a place field
+    pub fn place_field(mut self, field: &str) -> Self {
+        self.place_fields.push(field.to_string());
+        self
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let lists = [
+            ("expansions", &self.expansions),
+            ("tweet.fields", &self.tweet_fields),
+            ("user.fields", &self.user_fields),
+            ("media.fields", &self.media_fields),
+            ("place.fields", &self.place_fields),
+        ];
+
+        lists.iter()
+             .filter(|(_, values)| !values.is_empty())
+             .map(|(key, values)| (*key, values.join(",")))
+             .collect()
+    }
+}
+
+/// `GET /2/tweets/:id`
+#[derive(Clone, Debug)]
+pub struct TweetById {
+    /// The id of the tweet
+    pub id: u64,
+    /// The requested expansions and fields
+    pub fields: Fields,
+}
+
+impl Endpoint for TweetById {
+    type Output = v2::Response<v2::Tweet>;
+
+    fn path(&self) -> String {
+        format!("2/tweets/{}", self.id)
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        self.fields.params()
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        "/2/tweets/:id"
+    }
+}
+
+/// `GET /2/tweets`, up to 100 tweets by id. Missing tweets are
+/// explained in `errors`.
+#[derive(Clone, Debug)]
+pub struct TweetsByIds {
+    /// The ids of the tweets
+    pub ids: Vec<u64>,
+    /// The requested expansions and fields
+    pub fields: Fields,
+}
+
+impl Endpoint for TweetsByIds {
+    type Output = v2::Response<Vec<v2::Tweet>>;
+
+    fn path(&self) -> String {
+        "2/tweets".to_string()
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![("ids", join(&self.ids))];
+        params.extend(self.fields.params());
+        params
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        "/2/tweets"
+    }
+}
+
+/// `GET /2/users/:id`
+#[derive(Clone, Debug)]
+pub struct UserById {
+    /// The id of the user
+    pub id: u64,
+    /// The requested expansions and fields
+    pub fields: Fields,
+}
+
+impl Endpoint for UserById {
+    type Output = v2::Response<v2::User>;
+
+    fn path(&self) -> String {
+        format!("2/users/{}", self.id)
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        self.fields.params()
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        "/2/users/:id"
+    }
+}
+
+/// `GET /2/users`, up to 100 users by id
+#[derive(Clone, Debug)]
+pub struct UsersByIds {
+    /// The ids of the users
+    pub ids: Vec<u64>,
+    /// The requested expansions and fields
+    pub fields: Fields,
+}
+
+impl Endpoint for UsersByIds {
+    type Output = v2::Response<Vec<v2::User>>;
+
+    fn path(&self) -> String {
+        "2/users".to_string()
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![("ids", join(&self.ids))];
+        params.extend(self.fields.params());
+        params
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        "/2/users"
+    }
+}
+
+/// `GET /2/users/by/username/:username`
+#[derive(Clone, Debug)]
+pub struct UserByUsername {
+    /// The username, without `@`
+    pub username: String,
+    /// The requested expansions and fields
+    pub fields: Fields,
+}
+
+impl Endpoint for UserByUsername {
+    type Output = v2::Response<v2::User>;
+
+    fn path(&self) -> String {
+        format!("2/users/by/username/{}", self.username.trim_start_matches('@'))
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        self.fields.params()
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        "/2/users/by/username/:username"
+    }
+}
+
+fn join(ids: &[u64]) -> String {
+    ids.iter().map(u64::to_string).collect::<Vec<_>>().join(",")
+}
+
+fn push<T: ToString>(params: &mut Vec<(&'static str, String)>, key: &'static str, value: Option<T>) {
+    if let Some(value) = value {
+        params.push((key, value.to_string()));
+    }
+}
@@ -0,0 +1,387 @@
+//! A thin typed client for the REST API.
+//!
+//! The crate does not ship an HTTP client. Requests are sent through
+//! an `HttpTransport`, which can be implemented over any HTTP library,
+//! or as a closure returning canned responses in tests. `Client` signs
+//! each request, sends it, records the rate limit headers and parses
+//! the body into the `Output` of the `Endpoint`.
+
+mod endpoint;
+
+pub use endpoint::{
+    Endpoint,
+    Fields,
+    LookupTweets,
+    SearchTweets,
+    ShowTweet,
+    ShowUser,
+    Timeline,
+    TweetById,
+    TweetsByIds,
+    UserById,
+    UserByUsername,
+    UserRef,
+    UsersByIds,
+};
+
+use std::error;
+use std::fmt;
+use std::time::Duration;
+
+use crate::auth::{form_encode, OAuth1, FORM_CONTENT_TYPE};
+use crate::model::TwitterError;
+use crate::rate_limit::RateLimitTracker;
+
+/// Where requests are sent unless configured otherwise
+pub const DEFAULT_BASE_URL: &str = "https://api.twitter.com";
+
+/// How long to wait after HTTP 429 when the response does not tell,
+/// one rate limit window
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+/// HTTP method of a request
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Method {
+    /// `GET`
+    Get,
+    /// `POST`
+    Post,
+}
+
+/// A request for the transport to send
+#[derive(Clone, Debug)]
+pub struct Request {
+    /// The method
+    pub method: Method,
+    /// The full URL, including the query
+    pub url: String,
+    /// Headers to send, including `Authorization`
+    pub headers: Vec<(String, String)>,
+    /// The form encoded body of `POST` requests
+    pub body: Option<String>,
+}
+
+/// A response returned by the transport
+#[derive(Clone, Debug, Default)]
+pub struct Response {
+    /// The status code
+    pub status: u16,
+    /// Every header of the response
+    pub headers: Vec<(String, String)>,
+    /// The body
+    pub body: String,
+}
+
+/// Sends requests over HTTP
+pub trait HttpTransport {
+    /// Error returned when a request could not be sent or the
+    /// response could not be read
+    type Error;
+
+    /// Sends a request and returns the response, whatever its status
+    fn send(&mut self, request: &Request) -> Result<Response, Self::Error>;
+}
+
+impl<E, F> HttpTransport for F
+    where F: FnMut(&Request) -> Result<Response, E>
+{
+    type Error = E;
+
+    fn send(&mut self, request: &Request) -> Result<Response, E> {
+        self(request)
+    }
+}
+
+/// How requests are authorized
+#[derive(Clone, Debug)]
+pub enum Auth {
+    /// Requests are not authorized, for example against a mock server
+    None,
+    /// Requests are signed in the context of a user
+    OAuth1(OAuth1),
+    /// Requests carry an app-only bearer token or an OAuth 2.0 user token
+    Bearer(String),
+}
+
+/// Errors that can occur while calling an endpoint
+#[derive(Debug)]
+pub enum Error<E> {
+    /// The transport failed
+    Transport(E),
+    /// The API answered with an error body
+    Api {
+        /// The status code
+        status: u16,
+        /// The parsed error
+        error: Box<TwitterError>,
+    },
+    /// The API answered with an error status and a body that is not
+    /// an API error
+    Http {
+        /// The status code
+        status: u16,
+        /// The body
+        body: String,
+    },
+    /// The rate limit of the endpoint is used up. The request was not
+    /// sent, or was rejected with HTTP 429.
+    RateLimited(Duration),
+    /// The body of a successful response could not be parsed
+    Json(serde_json::Error),
+}
+
+impl<E> Error<E> {
+    /// Whether the same request may succeed if it is sent again later
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Transport(_) | Error::RateLimited(_) => true,
+            Error::Api { status, error } => *status >= 500 || error.is_retryable(),
+            Error::Http { status, .. } => *status >= 500,
+            Error::Json(_) => false,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Transport(why) => write!(f, "transport error: {}", why),
+            Error::Api { status, error } => write!(f, "api error {}: {}", status, error),
+            Error::Http { status, body } => write!(f, "http error {}: {}", status, body),
+            Error::RateLimited(wait) => write!(f, "rate limited for {} seconds", wait.as_secs()),
+            Error::Json(why) => write!(f, "json error: {}", why),
+        }
+    }
+}
+
+impl<E: error::Error + 'static> error::Error for Error<E> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Transport(why) => Some(why),
+            Error::Api { error, .. } => Some(error.as_ref()),
+            Error::Json(why) => Some(why),
+            Error::Http { .. } | Error::RateLimited(_) => None,
+        }
+    }
+}
+
+impl<E> From<serde_json::Error> for Error<E> {
+    fn from(why: serde_json::Error) -> Self {
+        Error::Json(why)
+    }
+}
+
+/// Calls endpoints through a transport and keeps track of rate limits
+pub struct Client<T> {
+    transport: T,
+    auth: Auth,
+    base_url: String,
+    rate_limits: RateLimitTracker,
+}
+
+impl<T: HttpTransport> Client<T> {
+    /// Creates a client for `DEFAULT_BASE_URL`
+    pub fn new(transport: T, auth: Auth) -> Self {
+        Client {
+            transport,
+            auth,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            rate_limits: RateLimitTracker::new(),
+        }
+    }
+
+    /// Sends requests to another host, like a local mock server
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// The latest rate limits of every endpoint that was called
+    pub fn rate_limits(&self) -> &RateLimitTracker {
+        &self.rate_limits
+    }
+
+    /// Returns the transport
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Calls an endpoint. Fails without sending the request when the
+    /// rate limit of the endpoint is known to be used up.
+    pub fn send<P: Endpoint>(&mut self, endpoint: &P) -> Result<P::Output, Error<T::Error>> {
+        let key = endpoint.rate_limit_key();
+        let wait = self.rate_limits.wait_time_now(key);
+
+        if wait > Duration::from_secs(0) {
+            return Err(Error::RateLimited(wait));
+        }
+
+        let request = self.request(endpoint);
+        let response = self.transport.send(&request).map_err(Error::Transport)?;
+
+        self.rate_limits.update_from_headers(key, response.headers.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+
+        match response.status {
+            200..=299 => Ok(serde_json::from_str(&response.body)?),
+            429 => Err(Error::RateLimited(self.retry_after(key, &response))),
+            status => match serde_json::from_str(&response.body) {
+                Ok(error) => Err(Error::Api { status, error: Box::new(error) }),
+                Err(_) => Err(Error::Http { status, body: response.body }),
+            },
+        }
+    }
+
+    /// How long to wait after HTTP 429. Uses the rate limit headers,
+    /// then `retry-after`, and falls back to a whole window.
+    fn retry_after(&self, key: &str, response: &Response) -> Duration {
+        let wait = self.rate_limits.wait_time_now(key);
+
+        if wait > Duration::from_secs(0) {
+            return wait;
+        }
+
+        response.header("retry-after")
+                .and_then(|secs| secs.trim().parse().ok())
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(RATE_LIMIT_WINDOW)
+    }
+
+    /// Builds the signed request for an endpoint
+    pub fn request<P: Endpoint>(&self, endpoint: &P) -> Request {
+        let method = endpoint.method();
+        let params = endpoint.params();
+        let pairs = params.iter().map(|(key, value)| (*key, value.as_str())).collect::<Vec<_>>();
+        let url = format!("{}/{}", self.base_url, endpoint.path());
+
+        let mut headers = Vec::new();
+
+        match &self.auth {
+            Auth::None => {}
+            Auth::OAuth1(oauth) => {
+                let method = match method {
+                    Method::Get => "GET",
+                    Method::Post => "POST",
+                };
+                headers.push(("Authorization".to_string(), oauth.authorization(method, &url, &pairs)));
+            }
+            Auth::Bearer(token) => headers.push(("Authorization".to_string(), format!("Bearer {}", token))),
+        }
+
+        let encoded = form_encode(pairs);
+
+        match method {
+            Method::Get => Request {
+                method,
+                url: if encoded.is_empty() { url } else { format!("{}?{}", url, encoded) },
+                headers,
+                body: None,
+            },
+            Method::Post => {
+                headers.push(("Content-Type".to_string(), FORM_CONTENT_TYPE.to_string()));
+
+                Request {
+                    method,
+                    url,
+                    headers,
+                    body: Some(encoded),
+                }
+            }
+        }
+    }
+}
+
+impl Response {
+    /// The value of a header, matched case insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Tweet;
+    use chrono::Utc;
+    use std::convert::Infallible;
+
+    fn response(status: u16, headers: &[(&str, String)], body: &str) -> Response {
+        Response {
+            status,
+            headers: headers.iter().map(|(name, value)| (name.to_string(), value.clone())).collect(),
+            body: body.to_string(),
+        }
+    }
+
+    fn canned<F>(mut respond: F) -> Client<impl HttpTransport<Error = Infallible>>
+        where F: FnMut(&Request) -> Response
+    {
+        Client::new(move |request: &Request| Ok::<_, Infallible>(respond(request)), Auth::Bearer("token".to_string()))
+    }
+
+    #[test]
+    fn parses_successful_responses() {
+        let body = serde_json::to_string(&Tweet { id: 20, ..Default::default() }).unwrap();
+        let mut client = canned(|request| {
+            assert_eq!(request.url, "https://api.twitter.com/1.1/statuses/show.json?id=20&tweet_mode=extended");
+            assert_eq!(request.headers, vec![("Authorization".to_string(), "Bearer token".to_string())]);
+            response(200, &[], &body)
+        });
+
+        assert_eq!(client.send(&ShowTweet { id: 20 }).unwrap().id, 20);
+    }
+
+    #[test]
+    fn exhausted_limits_are_not_sent() {
+        let reset = (Utc::now().timestamp() + 60).to_string();
+        let mut calls = 0;
+        let mut client = canned(|_| {
+            calls += 1;
+            let headers = [
+                ("x-rate-limit-limit", "1".to_string()),
+                ("x-rate-limit-remaining", "0".to_string()),
+                ("x-rate-limit-reset", reset.clone()),
+            ];
+            response(200, &headers, "[]")
+        });
+
+        assert!(client.send(&LookupTweets { ids: vec![1] }).unwrap().is_empty());
+        assert_eq!(client.rate_limits().remaining("/statuses/lookup"), Some(0));
+
+        match client.send(&LookupTweets { ids: vec![1] }) {
+            Err(Error::RateLimited(wait)) => assert!(wait > Duration::from_secs(0) && wait <= Duration::from_secs(60)),
+            other => panic!("expected a rate limit, got {:?}", other.map(|_| ())),
+        }
+
+        drop(client);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn too_many_requests_waits_without_headers() {
+        let mut client = canned(|_| response(429, &[], r#"{"errors":[{"code":88,"message":"Rate limit exceeded"}]}"#));
+        assert!(matches!(client.send(&ShowTweet { id: 1 }), Err(Error::RateLimited(wait)) if wait == RATE_LIMIT_WINDOW));
+
+        let mut client = canned(|_| response(429, &[("Retry-After", "30".to_string())], ""));
+        assert!(matches!(client.send(&ShowTweet { id: 1 }), Err(Error::RateLimited(wait)) if wait == Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn error_bodies_are_parsed() {
+        let mut client = canned(|_| response(404, &[], r#"{"errors":[{"code":144,"message":"No status found with that ID."}]}"#));
+
+        match client.send(&ShowTweet { id: 1 }) {
+            Err(Error::Api { status, error }) => {
+                assert_eq!(status, 404);
+                assert!(!Error::<Infallible>::Api { status, error }.is_retryable());
+            }
+            other => panic!("expected an api error, got {:?}", other.map(|_| ())),
+        }
+
+        let mut client = canned(|_| response(503, &[], "<html>"));
+        assert!(matches!(client.send(&ShowTweet { id: 1 }), Err(Error::Http { status: 503, .. })));
+    }
+}
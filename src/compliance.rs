@@ -0,0 +1,537 @@
+//! Applies compliance events to stored tweets.
+//!
+//! Twitter requires anyone storing tweets to honor deletions, location
+//! scrubbing and withholding. The `ComplianceEngine` turns the relevant
+//! stream messages into `ComplianceEvent`s, applies them to any storage
+//! implementing `ComplianceStore` and keeps an audit log of every tweet
+//! that was changed.
+
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::model::{Delete, ScrubGeo, StatusWithheld, Tweet, UserWithheld};
+use crate::TwitterResponse;
+
+/// A change that has to be applied to stored tweets
+#[derive(Clone, Debug, PartialEq)]
+pub enum ComplianceEvent {
+    /// A tweet was deleted by its author
+    Delete {
+        /// Id of the deleted tweet
+        status_id: u64,
+        /// Id of the author of the deleted tweet
+        user_id: u64,
+    },
+    /// A user removed location information from their tweets
+    ScrubGeo {
+        /// Id of the user
+        user_id: u64,
+        /// Tweets with an id up to and including this one are affected
+        up_to_status_id: u64,
+    },
+    /// A tweet was withheld in certain countries
+    StatusWithheld {
+        /// Id of the withheld tweet
+        status_id: u64,
+        /// Id of the author of the withheld tweet
+        user_id: u64,
+        /// Countries the tweet is withheld in
+        countries: Vec<String>,
+    },
+    /// A user was withheld in certain countries
+    UserWithheld {
+        /// Id of the withheld user
+        user_id: u64,
+        /// Countries the user is withheld in
+        countries: Vec<String>,
+    },
+}
+
+impl ComplianceEvent {
+    /// Extracts the compliance event from a stream message, if it is one
+    pub fn from_response(response: &TwitterResponse) -> Option<Self> {
+        match response {
+            TwitterResponse::Delete(delete) => Some(delete.into()),
+            TwitterResponse::ScrubGeo(scrub) => Some(scrub.into()),
+            TwitterResponse::StatusWithheld(withheld) => Some(withheld.into()),
+            TwitterResponse::UserWithheld(withheld) => Some(withheld.into()),
+            _ => None,
+        }
+    }
+}
+
+impl From<&Delete> for ComplianceEvent {
+    fn from(delete: &Delete) -> Self {
+        ComplianceEvent::Delete {
+            status_id: delete.delete.status.id,
+            user_id: delete.delete.status.user_id,
+        }
+    }
+}
+
+impl From<&ScrubGeo> for ComplianceEvent {
+    fn from(scrub: &ScrubGeo) -> Self {
+        ComplianceEvent::ScrubGeo {
+            user_id: scrub.scrub_geo.user_id,
+            up_to_status_id: scrub.scrub_geo.up_to_status_id,
+        }
+    }
+}
+
+impl From<&StatusWithheld> for ComplianceEvent {
+    fn from(withheld: &StatusWithheld) -> Self {
+        ComplianceEvent::StatusWithheld {
+            status_id: withheld.status_withheld.id,
+            user_id: withheld.status_withheld.user_id,
+            countries: withheld.status_withheld.withheld_in_countries.clone(),
+        }
+    }
+}
+
+impl From<&UserWithheld> for ComplianceEvent {
+    fn from(withheld: &UserWithheld) -> Self {
+        ComplianceEvent::UserWithheld {
+            user_id: withheld.user_withheld.id,
+            countries: withheld.user_withheld.withheld_in_countries.clone(),
+        }
+    }
+}
+
+/// Storage that compliance events can be applied to.
+///
+/// Every method returns the ids of the tweets that were actually
+/// changed, which is what ends up in the audit log. Copies of a tweet
+/// embedded as a retweet or quote in another tweet are changed too.
+pub trait ComplianceStore {
+    /// Error returned by the storage
+    type Error;
+
+    /// Removes a tweet along with any retweets of it
+    fn delete_tweet(&mut self, status_id: u64) -> Result<Vec<u64>, Self::Error>;
+
+    /// Clears `coordinates`, `place` and `geo` of every tweet from the
+    /// user with an id up to and including `up_to_status_id`
+    fn scrub_geo(&mut self, user_id: u64, up_to_status_id: u64) -> Result<Vec<u64>, Self::Error>;
+
+    /// Records the countries a tweet is withheld in
+    fn withhold_tweet(&mut self, status_id: u64, countries: &[String]) -> Result<Vec<u64>, Self::Error>;
+
+    /// Records the countries a user is withheld in on each of their tweets
+    fn withhold_user(&mut self, user_id: u64, countries: &[String]) -> Result<Vec<u64>, Self::Error>;
+}
+
+impl<S: ComplianceStore + ?Sized> ComplianceStore for &mut S {
+    type Error = S::Error;
+
+    fn delete_tweet(&mut self, status_id: u64) -> Result<Vec<u64>, Self::Error> {
+        (**self).delete_tweet(status_id)
+    }
+
+    fn scrub_geo(&mut self, user_id: u64, up_to_status_id: u64) -> Result<Vec<u64>, Self::Error> {
+        (**self).scrub_geo(user_id, up_to_status_id)
+    }
+
+    fn withhold_tweet(&mut self, status_id: u64, countries: &[String]) -> Result<Vec<u64>, Self::Error> {
+        (**self).withhold_tweet(status_id, countries)
+    }
+
+    fn withhold_user(&mut self, user_id: u64, countries: &[String]) -> Result<Vec<u64>, Self::Error> {
+        (**self).withhold_user(user_id, countries)
+    }
+}
+
+/// What was done to a stored tweet
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// The tweet was removed
+    Deleted,
+    /// Location information was removed from the tweet
+    GeoScrubbed,
+    /// The tweet was marked as withheld in these countries
+    StatusWithheld(Vec<String>),
+    /// The author of the tweet was marked as withheld in these countries
+    UserWithheld(Vec<String>),
+}
+
+/// A single change made while applying a compliance event
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditEntry {
+    /// When the change was made
+    pub applied_at: DateTime<Utc>,
+    /// The event that caused the change
+    pub event: ComplianceEvent,
+    /// The tweet that was changed
+    pub status_id: u64,
+    /// What was changed
+    pub action: Action,
+}
+
+/// Applies compliance events to a store and records what changed
+pub struct ComplianceEngine<S> {
+    store: S,
+    audit_log: Vec<AuditEntry>,
+}
+
+impl<S: ComplianceStore> ComplianceEngine<S> {
+    /// Creates an engine with an empty audit log
+    pub fn new(store: S) -> Self {
+        ComplianceEngine {
+            store,
+            audit_log: Vec::new(),
+        }
+    }
+
+    /// Applies an event and returns the audit entries it produced
+    pub fn apply(&mut self, event: &ComplianceEvent) -> Result<&[AuditEntry], S::Error> {
+        self.apply_at(event, Utc::now())
+    }
+
+    /// Same as `apply`, but with an explicit time for the audit entries
+    pub fn apply_at(&mut self, event: &ComplianceEvent, now: DateTime<Utc>) -> Result<&[AuditEntry], S::Error> {
+        let (ids, action) = match event {
+            ComplianceEvent::Delete { status_id, .. } => {
+                (self.store.delete_tweet(*status_id)?, Action::Deleted)
+            }
+            ComplianceEvent::ScrubGeo { user_id, up_to_status_id } => {
+                (self.store.scrub_geo(*user_id, *up_to_status_id)?, Action::GeoScrubbed)
+            }
+            ComplianceEvent::StatusWithheld { status_id, countries, .. } => {
+                (self.store.withhold_tweet(*status_id, countries)?, Action::StatusWithheld(countries.clone()))
+            }
+            ComplianceEvent::UserWithheld { user_id, countries } => {
+                (self.store.withhold_user(*user_id, countries)?, Action::UserWithheld(countries.clone()))
+            }
+        };
+
+        let start = self.audit_log.len();
+
+        for status_id in ids {
+            self.audit_log.push(AuditEntry {
+                applied_at: now,
+                event: event.clone(),
+                status_id,
+                action: action.clone(),
+            });
+        }
+
+        Ok(&self.audit_log[start..])
+    }
+
+    /// Applies the message if it is a compliance event. Returns the
+    /// audit entries it produced, or `None` for any other message.
+    pub fn apply_response(&mut self, response: &TwitterResponse) -> Result<Option<&[AuditEntry]>, S::Error> {
+        match ComplianceEvent::from_response(response) {
+            Some(event) => self.apply(&event).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Every change made so far
+    pub fn audit_log(&self) -> &[AuditEntry] {
+        &self.audit_log
+    }
+
+    /// Returns the audit log and starts a new one
+    pub fn take_audit_log(&mut self) -> Vec<AuditEntry> {
+        std::mem::take(&mut self.audit_log)
+    }
+
+    /// The store events are applied to
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// The store events are applied to
+    pub fn store_mut(&mut self) -> &mut S {
+        &mut self.store
+    }
+
+    /// Returns the store, dropping the audit log
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+/// A simple store that keeps tweets in memory, keyed by id
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    tweets: HashMap<u64, Tweet>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces a tweet
+    pub fn insert(&mut self, tweet: Tweet) {
+        self.tweets.insert(tweet.id, tweet);
+    }
+
+    /// Looks up a tweet by id
+    pub fn get(&self, id: u64) -> Option<&Tweet> {
+        self.tweets.get(&id)
+    }
+
+    /// Iterates over all stored tweets in no particular order
+    pub fn tweets(&self) -> impl Iterator<Item = &Tweet> {
+        self.tweets.values()
+    }
+
+    /// How many tweets are stored
+    pub fn len(&self) -> usize {
+        self.tweets.len()
+    }
+
+    /// Whether no tweets are stored
+    pub fn is_empty(&self) -> bool {
+        self.tweets.is_empty()
+    }
+}
+
+impl ComplianceStore for MemoryStore {
+    type Error = std::convert::Infallible;
+
+    /// Removes a tweet along with any retweets of it, including the
+    /// copies embedded as retweets or quotes in other tweets
+    fn delete_tweet(&mut self, status_id: u64) -> Result<Vec<u64>, Self::Error> {
+        let is_removed = |tweet: &Tweet| tweet.id == status_id || tweet.base_id() == status_id;
+
+        let mut found = false;
+        let mut retweets = BTreeSet::new();
+
+        for tweet in self.tweets.values_mut() {
+            for_each_tweet(tweet, &mut |tweet| {
+                found |= tweet.id == status_id;
+
+                if tweet.base_id() == status_id && tweet.id != status_id {
+                    retweets.insert(tweet.id);
+                }
+
+                if matches!(tweet.quoted_status.as_deref(), Some(quoted) if is_removed(quoted)) {
+                    tweet.quoted_status = None;
+                }
+            });
+        }
+
+        self.tweets.retain(|_, tweet| !is_removed(tweet));
+
+        let mut removed = if found { vec![status_id] } else { Vec::new() };
+        removed.extend(retweets);
+        Ok(removed)
+    }
+
+    #[allow(deprecated)]
+    fn scrub_geo(&mut self, user_id: u64, up_to_status_id: u64) -> Result<Vec<u64>, Self::Error> {
+        let mut changed = BTreeSet::new();
+
+        for tweet in self.tweets.values_mut() {
+            for_each_tweet(tweet, &mut |tweet| {
+                if tweet.user.id != user_id || tweet.id > up_to_status_id {
+                    return;
+                }
+
+                if tweet.coordinates.is_some() || tweet.place.is_some() || tweet.geo.is_some() {
+                    tweet.coordinates = None;
+                    tweet.place = None;
+                    tweet.geo = None;
+                    changed.insert(tweet.id);
+                }
+            });
+        }
+
+        Ok(changed.into_iter().collect())
+    }
+
+    fn withhold_tweet(&mut self, status_id: u64, countries: &[String]) -> Result<Vec<u64>, Self::Error> {
+        let mut changed = BTreeSet::new();
+
+        for tweet in self.tweets.values_mut() {
+            for_each_tweet(tweet, &mut |tweet| {
+                if tweet.id == status_id {
+                    tweet.withheld_in_countries = Some(countries.to_vec());
+                    changed.insert(tweet.id);
+                }
+            });
+        }
+
+        Ok(changed.into_iter().collect())
+    }
+
+    fn withhold_user(&mut self, user_id: u64, countries: &[String]) -> Result<Vec<u64>, Self::Error> {
+        let mut changed = BTreeSet::new();
+
+        for tweet in self.tweets.values_mut() {
+            for_each_tweet(tweet, &mut |tweet| {
+                if tweet.user.id == user_id {
+                    tweet.user.withheld_in_countries = Some(countries.to_vec());
+                    changed.insert(tweet.id);
+                }
+            });
+        }
+
+        Ok(changed.into_iter().collect())
+    }
+}
+
+/// Calls `f` on every tweet embedded in a tweet and then on the tweet
+/// itself, so `f` may drop embedded tweets it has already seen
+fn for_each_tweet<F: FnMut(&mut Tweet)>(tweet: &mut Tweet, f: &mut F) {
+    if let Some(rt) = &mut tweet.retweeted_status {
+        for_each_tweet(rt, f);
+    }
+
+    if let Some(quote) = &mut tweet.quoted_status {
+        for_each_tweet(quote, f);
+    }
+
+    f(tweet);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{BoundingBox, Coordinates, Place, User};
+    use chrono::TimeZone;
+
+    fn tweet(id: u64, user_id: u64) -> Tweet {
+        Tweet {
+            id,
+            id_str: id.to_string(),
+            text: format!("tweet {}", id),
+            user: User {
+                id: user_id,
+                id_str: user_id.to_string(),
+                screen_name: format!("user{}", user_id),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn geo_tweet(id: u64, user_id: u64) -> Tweet {
+        Tweet {
+            coordinates: Some(Coordinates {
+                coordinates: (-122.4, 37.8),
+                kind: "Point".to_string(),
+            }),
+            place: Some(Place {
+                id: "5a110d312052166f".to_string(),
+                url: String::new(),
+                place_type: "city".to_string(),
+                name: "San Francisco".to_string(),
+                full_name: "San Francisco, CA".to_string(),
+                country_code: "US".to_string(),
+                country: "United States".to_string(),
+                bounding_box: BoundingBox { coordinates: Vec::new() },
+            }),
+            ..tweet(id, user_id)
+        }
+    }
+
+    fn retweet(id: u64, user_id: u64, original: Tweet) -> Tweet {
+        Tweet {
+            retweeted_status: Some(Box::new(original)),
+            ..tweet(id, user_id)
+        }
+    }
+
+    fn quote(id: u64, user_id: u64, quoted: Tweet) -> Tweet {
+        Tweet {
+            quoted_status_id: Some(quoted.id),
+            is_quote_status: true,
+            quoted_status: Some(Box::new(quoted)),
+            ..tweet(id, user_id)
+        }
+    }
+
+    /// Tweets with copies of other tweets embedded in them
+    fn tweets() -> Vec<Tweet> {
+        vec![
+            geo_tweet(1, 10),
+            retweet(2, 20, geo_tweet(1, 10)),
+            quote(4, 30, geo_tweet(3, 10)),
+            tweet(5, 40),
+            quote(6, 20, tweet(7, 50)),
+            retweet(8, 60, quote(4, 30, geo_tweet(3, 10))),
+        ]
+    }
+
+    fn events() -> Vec<ComplianceEvent> {
+        let countries = |code: &str| vec![code.to_string()];
+
+        vec![
+            ComplianceEvent::ScrubGeo { user_id: 10, up_to_status_id: 3 },
+            ComplianceEvent::StatusWithheld { status_id: 5, user_id: 40, countries: countries("DE") },
+            ComplianceEvent::UserWithheld { user_id: 10, countries: countries("FR") },
+            ComplianceEvent::Delete { status_id: 7, user_id: 50 },
+            ComplianceEvent::Delete { status_id: 1, user_id: 10 },
+        ]
+    }
+
+    fn apply_all<S: ComplianceStore>(store: S) -> Vec<AuditEntry>
+        where S::Error: std::fmt::Debug
+    {
+        let now = Utc.timestamp_opt(1_600_000_000, 0).unwrap();
+        let mut engine = ComplianceEngine::new(store);
+
+        for event in events() {
+            engine.apply_at(&event, now).unwrap();
+        }
+
+        engine.take_audit_log()
+    }
+
+    fn changed(log: &[AuditEntry], event: &ComplianceEvent) -> Vec<u64> {
+        log.iter().filter(|entry| &entry.event == event).map(|entry| entry.status_id).collect()
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn memory_store_changes_embedded_tweets() {
+        let mut store = MemoryStore::new();
+        tweets().into_iter().for_each(|tweet| store.insert(tweet));
+
+        let log = apply_all(&mut store);
+        let events = events();
+
+        assert_eq!(changed(&log, &events[0]), vec![1, 3]);
+        assert_eq!(changed(&log, &events[1]), vec![5]);
+        assert_eq!(changed(&log, &events[2]), vec![1, 3]);
+        assert_eq!(changed(&log, &events[3]), vec![7]);
+        assert_eq!(changed(&log, &events[4]), vec![1, 2]);
+
+        assert!(store.get(1).is_none());
+        assert!(store.get(2).is_none());
+        assert!(store.get(6).unwrap().quoted_status.is_none());
+
+        let quoted = store.get(4).unwrap().quoted_status.as_ref().unwrap();
+        assert!(quoted.coordinates.is_none() && quoted.place.is_none());
+        assert_eq!(quoted.user.withheld_in_countries, Some(vec!["FR".to_string()]));
+
+        let rt = store.get(8).unwrap().retweeted_status.as_ref().unwrap();
+        assert!(rt.quoted_status.as_ref().unwrap().coordinates.is_none());
+        assert_eq!(store.get(5).unwrap().withheld_in_countries, Some(vec!["DE".to_string()]));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn memory_and_sqlite_stores_agree() {
+        use crate::sqlite::SqliteStore;
+
+        let mut memory = MemoryStore::new();
+        tweets().into_iter().for_each(|tweet| memory.insert(tweet));
+
+        let mut sqlite = SqliteStore::open_in_memory().unwrap();
+        sqlite.upsert_all(&tweets()).unwrap();
+
+        assert_eq!(apply_all(&mut memory), apply_all(&mut sqlite));
+
+        for tweet in tweets() {
+            let expected = memory.get(tweet.id).map(|tweet| serde_json::to_value(tweet).unwrap());
+            let actual = sqlite.get(tweet.id).unwrap().map(|tweet| serde_json::to_value(tweet).unwrap());
+            assert_eq!(expected, actual, "tweet {}", tweet.id);
+        }
+    }
+}
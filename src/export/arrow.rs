@@ -0,0 +1,413 @@
+//! Apache Arrow and Parquet export.
+//!
+//! Tweets are converted into `RecordBatch`es with a fixed schema, see
+//! [`schema`]. Entities and media become lists of structs, and the
+//! author, place and coordinates become nullable structs.
+
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, ListArray, RecordBatch, StringArray, StructArray,
+    TimestampMillisecondArray, UInt32Array, UInt64Array,
+};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
+use arrow_schema::{ArrowError, DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use std::borrow::Borrow;
+use std::io::Write;
+use std::sync::Arc;
+
+use crate::model::Tweet;
+use crate::export::entities;
+use crate::normalize::tweet_media;
+
+/// How many tweets go into each batch unless told otherwise
+pub const DEFAULT_BATCH_SIZE: usize = 8192;
+
+fn timestamp() -> DataType {
+    DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+}
+
+fn user_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("id", DataType::UInt64, false),
+        Field::new("screen_name", DataType::Utf8, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("location", DataType::Utf8, true),
+        Field::new("description", DataType::Utf8, true),
+        Field::new("protected", DataType::Boolean, false),
+        Field::new("verified", DataType::Boolean, false),
+        Field::new("followers_count", DataType::UInt32, false),
+        Field::new("friends_count", DataType::UInt32, false),
+        Field::new("statuses_count", DataType::UInt32, false),
+        Field::new("created_at", timestamp(), false),
+    ])
+}
+
+fn coordinates_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("longitude", DataType::Float64, false),
+        Field::new("latitude", DataType::Float64, false),
+    ])
+}
+
+fn place_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("full_name", DataType::Utf8, false),
+        Field::new("place_type", DataType::Utf8, false),
+        Field::new("country_code", DataType::Utf8, false),
+    ])
+}
+
+fn mention_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("id", DataType::UInt64, true),
+        Field::new("screen_name", DataType::Utf8, false),
+    ])
+}
+
+fn url_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("url", DataType::Utf8, false),
+        Field::new("expanded_url", DataType::Utf8, false),
+        Field::new("display_url", DataType::Utf8, false),
+    ])
+}
+
+fn media_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("id", DataType::UInt64, false),
+        Field::new("type", DataType::Utf8, false),
+        Field::new("media_url_https", DataType::Utf8, false),
+        Field::new("url", DataType::Utf8, true),
+    ])
+}
+
+fn list_of(item: DataType) -> DataType {
+    DataType::List(Arc::new(Field::new("item", item, true)))
+}
+
+/// The schema of every batch produced by this module
+pub fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::UInt64, false),
+        Field::new("created_at", timestamp(), false),
+        Field::new("text", DataType::Utf8, false),
+        Field::new("full_text", DataType::Utf8, false),
+        Field::new("source", DataType::Utf8, false),
+        Field::new("lang", DataType::Utf8, true),
+        Field::new("in_reply_to_status_id", DataType::UInt64, true),
+        Field::new("in_reply_to_user_id", DataType::UInt64, true),
+        Field::new("in_reply_to_screen_name", DataType::Utf8, true),
+        Field::new("retweeted_status_id", DataType::UInt64, true),
+        Field::new("quoted_status_id", DataType::UInt64, true),
+        Field::new("retweet_count", DataType::UInt32, false),
+        Field::new("reply_count", DataType::UInt32, false),
+        Field::new("quote_count", DataType::UInt32, true),
+        Field::new("favorite_count", DataType::UInt32, true),
+        Field::new("possibly_sensitive", DataType::Boolean, true),
+        Field::new("user", DataType::Struct(user_fields()), false),
+        Field::new("coordinates", DataType::Struct(coordinates_fields()), true),
+        Field::new("place", DataType::Struct(place_fields()), true),
+        Field::new("hashtags", list_of(DataType::Utf8), false),
+        Field::new("user_mentions", list_of(DataType::Struct(mention_fields())), false),
+        Field::new("urls", list_of(DataType::Struct(url_fields())), false),
+        Field::new("media", list_of(DataType::Struct(media_fields())), false),
+    ]))
+}
+
+fn millis(date: &DateTime<Utc>) -> i64 {
+    date.timestamp_millis()
+}
+
+fn timestamps<I: IntoIterator<Item = i64>>(values: I) -> ArrayRef {
+    Arc::new(TimestampMillisecondArray::from_iter_values(values).with_timezone("UTC"))
+}
+
+fn strings<I, S>(values: I) -> ArrayRef
+    where I: IntoIterator<Item = Option<S>>,
+          S: AsRef<str>
+{
+    Arc::new(values.into_iter().collect::<StringArray>())
+}
+
+fn nullable_struct(fields: Fields, columns: Vec<ArrayRef>, valid: Vec<bool>) -> Result<ArrayRef, ArrowError> {
+    let nulls = if valid.iter().all(|v| *v) {
+        None
+    } else {
+        Some(NullBuffer::from(valid))
+    };
+
+    Ok(Arc::new(StructArray::try_new(fields, columns, nulls)?))
+}
+
+fn list(item: DataType, lengths: Vec<usize>, values: ArrayRef) -> Result<ArrayRef, ArrowError> {
+    let field = Arc::new(Field::new("item", item, true));
+    let offsets = OffsetBuffer::from_lengths(lengths);
+
+    Ok(Arc::new(ListArray::try_new(field, offsets, values, None)?))
+}
+
+/// Converts a slice of tweets into a single record batch
+pub fn to_record_batch<T: Borrow<Tweet>>(tweets: &[T]) -> Result<RecordBatch, ArrowError> {
+    let tweets = tweets.iter().map(Borrow::borrow).collect::<Vec<&Tweet>>();
+
+    let users = tweets.iter().map(|t| &t.user).collect::<Vec<_>>();
+    let user = nullable_struct(user_fields(), vec![
+        Arc::new(users.iter().map(|u| u.id).collect::<UInt64Array>()),
+        strings(users.iter().map(|u| Some(&u.screen_name))),
+        strings(users.iter().map(|u| Some(&u.name))),
+        strings(users.iter().map(|u| u.location.as_ref())),
+        strings(users.iter().map(|u| u.description.as_ref())),
+        Arc::new(users.iter().map(|u| Some(u.protected)).collect::<BooleanArray>()),
+        Arc::new(users.iter().map(|u| Some(u.verified)).collect::<BooleanArray>()),
+        Arc::new(users.iter().map(|u| u.followers_count).collect::<UInt32Array>()),
+        Arc::new(users.iter().map(|u| u.friends_count).collect::<UInt32Array>()),
+        Arc::new(users.iter().map(|u| u.statuses_count).collect::<UInt32Array>()),
+        timestamps(users.iter().map(|u| millis(&u.created_at))),
+    ], vec![true; tweets.len()])?;
+
+    let coords = tweets.iter().map(|t| t.coordinates.as_ref().map(|c| c.coordinates)).collect::<Vec<_>>();
+    let coordinates = nullable_struct(coordinates_fields(), vec![
+        Arc::new(coords.iter().map(|c| c.map_or(0.0, |c| c.0)).collect::<Float64Array>()),
+        Arc::new(coords.iter().map(|c| c.map_or(0.0, |c| c.1)).collect::<Float64Array>()),
+    ], coords.iter().map(Option::is_some).collect())?;
+
+    let places = tweets.iter().map(|t| t.place.as_ref()).collect::<Vec<_>>();
+    let place = nullable_struct(place_fields(), vec![
+        strings(places.iter().map(|p| Some(p.map_or("", |p| &p.id)))),
+        strings(places.iter().map(|p| Some(p.map_or("", |p| &p.full_name)))),
+        strings(places.iter().map(|p| Some(p.map_or("", |p| &p.place_type)))),
+        strings(places.iter().map(|p| Some(p.map_or("", |p| &p.country_code)))),
+    ], places.iter().map(Option::is_some).collect())?;
+
+    let ents = tweets.iter().map(|t| entities(t)).collect::<Vec<_>>();
+
+    let hashtags = ents.iter()
+                       .map(|e| e.map_or_else(Vec::new, |e| e.hashtags.iter().map(|h| h.text.as_str()).collect()))
+                       .collect::<Vec<Vec<&str>>>();
+    let hashtags = list(
+        DataType::Utf8,
+        hashtags.iter().map(Vec::len).collect(),
+        strings(hashtags.iter().flatten().map(Some)),
+    )?;
+
+    let mentions = ents.iter()
+                       .map(|e| e.map_or_else(Vec::new, |e| e.user_mentions.iter().collect()))
+                       .collect::<Vec<Vec<_>>>();
+    let mention_values = mentions.iter().flatten().collect::<Vec<_>>();
+    let user_mentions = list(
+        DataType::Struct(mention_fields()),
+        mentions.iter().map(Vec::len).collect(),
+        nullable_struct(mention_fields(), vec![
+            Arc::new(mention_values.iter().map(|m| m.id).collect::<UInt64Array>()),
+            strings(mention_values.iter().map(|m| Some(&m.screen_name))),
+        ], vec![true; mention_values.len()])?,
+    )?;
+
+    let links = ents.iter()
+                    .map(|e| e.map_or_else(Vec::new, |e| e.urls.iter().collect()))
+                    .collect::<Vec<Vec<_>>>();
+    let link_values = links.iter().flatten().collect::<Vec<_>>();
+    let urls = list(
+        DataType::Struct(url_fields()),
+        links.iter().map(Vec::len).collect(),
+        nullable_struct(url_fields(), vec![
+            strings(link_values.iter().map(|u| Some(&u.url))),
+            strings(link_values.iter().map(|u| Some(&u.expanded_url))),
+            strings(link_values.iter().map(|u| Some(&u.display_url))),
+        ], vec![true; link_values.len()])?,
+    )?;
+
+    let items = tweets.iter().map(|t| tweet_media(t)).collect::<Vec<Vec<_>>>();
+    let media_values = items.iter().flatten().collect::<Vec<_>>();
+    let media = list(
+        DataType::Struct(media_fields()),
+        items.iter().map(Vec::len).collect(),
+        nullable_struct(media_fields(), vec![
+            Arc::new(media_values.iter().map(|m| m.id).collect::<UInt64Array>()),
+            strings(media_values.iter().map(|m| Some(m.kind.as_str()))),
+            strings(media_values.iter().map(|m| Some(&m.media_url_https))),
+            strings(media_values.iter().map(|m| m.url())),
+        ], vec![true; media_values.len()])?,
+    )?;
+
+    RecordBatch::try_new(schema(), vec![
+        Arc::new(tweets.iter().map(|t| t.id).collect::<UInt64Array>()),
+        timestamps(tweets.iter().map(|t| millis(&t.created_at))),
+        strings(tweets.iter().map(|t| Some(&t.text))),
+        strings(tweets.iter().map(|t| Some(t.full_text()))),
+        strings(tweets.iter().map(|t| Some(&t.source))),
+        strings(tweets.iter().map(|t| t.lang.as_ref())),
+        Arc::new(tweets.iter().map(|t| t.in_reply_to_status_id).collect::<UInt64Array>()),
+        Arc::new(tweets.iter().map(|t| t.in_reply_to_user_id).collect::<UInt64Array>()),
+        strings(tweets.iter().map(|t| t.in_reply_to_screen_name.as_ref())),
+        Arc::new(tweets.iter().map(|t| t.retweeted_status.as_ref().map(|rt| rt.id)).collect::<UInt64Array>()),
+        Arc::new(tweets.iter().map(|t| t.quoted_status_id).collect::<UInt64Array>()),
+        Arc::new(tweets.iter().map(|t| t.retweet_count).collect::<UInt32Array>()),
+        Arc::new(tweets.iter().map(|t| t.reply_count).collect::<UInt32Array>()),
+        Arc::new(tweets.iter().map(|t| t.quote_count).collect::<UInt32Array>()),
+        Arc::new(tweets.iter().map(|t| t.favorite_count).collect::<UInt32Array>()),
+        Arc::new(tweets.iter().map(|t| t.possibly_sensitive).collect::<BooleanArray>()),
+        user,
+        coordinates,
+        place,
+        hashtags,
+        user_mentions,
+        urls,
+        media,
+    ])
+}
+
+/// Splits an iterator of tweets into record batches of at most `batch_size` rows
+pub fn record_batches<I>(tweets: I, batch_size: usize) -> RecordBatches<I::IntoIter>
+    where I: IntoIterator,
+          I::Item: Borrow<Tweet>
+{
+    RecordBatches {
+        tweets: tweets.into_iter(),
+        batch_size: batch_size.max(1),
+    }
+}
+
+/// Iterator returned by [`record_batches`]
+pub struct RecordBatches<I> {
+    tweets: I,
+    batch_size: usize,
+}
+
+impl<I> Iterator for RecordBatches<I>
+    where I: Iterator,
+          I::Item: Borrow<Tweet>
+{
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.tweets.by_ref().take(self.batch_size).collect::<Vec<_>>();
+
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(to_record_batch(&chunk))
+        }
+    }
+}
+
+/// Writes tweets into a Parquet file using the schema from [`schema`]
+pub struct ParquetWriter<W: Write + Send> {
+    writer: ArrowWriter<W>,
+    batch_size: usize,
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    /// Creates a writer that starts a new row group every `row_group_size` tweets
+    pub fn new(out: W, row_group_size: usize) -> Result<Self, ParquetError> {
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(row_group_size.max(1))
+            .build();
+
+        Self::with_properties(out, props)
+    }
+
+    /// Creates a writer with full control over the Parquet properties
+    pub fn with_properties(out: W, props: WriterProperties) -> Result<Self, ParquetError> {
+        let batch_size = props.max_row_group_size().min(DEFAULT_BATCH_SIZE);
+
+        Ok(ParquetWriter {
+            writer: ArrowWriter::try_new(out, schema(), Some(props))?,
+            batch_size,
+        })
+    }
+
+    /// Writes every tweet from an iterator
+    pub fn write<I>(&mut self, tweets: I) -> Result<(), ParquetError>
+        where I: IntoIterator,
+              I::Item: Borrow<Tweet>
+    {
+        for batch in record_batches(tweets, self.batch_size) {
+            self.writer.write(&batch?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the current row group even if it is not full
+    pub fn flush(&mut self) -> Result<(), ParquetError> {
+        self.writer.flush()
+    }
+
+    /// Writes the file footer and returns the underlying writer
+    pub fn into_inner(self) -> Result<W, ParquetError> {
+        self.writer.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{BoundingBox, Coordinates, Entity, Hashtag, Place};
+    use arrow_array::Array;
+
+    fn tweets() -> Vec<Tweet> {
+        let located = Tweet {
+            id: 1,
+            text: "#rust".to_string(),
+            lang: Some("en".to_string()),
+            entities: Some(Entity {
+                hashtags: vec![Hashtag { indices: vec![0, 5], text: "rust".to_string() }],
+                ..Default::default()
+            }),
+            coordinates: Some(Coordinates {
+                coordinates: (-122.4, 37.8),
+                kind: "Point".to_string(),
+            }),
+            place: Some(Place {
+                id: "5a110d312052166f".to_string(),
+                url: String::new(),
+                place_type: "city".to_string(),
+                name: "San Francisco".to_string(),
+                full_name: "San Francisco, CA".to_string(),
+                country_code: "US".to_string(),
+                country: "United States".to_string(),
+                bounding_box: BoundingBox { coordinates: Vec::new() },
+            }),
+            ..Default::default()
+        };
+
+        vec![located, Tweet { id: 2, ..Default::default() }]
+    }
+
+    #[test]
+    fn batch_has_one_row_per_tweet() {
+        let batch = to_record_batch(&tweets()).unwrap();
+
+        assert_eq!(batch.schema(), schema());
+        assert_eq!(batch.num_rows(), 2);
+
+        let ids = batch.column_by_name("id").unwrap().as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(ids.values(), &[1, 2]);
+
+        let lang = batch.column_by_name("lang").unwrap();
+        assert!(lang.is_valid(0));
+        assert!(lang.is_null(1));
+
+        for name in ["coordinates", "place"] {
+            let column = batch.column_by_name(name).unwrap();
+            assert!(column.is_valid(0), "{}", name);
+            assert!(column.is_null(1), "{}", name);
+        }
+
+        let hashtags = batch.column_by_name("hashtags").unwrap().as_any().downcast_ref::<ListArray>().unwrap();
+        assert_eq!(hashtags.value_length(0), 1);
+        assert_eq!(hashtags.value_length(1), 0);
+        assert_eq!(hashtags.null_count(), 0);
+    }
+
+    #[test]
+    fn batches_are_split_by_size() {
+        let sizes = record_batches(tweets(), 1).map(|b| b.unwrap().num_rows()).collect::<Vec<_>>();
+        assert_eq!(sizes, vec![1, 1]);
+    }
+}
@@ -0,0 +1,17 @@
+//! Converts tweets into formats used by data analysis tools.
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod table;
+
+pub use table::{Column, Field, Format, ParseColumnError, TableWriter};
+
+use crate::model::{Entity, Tweet};
+
+/// Entities of the untruncated text when available
+pub(crate) fn entities(tweet: &Tweet) -> Option<&Entity> {
+    match &tweet.extended_tweet {
+        Some(ext) => Some(&ext.entities),
+        None => tweet.entities.as_ref(),
+    }
+}
@@ -0,0 +1,563 @@
+//! CSV and TSV export with a configurable set of columns.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use crate::model::Tweet;
+use crate::util::datetime::FORMAT as TWITTER_DATE_FORMAT;
+use crate::export::entities;
+use crate::normalize::tweet_media;
+
+/// The layout of the exported table
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// Comma separated values with RFC 4180 quoting
+    Csv,
+    /// Tab separated values with backslash escapes instead of quoting
+    Tsv,
+}
+
+/// A value that can be extracted from a tweet
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Field {
+    /// The unique id for the tweet
+    Id,
+    /// Direct URL to the tweet
+    TweetUrl,
+    /// When the tweet was posted, in Twitter's format
+    CreatedAt,
+    /// When the tweet was posted, in RFC 3339 format
+    ParsedCreatedAt,
+    /// The possibly truncated text of the tweet
+    Text,
+    /// The untruncated text of the tweet
+    FullText,
+    /// One of `original`, `reply`, `quote` or `retweet`
+    TweetType,
+    /// Longitude and latitude separated by a comma
+    Coordinates,
+    /// Hashtags without the leading `#`
+    Hashtags,
+    /// Screen names of mentioned users
+    Mentions,
+    /// Expanded links
+    Urls,
+    /// The best available location of each media item
+    MediaUrls,
+    /// The type of each media item
+    MediaTypes,
+    /// How many times the tweet has been favorited
+    FavoriteCount,
+    /// How many times the tweet has been retweeted
+    RetweetCount,
+    /// How many times the tweet has been replied to
+    ReplyCount,
+    /// Approximate count of times the tweet was quoted
+    QuoteCount,
+    /// Screen name of the user being replied to
+    InReplyToScreenName,
+    /// Id of the tweet being replied to
+    InReplyToStatusId,
+    /// Id of the user being replied to
+    InReplyToUserId,
+    /// BCP 47 language identifier of the tweet
+    Lang,
+    /// Full, human-readable place name
+    PlaceFullName,
+    /// Country code of the place
+    PlaceCountryCode,
+    /// Whether the tweet is potentially sensitive
+    PossiblySensitive,
+    /// Id of the retweeted or quoted tweet
+    RetweetOrQuoteId,
+    /// Screen name of the author of the retweeted or quoted tweet
+    RetweetOrQuoteScreenName,
+    /// Id of the author of the retweeted or quoted tweet
+    RetweetOrQuoteUserId,
+    /// The client that posted the tweet
+    Source,
+    /// Id of the author
+    UserId,
+    /// Handle of the author
+    UserScreenName,
+    /// Display name of the author
+    UserName,
+    /// When the author's account was created
+    UserCreatedAt,
+    /// Whether the author has the default profile image
+    UserDefaultProfileImage,
+    /// Profile description of the author
+    UserDescription,
+    /// How many favorites the author has
+    UserFavouritesCount,
+    /// How many followers the author has
+    UserFollowersCount,
+    /// How many friends the author has
+    UserFriendsCount,
+    /// How many public lists the author is a member of
+    UserListedCount,
+    /// User-defined location of the author
+    UserLocation,
+    /// How many tweets the author has
+    UserStatusesCount,
+    /// Profile URL of the author
+    UserUrl,
+    /// Whether the author is verified
+    UserVerified,
+    /// Always empty. Stands in for columns that Twitter no longer sends.
+    Blank,
+}
+
+/// Every accepted column name and the field it maps to. Dotted names
+/// follow the JSON structure, underscored ones follow twarc's headers.
+const NAMES: &[(&str, Field)] = &[
+    ("id", Field::Id),
+    ("tweet_url", Field::TweetUrl),
+    ("url", Field::TweetUrl),
+    ("created_at", Field::CreatedAt),
+    ("parsed_created_at", Field::ParsedCreatedAt),
+    //  twarc writes the untruncated text under `text`
+    ("text", Field::FullText),
+    ("truncated_text", Field::Text),
+    ("full_text", Field::FullText),
+    ("tweet_type", Field::TweetType),
+    ("coordinates", Field::Coordinates),
+    ("hashtags", Field::Hashtags),
+    ("mentions", Field::Mentions),
+    ("user_mentions", Field::Mentions),
+    ("urls", Field::Urls),
+    ("media", Field::MediaUrls),
+    ("media_urls", Field::MediaUrls),
+    ("media_types", Field::MediaTypes),
+    ("favorite_count", Field::FavoriteCount),
+    ("retweet_count", Field::RetweetCount),
+    ("reply_count", Field::ReplyCount),
+    ("quote_count", Field::QuoteCount),
+    ("in_reply_to_screen_name", Field::InReplyToScreenName),
+    ("in_reply_to_status_id", Field::InReplyToStatusId),
+    ("in_reply_to_user_id", Field::InReplyToUserId),
+    ("lang", Field::Lang),
+    ("place", Field::PlaceFullName),
+    ("place.full_name", Field::PlaceFullName),
+    ("place.country_code", Field::PlaceCountryCode),
+    ("possibly_sensitive", Field::PossiblySensitive),
+    ("retweet_or_quote_id", Field::RetweetOrQuoteId),
+    ("retweet_or_quote_screen_name", Field::RetweetOrQuoteScreenName),
+    ("retweet_or_quote_user_id", Field::RetweetOrQuoteUserId),
+    ("source", Field::Source),
+    ("user_id", Field::UserId),
+    ("user.id", Field::UserId),
+    ("user_screen_name", Field::UserScreenName),
+    ("user.screen_name", Field::UserScreenName),
+    ("user_name", Field::UserName),
+    ("user.name", Field::UserName),
+    ("user_created_at", Field::UserCreatedAt),
+    ("user.created_at", Field::UserCreatedAt),
+    ("user_default_profile_image", Field::UserDefaultProfileImage),
+    ("user.default_profile_image", Field::UserDefaultProfileImage),
+    ("user_description", Field::UserDescription),
+    ("user.description", Field::UserDescription),
+    ("user_favourites_count", Field::UserFavouritesCount),
+    ("user.favourites_count", Field::UserFavouritesCount),
+    ("user_followers_count", Field::UserFollowersCount),
+    ("user.followers_count", Field::UserFollowersCount),
+    ("user_friends_count", Field::UserFriendsCount),
+    ("user.friends_count", Field::UserFriendsCount),
+    ("user_listed_count", Field::UserListedCount),
+    ("user.listed_count", Field::UserListedCount),
+    ("user_location", Field::UserLocation),
+    ("user.location", Field::UserLocation),
+    ("user_statuses_count", Field::UserStatusesCount),
+    ("user.statuses_count", Field::UserStatusesCount),
+    ("user_time_zone", Field::Blank),
+    ("user_urls", Field::UserUrl),
+    ("user.url", Field::UserUrl),
+    ("user_verified", Field::UserVerified),
+    ("user.verified", Field::UserVerified),
+];
+
+/// The columns written by twarc's `json2csv`, in the same order
+const TWARC_COLUMNS: &[&str] = &[
+    "id",
+    "tweet_url",
+    "created_at",
+    "parsed_created_at",
+    "user_screen_name",
+    "text",
+    "tweet_type",
+    "coordinates",
+    "hashtags",
+    "media",
+    "urls",
+    "favorite_count",
+    "in_reply_to_screen_name",
+    "in_reply_to_status_id",
+    "in_reply_to_user_id",
+    "lang",
+    "place",
+    "possibly_sensitive",
+    "retweet_count",
+    "retweet_or_quote_id",
+    "retweet_or_quote_screen_name",
+    "retweet_or_quote_user_id",
+    "source",
+    "user_id",
+    "user_created_at",
+    "user_default_profile_image",
+    "user_description",
+    "user_favourites_count",
+    "user_followers_count",
+    "user_friends_count",
+    "user_listed_count",
+    "user_location",
+    "user_name",
+    "user_statuses_count",
+    "user_time_zone",
+    "user_urls",
+    "user_verified",
+];
+
+/// A named column of the exported table
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Column {
+    /// Header written for this column
+    pub name: String,
+    /// Value written for this column
+    pub field: Field,
+}
+
+impl Column {
+    /// Creates a column with a custom header
+    pub fn new<S: Into<String>>(name: S, field: Field) -> Self {
+        Column {
+            name: name.into(),
+            field,
+        }
+    }
+
+    /// The column set written by twarc's `json2csv`, so that existing
+    /// notebooks can read the output without changes.
+    pub fn twarc() -> Vec<Column> {
+        TWARC_COLUMNS.iter()
+                     .map(|name| Column::from_str(name).expect("twarc columns are known"))
+                     .collect()
+    }
+}
+
+impl FromStr for Column {
+    type Err = ParseColumnError;
+
+    /// Parses a column name like `user.screen_name` or `user_screen_name`,
+    /// using it as the header.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NAMES.iter()
+             .find(|(name, _)| *name == s)
+             .map(|(_, field)| Column::new(s, *field))
+             .ok_or_else(|| ParseColumnError(s.to_string()))
+    }
+}
+
+/// Returned when a column name does not match any known field
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseColumnError(pub String);
+
+impl fmt::Display for ParseColumnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown column: {}", self.0)
+    }
+}
+
+impl Error for ParseColumnError {}
+
+impl Field {
+    /// Extracts this field from a tweet. Multi-valued fields are
+    /// returned as a list, single values as a list of one.
+    pub fn values(self, tweet: &Tweet) -> Vec<String> {
+        let single = |value: String| vec![value];
+        let optional = |value: Option<String>| value.into_iter().collect();
+
+        match self {
+            Field::Id => single(tweet.id.to_string()),
+            Field::TweetUrl => single(tweet.url()),
+            Field::CreatedAt => single(tweet.created_at.format(TWITTER_DATE_FORMAT).to_string()),
+            Field::ParsedCreatedAt => single(tweet.created_at.to_rfc3339()),
+            Field::Text => single(tweet.text.clone()),
+            Field::FullText => single(tweet.full_text()),
+            Field::TweetType => single(tweet_type(tweet).to_string()),
+            Field::Coordinates => optional(tweet.coordinates.as_ref().map(|c| {
+                format!("{},{}", c.coordinates.0, c.coordinates.1)
+            })),
+            Field::Hashtags => entities(tweet).map_or_else(Vec::new, |ent| {
+                ent.hashtags.iter().map(|ht| ht.text.clone()).collect()
+            }),
+            Field::Mentions => entities(tweet).map_or_else(Vec::new, |ent| {
+                ent.user_mentions.iter().map(|m| m.screen_name.clone()).collect()
+            }),
+            Field::Urls => entities(tweet).map_or_else(Vec::new, |ent| {
+                ent.urls.iter().map(|url| url.expanded_url.clone()).collect()
+            }),
+            Field::MediaUrls => {
+                let mut urls: Vec<String> = Vec::new();
+
+                for url in tweet_media(tweet).iter().filter_map(|media| media.url()) {
+                    if !urls.contains(&url) {
+                        urls.push(url);
+                    }
+                }
+
+                urls
+            }
+            Field::MediaTypes => tweet_media(tweet).iter()
+                                                    .map(|media| media.kind.as_str().to_string())
+                                                    .collect(),
+            Field::FavoriteCount => optional(tweet.favorite_count.map(|n| n.to_string())),
+            Field::RetweetCount => single(tweet.retweet_count.to_string()),
+            Field::ReplyCount => single(tweet.reply_count.to_string()),
+            Field::QuoteCount => optional(tweet.quote_count.map(|n| n.to_string())),
+            Field::InReplyToScreenName => optional(tweet.in_reply_to_screen_name.clone()),
+            Field::InReplyToStatusId => optional(tweet.in_reply_to_status_id.map(|n| n.to_string())),
+            Field::InReplyToUserId => optional(tweet.in_reply_to_user_id.map(|n| n.to_string())),
+            Field::Lang => optional(tweet.lang.clone()),
+            Field::PlaceFullName => optional(tweet.place.as_ref().map(|p| p.full_name.clone())),
+            Field::PlaceCountryCode => optional(tweet.place.as_ref().map(|p| p.country_code.clone())),
+            Field::PossiblySensitive => optional(tweet.possibly_sensitive.map(|b| b.to_string())),
+            Field::RetweetOrQuoteId => optional(retweet_or_quote(tweet).map(|t| t.id.to_string())),
+            Field::RetweetOrQuoteScreenName => optional(retweet_or_quote(tweet).map(|t| t.user.screen_name.clone())),
+            Field::RetweetOrQuoteUserId => optional(retweet_or_quote(tweet).map(|t| t.user.id.to_string())),
+            Field::Source => single(tweet.source.clone()),
+            Field::UserId => single(tweet.user.id.to_string()),
+            Field::UserScreenName => single(tweet.user.screen_name.clone()),
+            Field::UserName => single(tweet.user.name.clone()),
+            Field::UserCreatedAt => single(tweet.user.created_at.format(TWITTER_DATE_FORMAT).to_string()),
+            Field::UserDefaultProfileImage => single(tweet.user.default_profile_image.to_string()),
+            Field::UserDescription => optional(tweet.user.description.clone()),
+            Field::UserFavouritesCount => single(tweet.user.favourites_count.to_string()),
+            Field::UserFollowersCount => single(tweet.user.followers_count.to_string()),
+            Field::UserFriendsCount => single(tweet.user.friends_count.to_string()),
+            Field::UserListedCount => single(tweet.user.listed_count.to_string()),
+            Field::UserLocation => optional(tweet.user.location.clone()),
+            Field::UserStatusesCount => single(tweet.user.statuses_count.to_string()),
+            Field::UserUrl => optional(tweet.user.url.clone()),
+            Field::UserVerified => single(tweet.user.verified.to_string()),
+            Field::Blank => Vec::new(),
+        }
+    }
+}
+
+fn retweet_or_quote(tweet: &Tweet) -> Option<&Tweet> {
+    tweet.retweeted_status.as_deref().or(tweet.quoted_status.as_deref())
+}
+
+fn tweet_type(tweet: &Tweet) -> &'static str {
+    if tweet.is_retweet() {
+        "retweet"
+    } else if tweet.quoted_status.is_some() || tweet.quoted_status_id.is_some() {
+        "quote"
+    } else if tweet.in_reply_to_status_id.is_some() {
+        "reply"
+    } else {
+        "original"
+    }
+}
+
+/// Writes tweets as rows of a CSV or TSV table.
+///
+/// By default the twarc column set is used, multi-valued fields are
+/// joined with a space and a header row is written before the first tweet,
+/// or by `flush` and `into_inner` when no tweets were written.
+pub struct TableWriter<W> {
+    out: W,
+    format: Format,
+    columns: Vec<Column>,
+    separator: String,
+    escape_newlines: bool,
+    header: bool,
+}
+
+impl<W: Write> TableWriter<W> {
+    /// Creates a writer for the given format using the twarc columns
+    pub fn new(out: W, format: Format) -> Self {
+        TableWriter {
+            out,
+            format,
+            columns: Column::twarc(),
+            separator: String::from(" "),
+            escape_newlines: false,
+            header: true,
+        }
+    }
+
+    /// Replaces the columns that will be written
+    pub fn columns(mut self, columns: Vec<Column>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Sets what multi-valued fields like hashtags are joined with
+    pub fn multi_value_separator<S: Into<String>>(mut self, separator: S) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// When enabled, line breaks inside CSV values are written as `\n`
+    /// and `\r`, and backslashes as `\\`, so that every tweet occupies
+    /// exactly one line and the values can be unescaped again. TSV
+    /// output always escapes them since it has no quoting.
+    pub fn escape_newlines(mut self, escape: bool) -> Self {
+        self.escape_newlines = escape;
+        self
+    }
+
+    /// Sets whether a header row is written before the first tweet
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Writes a single tweet as a row
+    pub fn write(&mut self, tweet: &Tweet) -> io::Result<()> {
+        self.write_header()?;
+
+        let row = self.columns
+                      .iter()
+                      .map(|c| c.field.values(tweet).join(&self.separator))
+                      .collect::<Vec<_>>();
+
+        self.write_row(&row)
+    }
+
+    /// Writes every tweet from an iterator
+    pub fn write_all<'a, I>(&mut self, tweets: I) -> io::Result<()>
+        where I: IntoIterator<Item = &'a Tweet>
+    {
+        for tweet in tweets {
+            self.write(tweet)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the header row if it is still pending and flushes the
+    /// underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.write_header()?;
+        self.out.flush()
+    }
+
+    /// Flushes and returns the underlying writer
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self.out)
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        if !self.header {
+            return Ok(());
+        }
+
+        self.header = false;
+        let names = self.columns.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+        self.write_row(&names)
+    }
+
+    fn write_row(&mut self, values: &[String]) -> io::Result<()> {
+        let delimiter = match self.format {
+            Format::Csv => ",",
+            Format::Tsv => "\t",
+        };
+
+        let row = values.iter()
+                        .map(|value| self.escape(value))
+                        .collect::<Vec<_>>()
+                        .join(delimiter);
+
+        match self.format {
+            Format::Csv => write!(self.out, "{}\r\n", row),
+            Format::Tsv => writeln!(self.out, "{}", row),
+        }
+    }
+
+    fn escape(&self, value: &str) -> String {
+        match self.format {
+            Format::Csv => {
+                let value = if self.escape_newlines {
+                    value.replace('\\', "\\\\").replace('\r', "\\r").replace('\n', "\\n")
+                } else {
+                    value.to_string()
+                };
+
+                if value.contains(&[',', '"', '\r', '\n'][..]) {
+                    format!("\"{}\"", value.replace('"', "\"\""))
+                } else {
+                    value
+                }
+            }
+            Format::Tsv => value.replace('\\', "\\\\")
+                                .replace('\t', "\\t")
+                                .replace('\r', "\\r")
+                                .replace('\n', "\\n"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<Column> {
+        vec!["id".parse().unwrap(), "text".parse().unwrap()]
+    }
+
+    #[test]
+    fn empty_export_has_header() {
+        let csv = TableWriter::new(Vec::new(), Format::Csv).columns(columns()).into_inner().unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "id,text\r\n");
+
+        let mut writer = TableWriter::new(Vec::new(), Format::Tsv).columns(columns());
+        writer.flush().unwrap();
+        writer.flush().unwrap();
+        assert_eq!(String::from_utf8(writer.into_inner().unwrap()).unwrap(), "id\ttext\n");
+    }
+
+    #[test]
+    fn escaped_newlines_are_reversible() {
+        let tweet = Tweet {
+            id: 1,
+            text: "a\\nb\nc".to_string(),
+            ..Default::default()
+        };
+
+        let mut writer = TableWriter::new(Vec::new(), Format::Csv)
+            .columns(columns())
+            .header(false)
+            .escape_newlines(true);
+        writer.write(&tweet).unwrap();
+
+        let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(csv, "1,a\\\\nb\\nc\r\n");
+    }
+
+    #[test]
+    fn twarc_text_is_untruncated() {
+        let tweet = Tweet {
+            id: 2,
+            text: "RT @a: short…".to_string(),
+            retweeted_status: Some(Box::new(Tweet {
+                id: 1,
+                text: "short and long".to_string(),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+
+        let mut writer = TableWriter::new(Vec::new(), Format::Tsv)
+            .columns(vec!["text".parse().unwrap(), "truncated_text".parse().unwrap()])
+            .header(false);
+        writer.write(&tweet).unwrap();
+
+        let tsv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(tsv, "short and long\tRT @a: short…\n");
+    }
+}
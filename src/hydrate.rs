@@ -0,0 +1,338 @@
+//! Dehydration and rehydration of tweet datasets.
+//!
+//! Twitter only allows sharing tweet ids, so datasets are distributed
+//! as "dehydrated" id lists. Rehydrating looks the ids up again through
+//! a `Fetcher` and reports which ones could not be retrieved.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use crate::model::v2;
+use crate::model::Tweet;
+
+/// Collects the sorted, deduplicated ids of tweets
+pub fn dehydrate<'a, I>(tweets: I) -> Vec<u64>
+    where I: IntoIterator<Item = &'a Tweet>
+{
+    tweets.into_iter()
+          .map(|tweet| tweet.id)
+          .collect::<BTreeSet<_>>()
+          .into_iter()
+          .collect()
+}
+
+/// Writes ids one per line, which is how id datasets are distributed
+pub fn write_ids<W: Write>(mut out: W, ids: &[u64]) -> io::Result<()> {
+    for id in ids {
+        writeln!(out, "{}", id)?;
+    }
+
+    out.flush()
+}
+
+/// Reads ids written one per line. Blank lines are skipped.
+pub fn read_ids<R: BufRead>(input: R) -> io::Result<Vec<u64>> {
+    let mut ids = Vec::new();
+
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let id = line.parse()
+                     .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid tweet id: {}", line)))?;
+        ids.push(id);
+    }
+
+    Ok(ids)
+}
+
+/// A response from one of the tweet lookup endpoints
+#[derive(Debug)]
+pub enum LookupResponse {
+    /// `statuses/lookup`, which only returns the tweets it found
+    V1(Vec<Tweet>),
+    /// `statuses/lookup` with `map=true`, where unavailable tweets are `null`
+    V1Map(HashMap<String, Option<Tweet>>),
+    /// `/2/tweets`, which explains missing tweets in `errors`
+    V2(v2::Response<Vec<v2::Tweet>>),
+}
+
+impl FromStr for LookupResponse {
+    type Err = serde_json::error::Error;
+
+    /// Parses the body of either lookup endpoint
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        #[derive(serde_derive::Deserialize)]
+        struct Mapped {
+            id: HashMap<String, Option<Tweet>>,
+        }
+
+        if s.trim_start().starts_with('[') {
+            return serde_json::from_str(s).map(LookupResponse::V1);
+        }
+
+        let value: serde_json::Value = serde_json::from_str(s)?;
+
+        if value.get("id").is_some_and(|id| id.is_object()) {
+            serde_json::from_value::<Mapped>(value).map(|mapped| LookupResponse::V1Map(mapped.id))
+        } else {
+            serde_json::from_value(value).map(LookupResponse::V2)
+        }
+    }
+}
+
+/// Looks up batches of tweet ids. Implement this over an HTTP client
+/// to rehydrate from the API, or over local data in tests.
+pub trait Fetcher {
+    /// Error returned when a lookup fails entirely
+    type Error;
+
+    /// How many ids can be looked up at once. Both lookup endpoints allow 100.
+    fn batch_size(&self) -> usize {
+        100
+    }
+
+    /// Looks up a batch of ids
+    fn fetch(&mut self, ids: &[u64]) -> Result<LookupResponse, Self::Error>;
+}
+
+/// Why a tweet could or could not be rehydrated
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Status {
+    /// The tweet was retrieved
+    Hydrated,
+    /// The tweet no longer exists
+    Deleted,
+    /// The tweet exists but may not be viewed, usually because the author is protected
+    Protected,
+    /// The tweet was explicitly reported as unavailable without a reason.
+    /// `statuses/lookup` with `map=true` does this for deleted tweets as
+    /// well as ones that are protected, withheld or from suspended users.
+    Unavailable,
+    /// The tweet was not returned and no reason was given
+    Missing,
+}
+
+/// What happened to each id during rehydration
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Report {
+    /// Ids of tweets that were retrieved
+    pub hydrated: Vec<u64>,
+    /// Ids of tweets that were deleted
+    pub deleted: Vec<u64>,
+    /// Ids of tweets that may not be viewed
+    pub protected: Vec<u64>,
+    /// Ids of tweets reported as unavailable without a reason
+    pub unavailable: Vec<u64>,
+    /// Ids of tweets that were not returned for unknown reasons
+    pub missing: Vec<u64>,
+}
+
+impl Report {
+    fn record(&mut self, id: u64, status: Status) {
+        match status {
+            Status::Hydrated => self.hydrated.push(id),
+            Status::Deleted => self.deleted.push(id),
+            Status::Protected => self.protected.push(id),
+            Status::Unavailable => self.unavailable.push(id),
+            Status::Missing => self.missing.push(id),
+        }
+    }
+}
+
+/// The result of rehydrating a list of ids
+#[derive(Debug, Default)]
+pub struct Rehydrated {
+    /// Retrieved tweets, in the order their ids were given
+    pub tweets: Vec<Tweet>,
+    /// What happened to each id
+    pub report: Report,
+}
+
+/// Looks up every id in batches and merges the responses into `Tweet`s.
+/// Duplicate ids are only looked up once.
+pub fn rehydrate<F: Fetcher>(fetcher: &mut F, ids: &[u64]) -> Result<Rehydrated, F::Error> {
+    let mut seen = BTreeSet::new();
+    let ids = ids.iter().cloned().filter(|id| seen.insert(*id)).collect::<Vec<_>>();
+
+    let mut result = Rehydrated::default();
+
+    for batch in ids.chunks(fetcher.batch_size().max(1)) {
+        let (mut tweets, statuses) = merge(fetcher.fetch(batch)?);
+
+        for id in batch {
+            let status = statuses.get(id).cloned().unwrap_or(Status::Missing);
+
+            if status == Status::Hydrated {
+                if let Some(tweet) = tweets.remove(id) {
+                    result.tweets.push(tweet);
+                }
+            }
+
+            result.report.record(*id, status);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Splits a response into the tweets it contains and the status of every id it mentions
+fn merge(response: LookupResponse) -> (HashMap<u64, Tweet>, HashMap<u64, Status>) {
+    let mut tweets = HashMap::new();
+    let mut statuses = HashMap::new();
+
+    match response {
+        LookupResponse::V1(found) => {
+            for tweet in found {
+                statuses.insert(tweet.id, Status::Hydrated);
+                tweets.insert(tweet.id, tweet);
+            }
+        }
+        LookupResponse::V1Map(map) => {
+            for (id, tweet) in map {
+                let id = match id.parse() {
+                    Ok(id) => id,
+                    Err(_) => continue,
+                };
+
+                match tweet {
+                    Some(tweet) => {
+                        statuses.insert(id, Status::Hydrated);
+                        tweets.insert(id, tweet);
+                    }
+                    None => {
+                        statuses.insert(id, Status::Unavailable);
+                    }
+                }
+            }
+        }
+        LookupResponse::V2(response) => {
+            for tweet in response.data.iter().flatten() {
+                statuses.insert(tweet.id, Status::Hydrated);
+                tweets.insert(tweet.id, tweet.to_v1(&response.includes));
+            }
+
+            for problem in &response.errors {
+                let id = match problem.resource_id.as_ref().and_then(|id| id.parse().ok()) {
+                    Some(id) => id,
+                    None => continue,
+                };
+
+                let status = if problem.is_not_found() {
+                    Status::Deleted
+                } else if problem.is_not_authorized() {
+                    Status::Protected
+                } else {
+                    Status::Missing
+                };
+
+                statuses.entry(id).or_insert(status);
+            }
+        }
+    }
+
+    (tweets, statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers lookups from canned bodies, remembering the requested batches
+    struct Canned {
+        responses: Vec<String>,
+        batches: Vec<Vec<u64>>,
+    }
+
+    impl Fetcher for Canned {
+        type Error = serde_json::Error;
+
+        fn batch_size(&self) -> usize {
+            2
+        }
+
+        fn fetch(&mut self, ids: &[u64]) -> Result<LookupResponse, Self::Error> {
+            self.batches.push(ids.to_vec());
+            self.responses.remove(0).parse()
+        }
+    }
+
+    fn canned(responses: &[&str]) -> Canned {
+        Canned {
+            responses: responses.iter().map(|r| r.to_string()).collect(),
+            batches: Vec::new(),
+        }
+    }
+
+    /// A `map=true` lookup of ids 2 and 3 where only 3 was found
+    fn v1_map() -> String {
+        let tweet = Tweet { id: 3, id_str: "3".to_string(), ..Default::default() };
+        format!(r#"{{"id":{{"2":null,"3":{}}}}}"#, serde_json::to_string(&tweet).unwrap())
+    }
+
+    const V2: &str = r#"{
+        "data": [{
+            "id": "4",
+            "text": "four https://t.co/a",
+            "author_id": "10",
+            "attachments": {"media_keys": ["3_40"]},
+            "entities": {"urls": [{"start": 5, "end": 19, "url": "https://t.co/a", "display_url": "pic.twitter.com/a", "media_key": "3_40"}]}
+        }],
+        "includes": {
+            "media": [{"media_key": "3_40", "type": "photo", "url": "https://pbs.twimg.com/media/a.jpg", "width": 1600, "height": 900}]
+        },
+        "errors": [
+            {"title": "Not Found Error", "resource_id": "5", "type": "https://api.twitter.com/2/problems/resource-not-found"},
+            {"title": "Authorization Error", "resource_id": "6", "type": "https://api.twitter.com/2/problems/not-authorized-for-resource"}
+        ]
+    }"#;
+
+    #[test]
+    fn lookups_are_batched_and_classified() {
+        let mut fetcher = canned(&["[]", &v1_map()]);
+        let result = rehydrate(&mut fetcher, &[1, 2, 2, 3]).unwrap();
+
+        assert_eq!(fetcher.batches, vec![vec![1, 2], vec![3]]);
+        assert_eq!(result.tweets.iter().map(|t| t.id).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(result.report, Report {
+            hydrated: vec![3],
+            missing: vec![1, 2],
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn nulls_in_v1_map_are_unavailable() {
+        let mut fetcher = canned(&[&v1_map()]);
+        let result = rehydrate(&mut fetcher, &[2, 3]).unwrap();
+
+        assert_eq!(result.report.hydrated, vec![3]);
+        assert_eq!(result.report.unavailable, vec![2]);
+        assert!(result.report.missing.is_empty());
+    }
+
+    #[test]
+    fn v2_problems_and_media_are_kept() {
+        let mut fetcher = canned(&[V2, V2]);
+        let result = rehydrate(&mut fetcher, &[4, 5, 6, 7]).unwrap();
+
+        assert_eq!(result.report, Report {
+            hydrated: vec![4],
+            deleted: vec![5],
+            protected: vec![6],
+            unavailable: vec![],
+            missing: vec![7],
+        });
+
+        let media = &result.tweets[0].extended_entities.as_ref().unwrap().media[0];
+        assert_eq!(media.id, 40);
+        assert_eq!(media.media_url_https, "https://pbs.twimg.com/media/a.jpg");
+        assert_eq!(media.url, "https://t.co/a");
+        assert_eq!(media.indices, vec![5, 19]);
+    }
+}
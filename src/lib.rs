@@ -3,6 +3,23 @@ use std::str::FromStr;
 
 mod model;
 mod util;
+pub mod archive;
+pub mod auth;
+pub mod client;
+pub mod compliance;
+pub mod export;
+pub mod hydrate;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod normalize;
+pub mod paginate;
+pub mod rate_limit;
+pub mod redact;
+pub mod replay;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stream;
+pub mod webhook;
 pub use crate::model::*;
 
 impl FromStr for TwitterResponse {
@@ -13,9 +30,19 @@ impl FromStr for TwitterResponse {
     }
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum TwitterResponse {
     Tweet(Tweet),
     Limit(Limit),
+    Delete(Delete),
+    ScrubGeo(ScrubGeo),
+    StatusWithheld(StatusWithheld),
+    UserWithheld(UserWithheld),
+    Friends(Friends),
+    Event(UserStreamEvent),
+    Disconnect(Disconnect),
+    Warning(Warning),
+    Error(TwitterError),
 }
\ No newline at end of file
@@ -0,0 +1,134 @@
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+use crate::model::v2;
+use crate::model::{Tweet, User};
+use crate::util::snowflake::snowflake_id;
+
+/// The tweets and users the server knows about
+#[derive(Debug, Default)]
+pub(crate) struct Store {
+    pub(crate) tweets: BTreeMap<u64, Tweet>,
+    pub(crate) users: BTreeMap<u64, User>,
+}
+
+impl Store {
+    pub(crate) fn add_tweet(&mut self, tweet: Tweet) {
+        self.users.entry(tweet.user.id).or_insert_with(|| tweet.user.clone());
+        self.tweets.insert(tweet.id, tweet);
+    }
+
+    pub(crate) fn add_user(&mut self, user: User) {
+        self.users.insert(user.id, user);
+    }
+
+    /// Adds `users` users and `tweets` tweets spread over them, one
+    /// tweet per minute going back from now
+    pub(crate) fn generate(&mut self, tweets: usize, users: usize) {
+        let first_user = self.users.keys().next_back().map_or(1, |id| id + 1);
+        let generated = (0..users.max(1) as u64).map(|i| generate_user(first_user + i)).collect::<Vec<_>>();
+        let now = Utc::now();
+
+        for i in 0..tweets {
+            let user = generated[i % generated.len()].clone();
+            let created_at = now - Duration::minutes(i as i64);
+            let id = snowflake_id(created_at, i as u64);
+
+            self.add_tweet(Tweet {
+                created_at,
+                id,
+                id_str: id.to_string(),
+                text: format!("Mock tweet {} from @{} #mock", i, user.screen_name),
+                source: "<a href=\"https://github.com/Roughsketch/tweet\" rel=\"nofollow\">tweet mock</a>".to_string(),
+                user,
+                lang: Some("en".to_string()),
+                favorite_count: Some(0),
+                ..Default::default()
+            });
+        }
+
+        for user in generated {
+            self.add_user(user);
+        }
+    }
+
+    pub(crate) fn user_by_name(&self, screen_name: &str) -> Option<&User> {
+        self.users.values().find(|user| user.screen_name.eq_ignore_ascii_case(screen_name))
+    }
+
+    /// Tweets newest first
+    pub(crate) fn newest_first(&self) -> impl Iterator<Item = &Tweet> {
+        self.tweets.values().rev()
+    }
+}
+
+fn generate_user(id: u64) -> User {
+    User {
+        id,
+        id_str: id.to_string(),
+        name: format!("Mock User {}", id),
+        screen_name: format!("mock_user_{}", id),
+        created_at: Utc::now() - Duration::days(365),
+        profile_image_url_https: "https://abs.twimg.com/sticky/default_profile_images/default_profile_normal.png".to_string(),
+        default_profile: true,
+        default_profile_image: true,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn v1(value: &impl serde::Serialize) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// The v2 representation of a tweet with the default fields and
+/// the ones this crate can derive from v1.1
+pub(crate) fn v2_tweet(tweet: &Tweet) -> Value {
+    v1(&v2::Tweet {
+        id: tweet.id,
+        text: tweet.full_text(),
+        author_id: Some(tweet.user.id),
+        created_at: Some(tweet.created_at),
+        conversation_id: Some(tweet.in_reply_to_status_id.unwrap_or(tweet.id)),
+        in_reply_to_user_id: tweet.in_reply_to_user_id,
+        lang: tweet.lang.clone(),
+        possibly_sensitive: tweet.possibly_sensitive,
+        edit_history_tweet_ids: Some(vec![tweet.id_str.clone()]),
+        ..Default::default()
+    })
+}
+
+/// The v2 representation of a user
+pub(crate) fn v2_user(user: &User) -> Value {
+    v1(&v2::User {
+        id: user.id,
+        name: user.name.clone(),
+        username: user.screen_name.clone(),
+        created_at: Some(user.created_at),
+        description: user.description.clone(),
+        location: user.location.clone(),
+        url: user.url.clone(),
+        protected: Some(user.protected),
+        verified: Some(user.verified),
+        profile_image_url: Some(user.profile_image_url_https.clone()),
+        ..Default::default()
+    })
+}
+
+/// The problem v2 reports for an id that does not exist
+pub(crate) fn not_found(resource_type: &str, parameter: &str, value: &str) -> Value {
+    json!({
+        "value": value,
+        "detail": format!("Could not find {} with {}: [{}].", resource_type, parameter, value),
+        "title": "Not Found Error",
+        "resource_type": resource_type,
+        "parameter": parameter,
+        "resource_id": value,
+        "type": "https://api.twitter.com/2/problems/resource-not-found",
+    })
+}
+
+/// A v1.1 error body
+pub(crate) fn error(code: u32, message: &str) -> Value {
+    json!({ "errors": [{ "code": code, "message": message }] })
+}
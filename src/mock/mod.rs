@@ -0,0 +1,484 @@
+//! A local server that imitates the REST and streaming APIs, for
+//! integration tests that should not talk to Twitter.
+//!
+//! `MockServer` listens on a random local port and answers the v1.1
+//! and v2 tweet and user lookups, timelines and search from the tweets
+//! and users it was given or generated. The streaming endpoints send
+//! those tweets with fresh ids at the rate of a `StreamConfig`, along
+//! with limit notices, deletes and keep-alives. Errors, rate limits
+//! and dropped connections can be injected per path.
+//!
+//! The server is only meant for tests. It answers one request per
+//! connection and handles each connection on its own thread.
+
+mod data;
+mod routes;
+mod stream;
+
+pub use stream::StreamConfig;
+
+use chrono::Utc;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use crate::auth::form_decode;
+use crate::model::{Tweet, User};
+use data::{error, Store};
+
+/// Length of a rate limit window
+const WINDOW_SECS: i64 = 15 * 60;
+
+/// A failure to inject into the responses of a path
+#[derive(Clone, Debug)]
+pub enum Fault {
+    /// Answers with this status and a v1.1 error body
+    Error {
+        /// The HTTP status
+        status: u16,
+        /// The error code of the body
+        code: u32,
+        /// The message of the body
+        message: String,
+    },
+    /// Answers with this status and an empty body
+    Status(u16),
+    /// Closes the connection without answering
+    Disconnect,
+}
+
+/// A request the server received
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    /// The method, like `GET`
+    pub method: String,
+    /// The path without the query
+    pub path: String,
+    /// The query and form body parameters, decoded
+    pub params: Vec<(String, String)>,
+    /// The headers, with lowercase names
+    pub headers: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+struct Injected {
+    fault: Fault,
+    remaining: Option<usize>,
+}
+
+#[derive(Debug)]
+struct RateLimit {
+    limit: u32,
+    used: u32,
+    reset: i64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    faults: HashMap<String, Injected>,
+    limits: HashMap<String, RateLimit>,
+    requests: Vec<RecordedRequest>,
+    stream: StreamConfig,
+}
+
+/// Imitates the Twitter API on a local port. Shuts down when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    store: Arc<Mutex<Store>>,
+    state: Arc<Mutex<State>>,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Starts a server without any tweets or users on a random port
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let store = Arc::new(Mutex::new(Store::default()));
+        let state = Arc::new(Mutex::new(State::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let store = store.clone();
+            let state = state.clone();
+            let shutdown = shutdown.clone();
+
+            thread::spawn(move || {
+                for connection in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+
+                    if let Ok(connection) = connection {
+                        let store = store.clone();
+                        let state = state.clone();
+                        let shutdown = shutdown.clone();
+
+                        thread::spawn(move || {
+                            //  The client may go away at any time
+                            let _ = handle(connection, &store, &state, &shutdown);
+                        });
+                    }
+                }
+            })
+        };
+
+        Ok(MockServer {
+            addr,
+            store,
+            state,
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+
+    /// The base URL to point a client at, like `http://127.0.0.1:41234`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// The address the server listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Serves a tweet and its author
+    pub fn add_tweet(&self, tweet: Tweet) {
+        lock(&self.store).add_tweet(tweet);
+    }
+
+    /// Serves a user
+    pub fn add_user(&self, user: User) {
+        lock(&self.store).add_user(user);
+    }
+
+    /// Generates `tweets` tweets by `users` new users, one tweet per
+    /// minute going back from now
+    pub fn generate(&self, tweets: usize, users: usize) {
+        lock(&self.store).generate(tweets, users);
+    }
+
+    /// Sets what new connections to the streaming endpoints receive
+    pub fn set_stream(&self, config: StreamConfig) {
+        lock(&self.state).stream = config;
+    }
+
+    /// Answers requests to `path`, like `/1.1/statuses/show.json`, with
+    /// a fault. `times` limits how many requests fail, forever if `None`.
+    pub fn inject(&self, path: &str, fault: Fault, times: Option<usize>) {
+        lock(&self.state).faults.insert(normalize(path), Injected {
+            fault,
+            remaining: times,
+        });
+    }
+
+    /// Allows `limit` requests to `path` per 15 minute window and
+    /// answers with HTTP 429 after that. Every response of the path
+    /// carries the `x-rate-limit-*` headers.
+    pub fn rate_limit(&self, path: &str, limit: u32) {
+        lock(&self.state).limits.insert(normalize(path), RateLimit {
+            limit,
+            used: 0,
+            reset: Utc::now().timestamp() + WINDOW_SECS,
+        });
+    }
+
+    /// Removes every injected fault and rate limit
+    pub fn clear_faults(&self) {
+        let mut state = lock(&self.state);
+        state.faults.clear();
+        state.limits.clear();
+    }
+
+    /// Every request received so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        lock(&self.state).requests.clone()
+    }
+
+    /// Stops accepting connections and ends open streams
+    pub fn shutdown(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+
+        //  Wakes up the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.addr);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn normalize(path: &str) -> String {
+    format!("/{}", path.split('?').next().unwrap_or_default().trim_matches('/'))
+}
+
+fn handle(connection: TcpStream, store: &Arc<Mutex<Store>>, state: &Mutex<State>, shutdown: &AtomicBool) -> io::Result<()> {
+    let mut reader = BufReader::new(connection.try_clone()?);
+    let mut out = connection;
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        if let Some(at) = line.find(':') {
+            headers.push((line[..at].trim().to_ascii_lowercase(), line[at + 1..].trim().to_string()));
+        }
+    }
+
+    let length = headers.iter()
+                        .find(|(name, _)| name == "content-length")
+                        .and_then(|(_, value)| value.parse().ok())
+                        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    let path = normalize(&target);
+    let mut params = target.find('?').map(|at| form_decode(&target[at + 1..])).unwrap_or_default();
+    params.extend(form_decode(&String::from_utf8_lossy(&body)));
+
+    let mut extra = Vec::new();
+    let stream_config = {
+        let mut state = lock(state);
+        state.requests.push(RecordedRequest {
+            method,
+            path: path.clone(),
+            params: params.clone(),
+            headers,
+        });
+
+        let fault = match state.faults.get_mut(&path) {
+            Some(injected) if injected.remaining != Some(0) => {
+                injected.remaining = injected.remaining.map(|remaining| remaining - 1);
+                Some(injected.fault.clone())
+            }
+            _ => None,
+        };
+
+        match fault {
+            Some(Fault::Disconnect) => return Ok(()),
+            Some(Fault::Error { status, code, message }) => {
+                return respond(&mut out, status, &[], &error(code, &message).to_string());
+            }
+            Some(Fault::Status(status)) => return respond(&mut out, status, &[], ""),
+            None => {}
+        }
+
+        if let Some(limit) = state.limits.get_mut(&path) {
+            let now = Utc::now().timestamp();
+
+            if now >= limit.reset {
+                limit.used = 0;
+                limit.reset = now + WINDOW_SECS;
+            }
+
+            limit.used += 1;
+            extra.push(("x-rate-limit-limit", limit.limit.to_string()));
+            extra.push(("x-rate-limit-remaining", limit.limit.saturating_sub(limit.used).to_string()));
+            extra.push(("x-rate-limit-reset", limit.reset.to_string()));
+
+            if limit.used > limit.limit {
+                let body = error(88, "Rate limit exceeded").to_string();
+                return respond(&mut out, 429, &extra, &body);
+            }
+        }
+
+        state.stream.clone()
+    };
+
+    if let Some(v2) = stream::stream_kind(&path) {
+        return stream::serve(&mut out, &path, v2, &stream_config, store, shutdown);
+    }
+
+    let (status, body) = routes::route(&lock(store), &path, &params);
+    respond(&mut out, status, &extra, &body.to_string())
+}
+
+fn respond(out: &mut TcpStream, status: u16, headers: &[(&str, String)], body: &str) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        420 => "Enhance Your Calm",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    };
+
+    write!(out, "HTTP/1.1 {} {}\r\n", status, reason)?;
+    write!(out, "Content-Type: application/json;charset=utf-8\r\n")?;
+    write!(out, "Content-Length: {}\r\n", body.len())?;
+    write!(out, "Connection: close\r\n")?;
+
+    for (name, value) in headers {
+        write!(out, "{}: {}\r\n", name, value)?;
+    }
+
+    write!(out, "\r\n{}", body)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{
+        Auth, Client, Error, Fields, LookupTweets, Method, Request, Response, SearchTweets, ShowTweet, ShowUser,
+        Timeline, TweetsByIds, UserByUsername,
+    };
+
+    /// Sends a request over a plain HTTP/1.1 connection
+    fn http(request: &Request) -> io::Result<Response> {
+        let rest = request.url.trim_start_matches("http://");
+        let (host, target) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let method = match request.method {
+            Method::Get => "GET",
+            Method::Post => "POST",
+        };
+        let body = request.body.clone().unwrap_or_default();
+
+        let mut stream = TcpStream::connect(host)?;
+        write!(stream, "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\n", method, target, host, body.len())?;
+        for (name, value) in &request.headers {
+            write!(stream, "{}: {}\r\n", name, value)?;
+        }
+        write!(stream, "\r\n{}", body)?;
+        stream.flush()?;
+
+        let mut raw = String::new();
+        stream.read_to_string(&mut raw)?;
+
+        let (head, body) = raw.split_once("\r\n\r\n")
+                              .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "no response"))?;
+        let mut lines = head.lines();
+        let status = lines.next().and_then(|line| line.split_whitespace().nth(1)).and_then(|status| status.parse().ok());
+
+        Ok(Response {
+            status: status.unwrap_or_default(),
+            headers: lines.filter_map(|line| line.split_once(':'))
+                          .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                          .collect(),
+            body: body.to_string(),
+        })
+    }
+
+    fn connect(server: &MockServer) -> Client<fn(&Request) -> io::Result<Response>> {
+        Client::new(http as fn(&Request) -> io::Result<Response>, Auth::Bearer("token".to_string())).base_url(&server.url())
+    }
+
+    #[test]
+    fn serves_rest_endpoints() {
+        let server = MockServer::start().unwrap();
+        server.generate(6, 2);
+        let mut client = connect(&server);
+
+        let timeline = client.send(&Timeline::user("mock_user_1")).unwrap();
+        assert_eq!(timeline.len(), 3);
+        assert!(timeline.windows(2).all(|pair| pair[0].id > pair[1].id));
+
+        let tweet = client.send(&ShowTweet { id: timeline[0].id }).unwrap();
+        assert_eq!(tweet.user.screen_name, "mock_user_1");
+
+        let ids = vec![timeline[0].id, 1, timeline[1].id];
+        assert_eq!(client.send(&LookupTweets { ids }).unwrap().len(), 2);
+        assert_eq!(client.send(&ShowUser { user: 2.into() }).unwrap().screen_name, "mock_user_2");
+
+        let search = client.send(&SearchTweets::new("#mock").count(4)).unwrap();
+        assert_eq!(search.statuses.len(), 4);
+
+        match client.send(&ShowTweet { id: 1 }) {
+            Err(Error::Api { status: 404, .. }) => {}
+            other => panic!("expected a 404, got {:?}", other.map(|_| ())),
+        }
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/1.1/statuses/user_timeline.json");
+        assert!(requests[0].headers.contains(&("authorization".to_string(), "Bearer token".to_string())));
+    }
+
+    #[test]
+    fn serves_v2_endpoints() {
+        let server = MockServer::start().unwrap();
+        server.generate(2, 1);
+        let mut client = connect(&server);
+
+        let user = client.send(&UserByUsername { username: "mock_user_1".to_string(), fields: Fields::default() }).unwrap();
+        assert_eq!(user.data.unwrap().id, 1);
+
+        let tweets = client.send(&Timeline::user(1)).unwrap();
+        let lookup = client.send(&TweetsByIds { ids: vec![tweets[0].id, 42], fields: Fields::default() }).unwrap();
+        assert_eq!(lookup.data.unwrap()[0].id, tweets[0].id);
+        assert_eq!(lookup.errors[0].resource_id.as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn enforces_rate_limits() {
+        let server = MockServer::start().unwrap();
+        server.generate(1, 1);
+        server.rate_limit("/1.1/users/show.json", 2);
+        let mut client = connect(&server);
+
+        client.send(&ShowUser { user: 1.into() }).unwrap();
+        assert_eq!(client.rate_limits().remaining("/users/show/:id"), Some(1));
+        client.send(&ShowUser { user: 1.into() }).unwrap();
+
+        //  The client knows the window is used up and does not send a third request
+        assert!(matches!(client.send(&ShowUser { user: 1.into() }), Err(Error::RateLimited(_))));
+        assert_eq!(server.requests().len(), 2);
+
+        //  A new client without that knowledge gets HTTP 429
+        match connect(&server).send(&ShowUser { user: 1.into() }) {
+            Err(Error::RateLimited(wait)) => assert!(wait > std::time::Duration::from_secs(0)),
+            other => panic!("expected a rate limit, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn injects_faults() {
+        let server = MockServer::start().unwrap();
+        server.generate(1, 1);
+        let mut client = connect(&server);
+
+        let fault = Fault::Error { status: 503, code: 130, message: "Over capacity".to_string() };
+        server.inject("/1.1/users/show.json", fault, Some(1));
+
+        match client.send(&ShowUser { user: 1.into() }) {
+            Err(error @ Error::Api { status: 503, .. }) => assert!(error.is_retryable()),
+            other => panic!("expected an api error, got {:?}", other.map(|_| ())),
+        }
+        assert_eq!(client.send(&ShowUser { user: 1.into() }).unwrap().id, 1);
+
+        server.inject("/1.1/users/show.json", Fault::Disconnect, None);
+        assert!(matches!(client.send(&ShowUser { user: 1.into() }), Err(Error::Transport(_))));
+
+        server.inject("/1.1/users/show.json", Fault::Status(500), None);
+        assert!(matches!(client.send(&ShowUser { user: 1.into() }), Err(Error::Http { status: 500, .. })));
+
+        server.clear_faults();
+        assert!(client.send(&ShowUser { user: 1.into() }).is_ok());
+    }
+}
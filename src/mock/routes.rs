@@ -0,0 +1,132 @@
+use serde_json::{json, Value};
+
+use crate::auth::percent_encode;
+use crate::mock::data::{error, not_found, v1, v2_tweet, v2_user, Store};
+use crate::model::{Tweet, User};
+
+/// Answers a REST request from the store. Returns the status and body.
+pub(crate) fn route(store: &Store, path: &str, params: &[(String, String)]) -> (u16, Value) {
+    let param = |name: &str| params.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str());
+    let ids = |name: &str| -> Vec<u64> {
+        param(name).map(|ids| ids.split(',').filter_map(|id| id.trim().parse().ok()).collect()).unwrap_or_default()
+    };
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+
+    match segments.as_slice() {
+        ["1.1", "statuses", "show.json"] => {
+            match param("id").and_then(|id| id.parse().ok()).and_then(|id| store.tweets.get(&id)) {
+                Some(tweet) => (200, v1(tweet)),
+                None => (404, error(144, "No status found with that ID.")),
+            }
+        }
+        ["1.1", "statuses", "lookup.json"] => {
+            let tweets = ids("id").iter().filter_map(|id| store.tweets.get(id)).map(v1).collect();
+            (200, Value::Array(tweets))
+        }
+        ["1.1", "users", "show.json"] => match find_user(store, param("user_id"), param("screen_name")) {
+            Some(user) => (200, v1(user)),
+            None => (404, error(50, "User not found.")),
+        },
+        ["1.1", "statuses", "user_timeline.json"] => {
+            let user = match find_user(store, param("user_id"), param("screen_name")) {
+                Some(user) => user.id,
+                None => return (404, error(34, "Sorry, that page does not exist.")),
+            };
+            let tweets = store.newest_first().filter(|tweet| tweet.user.id == user);
+            (200, Value::Array(page(tweets, params, 20, 200)))
+        }
+        ["1.1", "statuses", "home_timeline.json"] | ["1.1", "statuses", "mentions_timeline.json"] => {
+            (200, Value::Array(page(store.newest_first(), params, 20, 200)))
+        }
+        ["1.1", "search", "tweets.json"] => {
+            let query = param("q").unwrap_or_default();
+            let terms = query.split_whitespace().map(str::to_lowercase).collect::<Vec<_>>();
+            let matches = store.newest_first().filter(|tweet| {
+                let text = tweet.full_text().to_lowercase();
+                terms.iter().all(|term| text.contains(term.as_str()))
+            });
+            let statuses = page(matches, params, 15, 100);
+            let max_id = param("max_id").and_then(|id| id.parse().ok())
+                                        .or_else(|| statuses.first().and_then(|tweet| tweet["id"].as_u64()))
+                                        .unwrap_or_default();
+            let since_id = param("since_id").and_then(|id| id.parse::<u64>().ok()).unwrap_or_default();
+            let next_results = statuses.last()
+                                       .and_then(|tweet| tweet["id"].as_u64())
+                                       .map(|oldest| format!("?max_id={}&q={}", oldest.saturating_sub(1), percent_encode(query)));
+
+            (200, json!({
+                "statuses": statuses,
+                "search_metadata": {
+                    "completed_in": 0.001,
+                    "max_id": max_id,
+                    "max_id_str": max_id.to_string(),
+                    "next_results": next_results,
+                    "query": percent_encode(query),
+                    "count": statuses.len(),
+                    "since_id": since_id,
+                    "since_id_str": since_id.to_string(),
+                },
+            }))
+        }
+        ["2", "tweets"] => {
+            let (found, missing): (Vec<_>, Vec<_>) = ids("ids").into_iter().partition(|id| store.tweets.contains_key(id));
+            lookup(found.iter().map(|id| v2_tweet(&store.tweets[id])).collect(),
+                   missing.iter().map(|id| not_found("tweet", "ids", &id.to_string())).collect())
+        }
+        ["2", "tweets", id] => match id.parse().ok().and_then(|id| store.tweets.get(&id)) {
+            Some(tweet) => (200, json!({ "data": v2_tweet(tweet) })),
+            None => (200, json!({ "errors": [not_found("tweet", "id", id)] })),
+        },
+        ["2", "users"] => {
+            let (found, missing): (Vec<_>, Vec<_>) = ids("ids").into_iter().partition(|id| store.users.contains_key(id));
+            lookup(found.iter().map(|id| v2_user(&store.users[id])).collect(),
+                   missing.iter().map(|id| not_found("user", "ids", &id.to_string())).collect())
+        }
+        ["2", "users", "by", "username", username] => match store.user_by_name(username) {
+            Some(user) => (200, json!({ "data": v2_user(user) })),
+            None => (200, json!({ "errors": [not_found("user", "username", username)] })),
+        },
+        ["2", "users", id] => match id.parse().ok().and_then(|id| store.users.get(&id)) {
+            Some(user) => (200, json!({ "data": v2_user(user) })),
+            None => (200, json!({ "errors": [not_found("user", "id", id)] })),
+        },
+        _ => (404, error(34, "Sorry, that page does not exist.")),
+    }
+}
+
+fn find_user<'a>(store: &'a Store, user_id: Option<&str>, screen_name: Option<&str>) -> Option<&'a User> {
+    match (user_id.and_then(|id| id.parse().ok()), screen_name) {
+        (Some(id), _) => store.users.get(&id),
+        (None, Some(name)) => store.user_by_name(name.trim_start_matches('@')),
+        (None, None) => None,
+    }
+}
+
+/// Applies `count`, `since_id` and `max_id` to tweets ordered newest first
+fn page<'a, I>(tweets: I, params: &[(String, String)], default_count: usize, max_count: usize) -> Vec<Value>
+    where I: Iterator<Item = &'a Tweet>
+{
+    let param = |name: &str| params.iter().find(|(key, _)| key == name).and_then(|(_, value)| value.parse::<u64>().ok());
+    let count = param("count").map_or(default_count, |count| (count as usize).min(max_count));
+    let since_id = param("since_id").unwrap_or(0);
+    let max_id = param("max_id").unwrap_or(u64::MAX);
+
+    tweets.filter(|tweet| tweet.id > since_id && tweet.id <= max_id)
+          .take(count)
+          .map(v1)
+          .collect()
+}
+
+fn lookup(data: Vec<Value>, errors: Vec<Value>) -> (u16, Value) {
+    let mut body = json!({});
+
+    if !data.is_empty() {
+        body["data"] = Value::Array(data);
+    }
+
+    if !errors.is_empty() {
+        body["errors"] = Value::Array(errors);
+    }
+
+    (200, body)
+}
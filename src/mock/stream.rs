@@ -0,0 +1,179 @@
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::mock::data::{v1, v2_tweet, Store};
+use crate::mock::lock;
+use crate::util::snowflake::snowflake_id;
+
+/// What the streaming endpoints send and how fast
+#[derive(Clone, Debug)]
+pub struct StreamConfig {
+    /// How many tweets are sent per second. No tweets are sent at 0.
+    pub tweets_per_second: f64,
+    /// How long to wait without data before sending a keep-alive newline
+    pub keep_alive: Duration,
+    /// Sends a limit notice after every this many tweets, counting as
+    /// many undelivered tweets
+    pub limit_every: Option<u64>,
+    /// Sends a delete notice for the latest tweet after every this many tweets
+    pub delete_every: Option<u64>,
+    /// Sends a disconnect message with `disconnect_code` and closes the
+    /// stream after this many tweets
+    pub disconnect_after: Option<u64>,
+    /// Code of the disconnect message
+    pub disconnect_code: u32,
+    /// Ends the stream normally after this many tweets
+    pub max_tweets: Option<u64>,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        StreamConfig {
+            tweets_per_second: 10.0,
+            keep_alive: Duration::from_secs(30),
+            limit_every: None,
+            delete_every: None,
+            disconnect_after: None,
+            disconnect_code: 4,
+            max_tweets: None,
+        }
+    }
+}
+
+/// Whether a path is one of the streaming endpoints, and whether it is v2
+pub(crate) fn stream_kind(path: &str) -> Option<bool> {
+    match path.trim_matches('/') {
+        "1.1/statuses/filter.json" | "1.1/statuses/sample.json" => Some(false),
+        "2/tweets/search/stream" | "2/tweets/sample/stream" => Some(true),
+        _ => None,
+    }
+}
+
+/// Writes a chunked stream until it ends, the client goes away or the
+/// server shuts down
+pub(crate) fn serve<W: Write>(out: &mut W,
+                              path: &str,
+                              v2: bool,
+                              config: &StreamConfig,
+                              store: &Arc<Mutex<Store>>,
+                              shutdown: &AtomicBool) -> io::Result<()> {
+    write!(out, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n")?;
+    out.flush()?;
+
+    let interval = if config.tweets_per_second > 0.0 {
+        Some(Duration::from_secs_f64(1.0 / config.tweets_per_second))
+    } else {
+        None
+    };
+
+    let stream_name = path.trim_matches('/').to_string();
+    let started = Instant::now();
+    let mut last_write = started;
+    let mut sent = 0u64;
+    let mut last_id = None;
+
+    while !shutdown.load(Ordering::SeqCst) {
+        if config.max_tweets.is_some_and(|max| sent >= max) {
+            break;
+        }
+
+        let now = Instant::now();
+        let has_tweets = !lock(store).tweets.is_empty();
+        let next_tweet = interval.filter(|_| has_tweets).map(|interval| started + interval.mul_f64(sent as f64));
+        let next_keep_alive = last_write + config.keep_alive;
+
+        if next_tweet.is_some_and(|due| now >= due) {
+            let (id, message) = next_message(store, sent, v2);
+            chunk(out, &message)?;
+            sent += 1;
+            last_id = id.or(last_id);
+
+            if let Some((id, user_id)) = last_id.filter(|_| config.delete_every.is_some_and(|every| sent % every == 0)) {
+                chunk(out, &json!({
+                    "delete": {
+                        "status": { "id": id, "id_str": id.to_string(), "user_id": user_id, "user_id_str": user_id.to_string() },
+                        "timestamp_ms": Utc::now().timestamp_millis().to_string(),
+                    },
+                }))?;
+            }
+
+            if let Some(every) = config.limit_every.filter(|every| sent % every == 0) {
+                chunk(out, &json!({
+                    "limit": { "track": sent / every * every, "timestamp_ms": Utc::now().timestamp_millis().to_string() },
+                }))?;
+            }
+
+            if config.disconnect_after.is_some_and(|after| sent >= after) {
+                chunk(out, &json!({
+                    "disconnect": { "code": config.disconnect_code, "stream_name": stream_name, "reason": "Disconnected by the mock server" },
+                }))?;
+                break;
+            }
+
+            out.flush()?;
+            last_write = Instant::now();
+        } else if now >= next_keep_alive {
+            write_chunk(out, b"\r\n")?;
+            out.flush()?;
+            last_write = now;
+        } else {
+            let due = next_tweet.map_or(next_keep_alive, |due| due.min(next_keep_alive));
+            thread::sleep(due.saturating_duration_since(now).min(Duration::from_millis(50)));
+        }
+    }
+
+    write!(out, "0\r\n\r\n")?;
+    out.flush()
+}
+
+/// A tweet from the store with a fresh id and the current time, along
+/// with the ids of the tweet and its author
+fn next_message(store: &Arc<Mutex<Store>>, sequence: u64, v2: bool) -> (Option<(u64, u64)>, Value) {
+    let store = lock(store);
+    let template = match store.tweets.values().nth(sequence as usize % store.tweets.len().max(1)) {
+        Some(tweet) => tweet,
+        None => return (None, Value::Null),
+    };
+
+    let now = Utc::now();
+    let id = snowflake_id(now, sequence);
+    let user_id = template.user.id;
+
+    let message = if v2 {
+        let mut data = v2_tweet(template);
+        data["id"] = json!(id.to_string());
+        data["created_at"] = json!(now);
+        data["edit_history_tweet_ids"] = json!([id.to_string()]);
+        json!({ "data": data, "matching_rules": [{ "id": "1", "tag": "mock" }] })
+    } else {
+        let mut tweet = v1(template);
+        tweet["id"] = json!(id);
+        tweet["id_str"] = json!(id.to_string());
+        tweet["created_at"] = json!(now.format("%a %b %d %T %z %Y").to_string());
+        tweet["timestamp_ms"] = json!(now.timestamp_millis().to_string());
+        tweet
+    };
+
+    (Some((id, user_id)), message)
+}
+
+fn chunk<W: Write>(out: &mut W, message: &Value) -> io::Result<()> {
+    if message.is_null() {
+        return Ok(());
+    }
+
+    let mut line = message.to_string();
+    line.push_str("\r\n");
+    write_chunk(out, line.as_bytes())
+}
+
+fn write_chunk<W: Write>(out: &mut W, data: &[u8]) -> io::Result<()> {
+    write!(out, "{:x}\r\n", data.len())?;
+    out.write_all(data)?;
+    write!(out, "\r\n")
+}
@@ -0,0 +1,319 @@
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::model::direct_message::{App, DirectMessageEvent, MessageTarget};
+use crate::model::tweet::Tweet;
+use crate::util::datetime::{datefmt_de, datefmt_ser};
+use crate::util::id::{id_de, id_ser, lenient_id_de, opt_id_de, opt_id_ser};
+
+impl FromStr for AccountActivityEvent {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// Represents the body of an Account Activity API webhook request.
+///
+/// Every request is about a single subscribed user and usually only
+/// carries one kind of event, so all other lists are empty.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct AccountActivityEvent {
+    /// Id of the subscribed user the events are about
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub for_user_id: u64,
+    /// Set on `tweet_create_events` when the subscribed user has
+    /// blocked the author of a mention
+    pub user_has_blocked: Option<bool>,
+    /// Tweets, retweets, replies, quotes and mentions involving the user
+    #[serde(default)]
+    pub tweet_create_events: Vec<Tweet>,
+    /// Tweets liked by or liked from the user
+    #[serde(default)]
+    pub favorite_events: Vec<FavoriteEvent>,
+    /// Follows and unfollows by or of the user
+    #[serde(default)]
+    pub follow_events: Vec<RelationshipEvent>,
+    /// Blocks and unblocks by the user
+    #[serde(default)]
+    pub block_events: Vec<RelationshipEvent>,
+    /// Mutes and unmutes by the user
+    #[serde(default)]
+    pub mute_events: Vec<RelationshipEvent>,
+    /// Direct messages sent or received by the user
+    #[serde(default)]
+    pub direct_message_events: Vec<DirectMessageEvent>,
+    /// Typing indicators in conversations with the user
+    #[serde(default)]
+    pub direct_message_indicate_typing_events: Vec<TypingEvent>,
+    /// Read receipts in conversations with the user
+    #[serde(default)]
+    pub direct_message_mark_read_events: Vec<MarkReadEvent>,
+    /// Tweets deleted by the user
+    #[serde(default)]
+    pub tweet_delete_events: Vec<TweetDeleteEvent>,
+    /// Changes to the user's authorization of the app
+    pub user_event: Option<UserEvent>,
+    /// Users involved in `direct_message_events`, keyed by id
+    #[serde(default)]
+    pub users: HashMap<String, ActivityUser>,
+    /// Apps that sent `direct_message_events`, keyed by id
+    #[serde(default)]
+    pub apps: HashMap<String, App>,
+}
+
+/// A tweet being liked
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FavoriteEvent {
+    /// The unique id for the event
+    pub id: String,
+    /// When the tweet was liked
+    #[serde(deserialize_with="datefmt_de", serialize_with="datefmt_ser")]
+    pub created_at: DateTime<Utc>,
+    /// Same as `created_at`, in milliseconds since the epoch
+    pub timestamp_ms: Option<u64>,
+    /// The tweet that was liked
+    pub favorited_status: Tweet,
+    /// The user who liked the tweet
+    pub user: ActivityUser,
+}
+
+/// A follow, block or mute, or the undoing of one
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RelationshipEvent {
+    /// What happened, like `follow`, `unfollow`, `block` or `unmute`
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// When it happened, in milliseconds since the epoch
+    pub created_timestamp: String,
+    /// The user who followed, blocked or muted
+    pub source: ActivityUser,
+    /// The user who was followed, blocked or muted
+    pub target: ActivityUser,
+}
+
+/// A user typing in a direct message conversation
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TypingEvent {
+    /// When the user started typing, in milliseconds since the epoch
+    pub created_timestamp: String,
+    /// Id of the user who is typing
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub sender_id: u64,
+    /// Who the user is typing to
+    pub target: MessageTarget,
+}
+
+/// A user reading a direct message conversation
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MarkReadEvent {
+    /// When the messages were read, in milliseconds since the epoch
+    pub created_timestamp: String,
+    /// Id of the user who read the messages
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub sender_id: u64,
+    /// Whose messages were read
+    pub target: MessageTarget,
+    /// Id of the last message that was read
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub last_read_event_id: u64,
+}
+
+/// A tweet being deleted
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TweetDeleteEvent {
+    /// The tweet that was deleted
+    pub status: ActivityDeletedStatus,
+    /// When the tweet was deleted, in milliseconds since the epoch
+    pub timestamp_ms: Option<String>,
+}
+
+/// Identifies a deleted tweet and its author
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ActivityDeletedStatus {
+    /// Id of the deleted tweet
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub id: u64,
+    /// Id of the user who posted the tweet
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub user_id: u64,
+}
+
+/// A change to the user's authorization of the app
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserEvent {
+    /// Set when the user revoked the app's access
+    pub revoke: Option<Revoke>,
+}
+
+/// The user revoking the app's access
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Revoke {
+    /// When access was revoked
+    pub date_time: DateTime<Utc>,
+    /// The app that lost access
+    pub target: RevokeTarget,
+    /// The user who revoked access
+    pub source: RevokeSource,
+}
+
+/// The app that lost access
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RevokeTarget {
+    /// Id of the app
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub app_id: u64,
+}
+
+/// The user who revoked access
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RevokeSource {
+    /// Id of the user
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub user_id: u64,
+}
+
+/// A user as sent in Account Activity API events. These are shorter
+/// than the users of the REST API and carry no `id_str`, with the id
+/// sent as a string or a number depending on the event.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ActivityUser {
+    /// Unique identifier for the user
+    #[serde(deserialize_with="lenient_id_de", serialize_with="id_ser")]
+    pub id: u64,
+    /// When the account was created, in milliseconds since the epoch
+    pub created_timestamp: Option<String>,
+    /// The name of the user, as they've defined it
+    pub name: String,
+    /// The screen name of the user
+    pub screen_name: String,
+    /// User defined location for the account's profile
+    pub location: Option<String>,
+    /// User defined string describing their account
+    pub description: Option<String>,
+    /// A URL provided by the user in association with their profile
+    pub url: Option<String>,
+    /// When true, indicates that this user has chosen to protect their tweets
+    #[serde(default)]
+    pub protected: bool,
+    /// When true, indicates that the user has a verified account
+    #[serde(default)]
+    pub verified: bool,
+    /// The number of followers this account currently has
+    #[serde(default)]
+    pub followers_count: u32,
+    /// The number of users this account is following
+    #[serde(default)]
+    pub friends_count: u32,
+    /// The number of tweets (including retweets) issued by the user
+    #[serde(default)]
+    pub statuses_count: u32,
+    /// A HTTPS-based URL pointing to the user's profile image
+    pub profile_image_url_https: Option<String>,
+    /// Id of the app the account is connected to, for bots
+    #[serde(default, deserialize_with="opt_id_de", serialize_with="opt_id_ser")]
+    pub app_id: Option<u64>,
+}
+
+impl AccountActivityEvent {
+    /// Whether the request carries no events, which is how a
+    /// subscription can be tested
+    pub fn is_empty(&self) -> bool {
+        self.tweet_create_events.is_empty()
+            && self.favorite_events.is_empty()
+            && self.follow_events.is_empty()
+            && self.block_events.is_empty()
+            && self.mute_events.is_empty()
+            && self.direct_message_events.is_empty()
+            && self.direct_message_indicate_typing_events.is_empty()
+            && self.direct_message_mark_read_events.is_empty()
+            && self.tweet_delete_events.is_empty()
+            && self.user_event.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOLLOW: &str = r#"{
+        "for_user_id": "2244994945",
+        "follow_events": [{
+            "type": "follow",
+            "created_timestamp": "1517588749178",
+            "target": {
+                "id": "2244994945",
+                "name": "Twitter Dev",
+                "screen_name": "TwitterDev",
+                "location": "Internet",
+                "url": "https://dev.twitter.com/",
+                "description": "Your source for Twitter news, developer tools, and resources.",
+                "protected": false,
+                "verified": true,
+                "followers_count": 431474,
+                "friends_count": 1631,
+                "listed_count": 1151,
+                "favourites_count": 2006,
+                "statuses_count": 3104,
+                "created_at": "Sat Dec 14 04:35:55 +0000 2013",
+                "utc_offset": null,
+                "time_zone": null,
+                "geo_enabled": true,
+                "lang": "en",
+                "profile_image_url": "http://pbs.twimg.com/profile_images/880136122604507136/xHrnqf1T_normal.jpg",
+                "profile_image_url_https": "https://pbs.twimg.com/profile_images/880136122604507136/xHrnqf1T_normal.jpg",
+                "default_profile": false,
+                "default_profile_image": false
+            },
+            "source": {
+                "id": "3065418664",
+                "name": "Ross",
+                "screen_name": "ross",
+                "location": null,
+                "url": null,
+                "description": null,
+                "protected": false,
+                "verified": false,
+                "followers_count": 37,
+                "friends_count": 156,
+                "listed_count": 3,
+                "favourites_count": 54,
+                "statuses_count": 1003,
+                "created_at": "Sun Mar 08 06:04:23 +0000 2015",
+                "profile_image_url_https": "https://abs.twimg.com/sticky/default_profile_images/default_profile_normal.png",
+                "default_profile": true,
+                "default_profile_image": true
+            }
+        }]
+    }"#;
+
+    #[test]
+    fn follow_events_parse() {
+        let event: AccountActivityEvent = FOLLOW.parse().unwrap();
+
+        assert_eq!(event.for_user_id, 2244994945);
+        assert!(!event.is_empty());
+
+        let follow = &event.follow_events[0];
+        assert_eq!(follow.kind, "follow");
+        assert_eq!(follow.source.id, 3065418664);
+        assert_eq!(follow.source.screen_name, "ross");
+        assert_eq!(follow.target.id, 2244994945);
+        assert!(follow.target.verified);
+    }
+
+    #[test]
+    fn numeric_user_ids_parse() {
+        let user: ActivityUser = serde_json::from_str(r#"{"id":12,"name":"a","screen_name":"a"}"#).unwrap();
+        assert_eq!(user.id, 12);
+    }
+
+    #[test]
+    fn empty_events_test_the_subscription() {
+        let event: AccountActivityEvent = r#"{"for_user_id":"12"}"#.parse().unwrap();
+        assert!(event.is_empty());
+    }
+}
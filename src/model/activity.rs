@@ -0,0 +1,683 @@
+//! Models for the Activity Streams format of the enterprise APIs.
+//!
+//! Before the native format was available, Gnip delivered tweets as
+//! activities: a `post` or `share` (retweet) `verb` done by an `actor`
+//! to an `object`, with the Twitter specific parts in `twitter_*`
+//! fields and enrichments in `gnip`. Activities can be converted into
+//! the native representation with `into_tweet`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+
+use crate::model::{
+    BoundingBox,
+    Coordinates,
+    Derived,
+    DerivedLocation,
+    Entity,
+    ExtendedEntity,
+    ExtendedTweet,
+    MatchingRule,
+    Place,
+    Tweet,
+    User,
+};
+
+impl FromStr for Activity {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// Represents a tweet in Activity Streams format
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Activity {
+    /// Id of the activity, like `tag:search.twitter.com,2005:123`
+    pub id: String,
+    /// Always `activity`
+    pub object_type: String,
+    /// `post` for tweets and `share` for retweets
+    pub verb: Verb,
+    /// When the tweet was posted
+    pub posted_time: DateTime<Utc>,
+    /// The client that posted the tweet
+    pub generator: Option<Generator>,
+    /// Link to the tweet
+    pub link: String,
+    /// The possibly truncated text of the tweet
+    #[serde(default)]
+    pub body: String,
+    /// The untruncated text and entities of tweets longer than 140 characters
+    #[serde(rename = "long_object")]
+    pub long_object: Option<LongObject>,
+    /// Start and end offsets of the displayable part of `body`
+    #[serde(rename = "display_text_range")]
+    pub display_text_range: Option<(u32, u32)>,
+    /// The user who posted the tweet
+    pub actor: Actor,
+    /// The posted note, or the retweeted activity for a `share`
+    pub object: Option<ActivityObject>,
+    /// The tweet this one replies to
+    pub in_reply_to: Option<InReplyTo>,
+    /// The quoted activity
+    #[serde(rename = "twitter_quoted_status")]
+    pub twitter_quoted_status: Option<Box<Activity>>,
+    /// Entities that have been parsed from the tweet
+    #[serde(rename = "twitter_entities")]
+    pub twitter_entities: Option<Entity>,
+    /// If there are media entities, this field contains them all
+    #[serde(rename = "twitter_extended_entities")]
+    pub twitter_extended_entities: Option<ExtendedEntity>,
+    /// The place that this tweet is associated with
+    pub location: Option<ActivityPlace>,
+    /// Exact location of the tweet. Unlike the native format the
+    /// coordinates are ordered latitude first.
+    pub geo: Option<Coordinates>,
+    /// How many times this tweet has been favorited
+    #[serde(default)]
+    pub favorites_count: u32,
+    /// How many times this tweet has been retweeted
+    #[serde(default)]
+    pub retweet_count: u32,
+    /// What filter level is associated with this tweet
+    #[serde(rename = "twitter_filter_level")]
+    pub twitter_filter_level: Option<String>,
+    /// BCP 47 language identifier corresponding to machine-detected language of tweet
+    #[serde(rename = "twitter_lang")]
+    pub twitter_lang: Option<String>,
+    /// Enrichments added by Gnip
+    pub gnip: Option<Gnip>,
+}
+
+/// What the actor did
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verb {
+    /// Posted a tweet
+    Post,
+    /// Retweeted a tweet
+    Share,
+}
+
+/// The client that posted a tweet
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Generator {
+    /// Name of the client
+    pub display_name: String,
+    /// Website of the client
+    pub link: Option<String>,
+}
+
+/// The untruncated form of a long tweet
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LongObject {
+    /// The untruncated text of the tweet
+    pub body: String,
+    /// Start and end offsets of the displayable part of `body`
+    pub display_text_range: Option<(u32, u32)>,
+    /// Entities that have been parsed from `body`
+    pub twitter_entities: Option<Entity>,
+    /// If there are media entities, this field contains them all
+    pub twitter_extended_entities: Option<ExtendedEntity>,
+}
+
+/// Represents a user in Activity Streams format
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Actor {
+    /// Id of the user, like `id:twitter.com:42`
+    pub id: String,
+    /// Link to the profile of the user
+    pub link: Option<String>,
+    /// The name of the user, as they've defined it
+    pub display_name: String,
+    /// The screen name of the user
+    pub preferred_username: String,
+    /// When the account was created
+    pub posted_time: Option<DateTime<Utc>>,
+    /// Url of the profile image
+    pub image: Option<String>,
+    /// User defined string describing their account
+    pub summary: Option<String>,
+    /// Links provided by the user in association with their profile
+    #[serde(default)]
+    pub links: Vec<ActorLink>,
+    /// User defined location for the account's profile
+    pub location: Option<ActorLocation>,
+    /// The number of followers this account currently has
+    #[serde(default)]
+    pub followers_count: u32,
+    /// The number of users this account is following
+    #[serde(default)]
+    pub friends_count: u32,
+    /// The number of public lists that this user is a member of
+    #[serde(default)]
+    pub listed_count: u32,
+    /// The number of tweets this user has liked
+    #[serde(default)]
+    pub favorites_count: u32,
+    /// The number of tweets (including retweets) issued by the user
+    #[serde(default)]
+    pub statuses_count: u32,
+    /// When true, indicates that the user has a verified account
+    #[serde(default)]
+    pub verified: bool,
+    /// Languages set by the user
+    #[serde(default)]
+    pub languages: Vec<String>,
+}
+
+/// A link in the profile of an actor
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ActorLink {
+    /// Where the link points to. `null` when the user has no link.
+    pub href: Option<String>,
+    /// Relation of the link to the user, usually `me`
+    pub rel: Option<String>,
+}
+
+/// The location an actor entered in their profile
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActorLocation {
+    /// The location as entered
+    pub display_name: String,
+}
+
+/// The object of an activity, told apart by its `objectType`
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum ActivityObject {
+    /// The retweeted activity of a `share`
+    Activity(Box<Activity>),
+    /// The tweet of a `post`
+    Note(Note),
+}
+
+impl<'de> Deserialize<'de> for ActivityObject {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        //  Trying each variant in turn would read a malformed activity as a note
+        let value = Value::deserialize(deserializer)?;
+
+        let object = if value.get("objectType").and_then(Value::as_str) == Some("activity") {
+            serde_json::from_value(value).map(ActivityObject::Activity)
+        } else {
+            serde_json::from_value(value).map(ActivityObject::Note)
+        };
+
+        object.map_err(serde::de::Error::custom)
+    }
+}
+
+/// The tweet of a `post` activity
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Note {
+    /// Id of the tweet, like `object:search.twitter.com,2005:123`
+    pub id: String,
+    /// Always `note`
+    pub object_type: String,
+    /// The text of the tweet
+    pub summary: Option<String>,
+    /// Link to the tweet
+    pub link: Option<String>,
+    /// When the tweet was posted
+    pub posted_time: Option<DateTime<Utc>>,
+}
+
+/// The tweet an activity replies to
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InReplyTo {
+    /// Link to the tweet, like `http://twitter.com/user/statuses/123`
+    pub link: String,
+}
+
+/// Represents a place in Activity Streams format
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityPlace {
+    /// Full, human-readable place name
+    pub display_name: String,
+    /// Short, human-readable place name
+    pub name: Option<String>,
+    /// Link to the place, like `https://api.twitter.com/1.1/geo/id/<id>.json`
+    pub link: Option<String>,
+    /// Name of the country the place is located in. Despite its name
+    /// this is not a code.
+    #[serde(rename = "country_code")]
+    pub country_code: Option<String>,
+    /// Code of the country the place is located in
+    #[serde(rename = "twitter_country_code")]
+    pub twitter_country_code: Option<String>,
+    /// Type of location represented by this place
+    #[serde(rename = "twitter_place_type")]
+    pub twitter_place_type: Option<String>,
+    /// The polygon enclosing the place
+    pub geo: Option<PlaceGeometry>,
+}
+
+/// The polygon enclosing a place
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PlaceGeometry {
+    /// Always `Polygon`
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Longitude and latitude of the corners of the polygon
+    pub coordinates: Vec<Vec<(f64, f64)>>,
+}
+
+/// Enrichments Gnip adds to activities
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Gnip {
+    /// The rules of the stream that matched this activity
+    #[serde(default)]
+    pub matching_rules: Vec<GnipRule>,
+    /// Klout score of the actor
+    pub klout_score: Option<u32>,
+    /// Language detected by Gnip
+    pub language: Option<GnipLanguage>,
+    /// Expanded versions of the links in the tweet
+    #[serde(default)]
+    pub urls: Vec<GnipUrl>,
+    /// Locations derived from the actor's profile
+    #[serde(default, rename = "profileLocations")]
+    pub profile_locations: Vec<GnipProfileLocation>,
+}
+
+/// A rule that matched an activity
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GnipRule {
+    /// Tag given to the rule when it was created
+    pub tag: Option<String>,
+    /// Id of the rule
+    pub id: Option<u64>,
+    /// Text of the rule, sent by older streams
+    pub value: Option<String>,
+}
+
+/// A language detected by Gnip
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GnipLanguage {
+    /// The language code
+    pub value: String,
+}
+
+/// The expansion of a link
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GnipUrl {
+    /// The link as it appears in the tweet
+    pub url: String,
+    /// Final destination of the link
+    pub expanded_url: Option<String>,
+    /// HTTP status of the final destination
+    pub expanded_status: Option<u32>,
+    /// Title of the final destination
+    pub expanded_url_title: Option<String>,
+    /// Description of the final destination
+    pub expanded_url_description: Option<String>,
+}
+
+/// A location derived from the profile of an actor
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GnipProfileLocation {
+    /// Full name of the location
+    pub display_name: Option<String>,
+    /// Structured parts of the location
+    pub address: Option<GnipAddress>,
+    /// Center of the location
+    pub geo: Option<Coordinates>,
+}
+
+/// The structured parts of a profile location
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GnipAddress {
+    /// Name of the country
+    pub country: Option<String>,
+    /// Code of the country
+    pub country_code: Option<String>,
+    /// Name of the state or province
+    pub region: Option<String>,
+    /// Name of the county or district
+    pub sub_region: Option<String>,
+    /// Name of the city
+    pub locality: Option<String>,
+}
+
+impl Activity {
+    /// Whether this activity is a retweet
+    pub fn is_share(&self) -> bool {
+        self.verb == Verb::Share
+    }
+
+    /// The numeric id of the tweet
+    pub fn tweet_id(&self) -> Option<u64> {
+        last_number(&self.id, ':')
+    }
+
+    /// Converts this activity into the native representation.
+    /// Retweeted and quoted activities are converted as well, and the
+    /// Gnip enrichments are moved to where the native format puts them.
+    #[allow(deprecated)]
+    pub fn into_tweet(self) -> Tweet {
+        let id = self.tweet_id().unwrap_or_default();
+        let gnip = self.gnip.unwrap_or_default();
+
+        let mut user = self.actor.into_user();
+        if !gnip.profile_locations.is_empty() {
+            user.derived = Some(Derived {
+                locations: Some(gnip.profile_locations.into_iter().map(GnipProfileLocation::into_derived).collect()),
+            });
+        }
+
+        let matching_rules = gnip.matching_rules
+                                 .into_iter()
+                                 .map(|rule| MatchingRule {
+                                     tag: rule.tag,
+                                     id: rule.id,
+                                     id_str: rule.id.map(|id| id.to_string()),
+                                     value: rule.value,
+                                 })
+                                 .collect::<Vec<_>>();
+
+        let retweeted_status = match self.object {
+            Some(ActivityObject::Activity(activity)) if self.verb == Verb::Share => {
+                Some(Box::new(activity.into_tweet()))
+            }
+            _ => None,
+        };
+
+        let quoted_status = self.twitter_quoted_status.map(|activity| Box::new(activity.into_tweet()));
+        let quoted_status_id = quoted_status.as_ref().map(|quote| quote.id);
+
+        let in_reply_to_status_id = self.in_reply_to.as_ref().and_then(|reply| last_number(&reply.link, '/'));
+        let in_reply_to_screen_name = self.in_reply_to
+                                          .as_ref()
+                                          .and_then(|reply| reply.link.rsplit('/').nth(2))
+                                          .map(String::from);
+
+        let mut entities = self.twitter_entities.unwrap_or_default();
+        enrich_urls(&mut entities, &gnip.urls);
+
+        let (truncated, extended_tweet, extended_entities) = match self.long_object {
+            Some(long) => {
+                let mut extended_tweet = ExtendedTweet {
+                    display_text_range: long.display_text_range.unwrap_or((0, long.body.chars().count() as u32)),
+                    full_text: long.body,
+                    entities: long.twitter_entities.unwrap_or_default(),
+                };
+                enrich_urls(&mut extended_tweet.entities, &gnip.urls);
+
+                (true, Some(extended_tweet), long.twitter_extended_entities.or(self.twitter_extended_entities))
+            }
+            None => (false, None, self.twitter_extended_entities),
+        };
+
+        Tweet {
+            created_at: self.posted_time,
+            id,
+            id_str: id.to_string(),
+            text: self.body,
+            source: self.generator.map(Generator::into_source).unwrap_or_default(),
+            truncated,
+            in_reply_to_status_id,
+            in_reply_to_status_id_str: in_reply_to_status_id.map(|id| id.to_string()),
+            in_reply_to_screen_name,
+            user,
+            extended_tweet,
+            //  Activity Streams puts latitude first, the native format longitude
+            coordinates: self.geo.map(|geo| Coordinates {
+                coordinates: (geo.coordinates.1, geo.coordinates.0),
+                kind: geo.kind,
+            }),
+            place: self.location.map(ActivityPlace::into_place),
+            quoted_status_id,
+            quoted_status_id_str: quoted_status_id.map(|id| id.to_string()),
+            is_quote_status: quoted_status_id.is_some(),
+            quoted_status,
+            retweeted_status,
+            retweet_count: self.retweet_count,
+            favorite_count: Some(self.favorites_count),
+            entities: Some(entities),
+            extended_entities,
+            filter_level: self.twitter_filter_level.unwrap_or_default(),
+            lang: self.twitter_lang,
+            matching_rules: if matching_rules.is_empty() { None } else { Some(matching_rules) },
+            display_text_range: self.display_text_range,
+            ..Default::default()
+        }
+    }
+}
+
+impl Generator {
+    /// Formats the client the way the native `source` field does
+    fn into_source(self) -> String {
+        match self.link {
+            Some(link) => format!("<a href=\"{}\" rel=\"nofollow\">{}</a>", link, self.display_name),
+            None => self.display_name,
+        }
+    }
+}
+
+impl Actor {
+    /// The numeric id of the user
+    pub fn user_id(&self) -> Option<u64> {
+        last_number(&self.id, ':')
+    }
+
+    /// Converts this actor into the native representation
+    pub fn into_user(self) -> User {
+        let id = self.user_id().unwrap_or_default();
+
+        User {
+            id,
+            id_str: id.to_string(),
+            name: self.display_name,
+            screen_name: self.preferred_username,
+            location: self.location.map(|location| location.display_name),
+            url: self.links.into_iter().find_map(|link| link.href),
+            description: self.summary,
+            verified: self.verified,
+            followers_count: self.followers_count,
+            friends_count: self.friends_count,
+            listed_count: self.listed_count,
+            favourites_count: self.favorites_count,
+            statuses_count: self.statuses_count,
+            created_at: self.posted_time.unwrap_or_default(),
+            profile_image_url_https: self.image.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+impl ActivityPlace {
+    /// Converts this place into the native representation
+    pub fn into_place(self) -> Place {
+        let link = self.link.unwrap_or_default();
+        let id = link.rsplit('/')
+                     .next()
+                     .map(|file| file.trim_end_matches(".json").to_string())
+                     .unwrap_or_default();
+
+        let display_name = self.display_name;
+
+        Place {
+            id,
+            url: link,
+            place_type: self.twitter_place_type.unwrap_or_default(),
+            name: self.name.unwrap_or_else(|| display_name.clone()),
+            full_name: display_name,
+            country_code: self.twitter_country_code.unwrap_or_default(),
+            country: self.country_code.unwrap_or_default(),
+            bounding_box: BoundingBox {
+                coordinates: self.geo.map(|geo| geo.coordinates).unwrap_or_default(),
+            },
+        }
+    }
+}
+
+impl GnipProfileLocation {
+    /// Converts this location into the native representation
+    pub fn into_derived(self) -> DerivedLocation {
+        let address = self.address.unwrap_or_default();
+
+        DerivedLocation {
+            country: address.country,
+            country_code: address.country_code,
+            locality: address.locality,
+            region: address.region,
+            sub_region: address.sub_region,
+            full_name: self.display_name,
+            geo: self.geo,
+        }
+    }
+}
+
+/// Copies the expanded URL enrichment onto the matching links
+fn enrich_urls(entities: &mut Entity, urls: &[GnipUrl]) {
+    for url in &mut entities.urls {
+        if let Some(expanded) = urls.iter().find(|expanded| expanded.url == url.url) {
+            if let Some(expanded_url) = &expanded.expanded_url {
+                url.expanded_url = expanded_url.clone();
+            }
+
+            url.expanded_status = expanded.expanded_status;
+            url.expanded_url_title = expanded.expanded_url_title.clone();
+            url.expanded_url_description = expanded.expanded_url_description.clone();
+        }
+    }
+}
+
+/// Parses the number after the last `separator`, as found in
+/// activity ids and links
+fn last_number(s: &str, separator: char) -> Option<u64> {
+    s.rsplit(separator).next().and_then(|last| last.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHARE: &str = r#"{
+        "id": "tag:search.twitter.com,2005:1002",
+        "objectType": "activity",
+        "verb": "share",
+        "postedTime": "2017-11-01T12:00:00.000Z",
+        "generator": {"displayName": "Twitter Web Client", "link": "http://twitter.com"},
+        "link": "http://twitter.com/ross/statuses/1002",
+        "body": "RT @TwitterDev: Hello https://t.co/a",
+        "actor": {
+            "objectType": "person",
+            "id": "id:twitter.com:3065418664",
+            "link": "http://www.twitter.com/ross",
+            "displayName": "Ross",
+            "preferredUsername": "ross",
+            "postedTime": "2015-03-08T06:04:23.000Z",
+            "links": [{"href": null, "rel": "me"}],
+            "followersCount": 37,
+            "verified": false
+        },
+        "object": {
+            "id": "tag:search.twitter.com,2005:1001",
+            "objectType": "activity",
+            "verb": "post",
+            "postedTime": "2017-11-01T11:00:00.000Z",
+            "link": "http://twitter.com/TwitterDev/statuses/1001",
+            "body": "Hello https://t.co/a",
+            "actor": {
+                "id": "id:twitter.com:2244994945",
+                "displayName": "Twitter Dev",
+                "preferredUsername": "TwitterDev"
+            },
+            "object": {
+                "objectType": "note",
+                "id": "object:search.twitter.com,2005:1001",
+                "summary": "Hello https://t.co/a",
+                "link": "http://twitter.com/TwitterDev/statuses/1001",
+                "postedTime": "2017-11-01T11:00:00.000Z"
+            },
+            "twitter_entities": {
+                "hashtags": [],
+                "symbols": [],
+                "user_mentions": [],
+                "urls": [{"url": "https://t.co/a", "expanded_url": "https://t.co/a", "display_url": "example.com", "indices": [6, 20]}]
+            },
+            "location": {
+                "objectType": "place",
+                "displayName": "Boulder, CO",
+                "name": "Boulder",
+                "link": "https://api.twitter.com/1.1/geo/id/fd70c22040963ac7.json",
+                "country_code": "United States",
+                "twitter_country_code": "US",
+                "twitter_place_type": "city",
+                "geo": {"type": "Polygon", "coordinates": [[[-105.3, 39.9], [-105.1, 39.9], [-105.1, 40.1], [-105.3, 40.1]]]}
+            },
+            "geo": {"type": "Point", "coordinates": [40.0, -105.2]},
+            "gnip": {
+                "urls": [{"url": "https://t.co/a", "expanded_url": "https://example.com/post", "expanded_status": 200}]
+            }
+        },
+        "gnip": {
+            "matching_rules": [{"tag": "with id", "id": 42}, {"tag": "legacy", "value": "hello"}],
+            "profileLocations": [{
+                "displayName": "Boulder, Colorado, United States",
+                "address": {"country": "United States", "countryCode": "US", "locality": "Boulder"},
+                "geo": {"type": "point", "coordinates": [-105.27, 40.01]}
+            }]
+        }
+    }"#;
+
+    #[test]
+    fn shares_convert_into_retweets() {
+        let activity: Activity = SHARE.parse().unwrap();
+        assert!(activity.is_share());
+        assert!(matches!(activity.object, Some(ActivityObject::Activity(_))));
+
+        let tweet = activity.into_tweet();
+        assert_eq!(tweet.id, 1002);
+        assert_eq!(tweet.user.id, 3065418664);
+        assert_eq!(tweet.user.url, None);
+        assert_eq!(tweet.source, "<a href=\"http://twitter.com\" rel=\"nofollow\">Twitter Web Client</a>");
+
+        let locations = tweet.user.derived.as_ref().and_then(|derived| derived.locations.as_ref()).unwrap();
+        assert_eq!(locations[0].locality.as_deref(), Some("Boulder"));
+
+        let rt = tweet.retweeted_status.unwrap();
+        assert_eq!(rt.id, 1001);
+        assert_eq!(rt.user.screen_name, "TwitterDev");
+        assert_eq!(rt.coordinates.unwrap().coordinates, (-105.2, 40.0));
+        let place = rt.place.unwrap();
+        assert_eq!((place.id.as_str(), place.country_code.as_str()), ("fd70c22040963ac7", "US"));
+        assert_eq!(rt.entities.unwrap().urls[0].expanded_url, "https://example.com/post");
+    }
+
+    #[test]
+    fn rules_without_ids_are_kept() {
+        let rules = SHARE.parse::<Activity>().unwrap().into_tweet().matching_rules.unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!((rules[0].id, rules[0].id_str.as_deref()), (Some(42), Some("42")));
+        assert_eq!((rules[1].id, rules[1].value.as_deref()), (None, Some("hello")));
+        assert_eq!(rules[1].tag.as_deref(), Some("legacy"));
+    }
+
+    #[test]
+    fn malformed_activity_objects_are_errors() {
+        let note: ActivityObject = serde_json::from_str(r#"{"objectType":"note","id":"object:search.twitter.com,2005:1"}"#).unwrap();
+        assert!(matches!(note, ActivityObject::Note(_)));
+
+        //  Missing `verb`, which a note would not need
+        let broken = r#"{"objectType":"activity","id":"tag:search.twitter.com,2005:1"}"#;
+        assert!(serde_json::from_str::<ActivityObject>(broken).is_err());
+    }
+}
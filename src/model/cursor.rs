@@ -0,0 +1,71 @@
+use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::model::user::User;
+use crate::util::id::lenient_ids_de;
+
+impl<T> FromStr for Cursored<T>
+    where T: serde::de::DeserializeOwned
+{
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// A page of a v1.1 collection endpoint that is paged with cursors
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Cursored<T> {
+    /// The collection on this page
+    #[serde(flatten)]
+    pub items: T,
+    /// Cursor to request the next page with. `0` on the last page.
+    pub next_cursor: i64,
+    /// Same as `next_cursor`, but a String
+    pub next_cursor_str: Option<String>,
+    /// Cursor to request the previous page with. `0` on the first page.
+    pub previous_cursor: i64,
+    /// Same as `previous_cursor`, but a String
+    pub previous_cursor_str: Option<String>,
+}
+
+/// A page of user ids
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Ids {
+    /// The ids on this page. Read from both numbers and strings.
+    #[serde(deserialize_with="lenient_ids_de")]
+    pub ids: Vec<u64>,
+}
+
+/// A page of users
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Users {
+    /// The users on this page
+    pub users: Vec<User>,
+}
+
+/// A page of `followers/ids`
+pub type FollowerIds = Cursored<Ids>;
+
+/// A page of `friends/ids`
+pub type FriendIds = Cursored<Ids>;
+
+/// A page of `blocks/ids`
+pub type BlockIds = Cursored<Ids>;
+
+/// A page of `followers/list`
+pub type FollowersList = Cursored<Users>;
+
+/// A page of `friends/list`
+pub type FriendsList = Cursored<Users>;
+
+/// A page of `lists/members`
+pub type ListMembers = Cursored<Users>;
+
+impl<T> Cursored<T> {
+    /// Whether there are no more pages after this one
+    pub fn is_last(&self) -> bool {
+        self.next_cursor == 0
+    }
+}
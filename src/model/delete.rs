@@ -0,0 +1,40 @@
+use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
+
+impl FromStr for Delete {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// Sent when a tweet has been deleted. Any stored
+/// copies of the tweet should be removed as well.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Delete {
+    /// Contains information about the deletion
+    pub delete: DeleteFields,
+}
+
+/// Holds information on a Delete payload
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeleteFields {
+    /// The tweet that was deleted
+    pub status: DeletedStatus,
+    /// Timestamp of the payload
+    pub timestamp_ms: Option<String>,
+}
+
+/// Identifies a deleted tweet and its author
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeletedStatus {
+    /// Id of the deleted tweet
+    pub id: u64,
+    /// Same as `id`, but a String
+    pub id_str: String,
+    /// Id of the user who posted the tweet
+    pub user_id: u64,
+    /// Same as `user_id`, but a String
+    pub user_id_str: String,
+}
@@ -0,0 +1,331 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::model::entity::Entity;
+use crate::model::media::Media;
+use crate::model::user::User;
+use crate::util::id::{id_de, id_ser, opt_id_de, opt_id_ser};
+
+/// Represents an event from the v1.1 `direct_messages/events` endpoints
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DirectMessageEvent {
+    /// The type of event. Only `message_create` is sent by the API.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The unique id for the event
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub id: u64,
+    /// When the event was created, in milliseconds since the epoch
+    pub created_timestamp: String,
+    /// Set for `message_create` events
+    pub message_create: Option<MessageCreate>,
+}
+
+/// A message being sent
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MessageCreate {
+    /// Who the message was sent to
+    pub target: MessageTarget,
+    /// Id of the user who sent the message
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub sender_id: u64,
+    /// Id of the app that sent the message, found in `DirectMessageEvents::apps`
+    #[serde(default, deserialize_with="opt_id_de", serialize_with="opt_id_ser")]
+    pub source_app_id: Option<u64>,
+    /// The contents of the message
+    pub message_data: MessageData,
+}
+
+/// The recipient of a message
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MessageTarget {
+    /// Id of the user the message was sent to
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub recipient_id: u64,
+}
+
+/// The contents of a message
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MessageData {
+    /// Text of the message
+    pub text: String,
+    /// Entities that have been parsed from the text
+    #[serde(default)]
+    pub entities: Entity,
+    /// Media attached to the message
+    pub attachment: Option<MessageAttachment>,
+    /// Options offered to the recipient to reply with
+    pub quick_reply: Option<QuickReply>,
+    /// The option the sender picked when replying to a quick reply
+    pub quick_reply_response: Option<QuickReplyResponse>,
+    /// Buttons shown below the message
+    pub ctas: Option<Vec<CallToAction>>,
+}
+
+/// Media attached to a message
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MessageAttachment {
+    /// The type of attachment. Only `media` is sent by the API.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The attached media
+    pub media: Media,
+}
+
+/// Options offered to the recipient of a message to reply with
+#[derive(Debug, Deserialize, Serialize)]
+pub struct QuickReply {
+    /// The type of quick reply. Only `options` is sent by the API.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The options to pick from
+    #[serde(default)]
+    pub options: Vec<QuickReplyOption>,
+}
+
+/// A single option of a quick reply
+#[derive(Debug, Deserialize, Serialize)]
+pub struct QuickReplyOption {
+    /// Text shown on the option
+    pub label: String,
+    /// Longer text shown below the label
+    pub description: Option<String>,
+    /// Data sent back in the response when this option is picked
+    pub metadata: Option<String>,
+}
+
+/// The option picked in reply to a quick reply
+#[derive(Debug, Deserialize, Serialize)]
+pub struct QuickReplyResponse {
+    /// The type of quick reply that was answered
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The metadata of the picked option
+    pub metadata: Option<String>,
+}
+
+/// A button shown below a message
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CallToAction {
+    /// The type of button. Only `web_url` is sent by the API.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Text shown on the button
+    pub label: String,
+    /// Where the button links to
+    pub url: String,
+}
+
+/// An app that sent messages
+#[derive(Debug, Deserialize, Serialize)]
+pub struct App {
+    /// The unique id for the app
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub id: u64,
+    /// Name of the app
+    pub name: String,
+    /// Website of the app
+    pub url: Option<String>,
+}
+
+/// The response of `direct_messages/events/list`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DirectMessageEvents {
+    /// The events on this page
+    #[serde(default)]
+    pub events: Vec<DirectMessageEvent>,
+    /// Apps that sent the events, keyed by id
+    #[serde(default)]
+    pub apps: HashMap<String, App>,
+    /// Cursor to request the next page with
+    pub next_cursor: Option<String>,
+}
+
+/// The response of `direct_messages/events/show` and `direct_messages/events/new`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DirectMessageEventResponse {
+    /// The requested event
+    pub event: DirectMessageEvent,
+    /// Apps that sent the event, keyed by id
+    #[serde(default)]
+    pub apps: HashMap<String, App>,
+}
+
+/// A direct message, independent of the API or archive it was read from
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct DirectMessage {
+    /// The unique id for the message
+    pub id: u64,
+    /// Id of the conversation the message belongs to. One to one
+    /// conversations are named after both users, like `12-34`.
+    pub conversation_id: String,
+    /// Id of the user who sent the message
+    pub sender_id: u64,
+    /// Id of the user the message was sent to. Not set in group conversations.
+    pub recipient_id: Option<u64>,
+    /// When the message was sent
+    pub created_at: DateTime<Utc>,
+    /// Text of the message
+    pub text: String,
+    /// Entities that have been parsed from the text
+    pub entities: Entity,
+    /// Media attached to the message
+    pub media: Vec<Media>,
+    /// The user who sent the message, when it was included in the response
+    pub sender: Option<User>,
+    /// Id of the app that sent the message
+    pub source_app_id: Option<u64>,
+}
+
+impl DirectMessage {
+    /// The id of the one to one conversation between two users
+    pub fn conversation_id_for(a: u64, b: u64) -> String {
+        format!("{}-{}", a.min(b), a.max(b))
+    }
+}
+
+impl DirectMessageEvent {
+    /// When the event was created
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_timestamp
+            .parse()
+            .ok()
+            .and_then(|ms| Utc.timestamp_millis_opt(ms).single())
+    }
+
+    /// Converts a `message_create` event into a `DirectMessage`
+    pub fn into_direct_message(self) -> Option<DirectMessage> {
+        let created_at = self.created_at().unwrap_or_default();
+        let create = self.message_create?;
+        let recipient_id = create.target.recipient_id;
+
+        Some(DirectMessage {
+            id: self.id,
+            conversation_id: DirectMessage::conversation_id_for(create.sender_id, recipient_id),
+            sender_id: create.sender_id,
+            recipient_id: Some(recipient_id),
+            created_at,
+            text: create.message_data.text,
+            entities: create.message_data.entities,
+            media: create.message_data.attachment.map(|a| a.media).into_iter().collect(),
+            sender: None,
+            source_app_id: create.source_app_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::v2;
+    use crate::model::MediaType;
+
+    const V1_EVENT: &str = r#"{
+        "type": "message_create",
+        "id": "1001",
+        "created_timestamp": "1577836800000",
+        "message_create": {
+            "target": {"recipient_id": "34"},
+            "sender_id": "12",
+            "source_app_id": "268278",
+            "message_data": {
+                "text": "look https://t.co/a",
+                "entities": {"hashtags": [], "symbols": [], "user_mentions": [], "urls": []},
+                "attachment": {
+                    "type": "media",
+                    "media": {
+                        "id": 40,
+                        "id_str": "40",
+                        "indices": [5, 19],
+                        "media_url": "http://pbs.twimg.com/dm_gif_preview/40/a.jpg",
+                        "media_url_https": "https://pbs.twimg.com/dm_gif_preview/40/a.jpg",
+                        "url": "https://t.co/a",
+                        "display_url": "pic.twitter.com/a",
+                        "expanded_url": "https://twitter.com/messages/media/1001",
+                        "type": "photo",
+                        "sizes": {
+                            "thumb": {"w": 150, "h": 150, "resize": "crop"},
+                            "small": {"w": 680, "h": 383, "resize": "fit"},
+                            "medium": {"w": 1200, "h": 675, "resize": "fit"},
+                            "large": {"w": 1600, "h": 900, "resize": "fit"}
+                        }
+                    }
+                }
+            }
+        }
+    }"#;
+
+    const V2_EVENTS: &str = r#"{
+        "data": [
+            {
+                "id": "1001",
+                "event_type": "MessageCreate",
+                "text": "look https://t.co/a",
+                "sender_id": "12",
+                "dm_conversation_id": "12-34",
+                "created_at": "2020-01-01T00:00:00.000Z",
+                "attachments": {"media_keys": ["16_40"]},
+                "entities": {"urls": [{"start": 5, "end": 19, "url": "https://t.co/a", "media_key": "16_40"}]}
+            },
+            {
+                "id": "1002",
+                "event_type": "ParticipantsJoin",
+                "dm_conversation_id": "1500",
+                "participant_ids": ["56"]
+            },
+            {
+                "id": "1003",
+                "event_type": "MessageReactionCreate",
+                "dm_conversation_id": "12-34"
+            }
+        ],
+        "includes": {
+            "users": [{"id": "12", "name": "twelve", "username": "twelve"}],
+            "media": [{
+                "media_key": "16_40",
+                "type": "animated_gif",
+                "preview_image_url": "https://pbs.twimg.com/dm_gif_preview/40/a.jpg",
+                "width": 480,
+                "height": 270,
+                "variants": [{"bit_rate": 0, "content_type": "video/mp4", "url": "https://video.twimg.com/dm_gif/40/a.mp4"}]
+            }]
+        }
+    }"#;
+
+    #[test]
+    fn v1_events_keep_their_attachment() {
+        let event: DirectMessageEvent = serde_json::from_str(V1_EVENT).unwrap();
+        let message = event.into_direct_message().unwrap();
+
+        assert_eq!(message.conversation_id, "12-34");
+        assert_eq!(message.recipient_id, Some(34));
+        assert_eq!(message.created_at.timestamp(), 1_577_836_800);
+        assert_eq!(message.source_app_id, Some(268278));
+        assert_eq!(message.media.len(), 1);
+        assert_eq!(message.media[0].id, 40);
+    }
+
+    #[test]
+    fn v2_events_convert_media_and_skip_other_types() {
+        let response: v2::Response<Vec<v2::DmEvent>> = serde_json::from_str(V2_EVENTS).unwrap();
+        let events = response.data.as_ref().unwrap();
+
+        assert_eq!(events[1].event_type, v2::DmEventType::ParticipantsJoin);
+        assert_eq!(events[2].event_type, v2::DmEventType::Unknown);
+        assert!(events[1].to_direct_message(&response.includes).is_none());
+        assert!(events[2].to_direct_message(&response.includes).is_none());
+
+        let message = events[0].to_direct_message(&response.includes).unwrap();
+        assert_eq!(message.recipient_id, Some(34));
+        assert_eq!(message.sender.as_ref().map(|user| user.screen_name.as_str()), Some("twelve"));
+
+        let media = &message.media[0];
+        assert_eq!(media.id, 40);
+        assert_eq!(media.kind, MediaType::Unknown("animated_gif".to_string()));
+        assert_eq!(media.url, "https://t.co/a");
+        assert_eq!(media.indices, vec![5, 19]);
+        assert_eq!(media.video_info.as_ref().unwrap().aspect_ratio, (16, 9));
+        assert_eq!(media.url().as_deref(), Some("https://video.twimg.com/dm_gif/40/a.mp4"));
+    }
+}
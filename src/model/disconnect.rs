@@ -0,0 +1,110 @@
+use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
+
+impl FromStr for Disconnect {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// Sent right before a stream is closed by Twitter,
+/// telling why the connection ends
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Disconnect {
+    /// Contains information about the disconnect
+    pub disconnect: DisconnectFields,
+}
+
+/// Holds information on a Disconnect payload
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DisconnectFields {
+    /// The reason for the disconnect, see `DisconnectCode`
+    pub code: u32,
+    /// Name of the stream that was closed
+    pub stream_name: Option<String>,
+    /// Explanation of the disconnect
+    pub reason: String,
+}
+
+/// The documented disconnect codes
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DisconnectCode {
+    /// 1: The feed was shut down, possibly for a restart
+    Shutdown,
+    /// 2: Another connection was opened with the same credentials
+    DuplicateStream,
+    /// 3: The connection was closed through a control request
+    ControlRequest,
+    /// 4: The client was reading too slowly and fell behind
+    Stall,
+    /// 5: The connection was closed for no special reason
+    Normal,
+    /// 6: The access token was revoked
+    TokenRevoked,
+    /// 7: The account logged out of every session
+    AdminLogout,
+    /// 9: The connection delivered its maximum number of messages
+    MaxMessageLimit,
+    /// 10: An internal error occurred in the stream
+    StreamException,
+    /// 11: An internal error occurred upstream of the stream
+    BrokerStall,
+    /// 12: The stream was closed to reduce load
+    ShedLoad,
+    /// Any code this crate does not know about
+    Other(u32),
+}
+
+const CODES: &[(u32, DisconnectCode)] = &[
+    (1, DisconnectCode::Shutdown),
+    (2, DisconnectCode::DuplicateStream),
+    (3, DisconnectCode::ControlRequest),
+    (4, DisconnectCode::Stall),
+    (5, DisconnectCode::Normal),
+    (6, DisconnectCode::TokenRevoked),
+    (7, DisconnectCode::AdminLogout),
+    (9, DisconnectCode::MaxMessageLimit),
+    (10, DisconnectCode::StreamException),
+    (11, DisconnectCode::BrokerStall),
+    (12, DisconnectCode::ShedLoad),
+];
+
+impl From<u32> for DisconnectCode {
+    fn from(code: u32) -> Self {
+        CODES.iter()
+             .find(|(known, _)| *known == code)
+             .map(|(_, kind)| *kind)
+             .unwrap_or(DisconnectCode::Other(code))
+    }
+}
+
+impl DisconnectCode {
+    /// The numeric code
+    pub fn code(self) -> u32 {
+        match self {
+            DisconnectCode::Other(code) => code,
+            kind => CODES.iter()
+                         .find(|(_, known)| *known == kind)
+                         .map(|(code, _)| *code)
+                         .unwrap_or_default(),
+        }
+    }
+
+    /// Whether opening a new connection cannot succeed until the
+    /// credentials or the other connection are dealt with
+    pub fn is_fatal(self) -> bool {
+        matches!(self,
+                 DisconnectCode::DuplicateStream
+                 | DisconnectCode::TokenRevoked
+                 | DisconnectCode::AdminLogout)
+    }
+}
+
+impl Disconnect {
+    /// The typed disconnect code
+    pub fn kind(&self) -> DisconnectCode {
+        DisconnectCode::from(self.disconnect.code)
+    }
+}
@@ -0,0 +1,42 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::model::coordinates::Coordinates;
+
+/// A PowerTrack rule that matched a tweet
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MatchingRule {
+    /// Tag given to the rule when it was created
+    pub tag: Option<String>,
+    /// Id of the rule. Not sent by older streams.
+    pub id: Option<u64>,
+    /// Same as `id`, but a String
+    pub id_str: Option<String>,
+    /// Text of the rule, sent by older streams instead of the id
+    pub value: Option<String>,
+}
+
+/// Data derived from the profile of a user by the enterprise APIs
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Derived {
+    /// Locations derived from the user defined location
+    pub locations: Option<Vec<DerivedLocation>>,
+}
+
+/// A location derived from the profile of a user
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DerivedLocation {
+    /// Name of the country
+    pub country: Option<String>,
+    /// Code of the country
+    pub country_code: Option<String>,
+    /// Name of the city
+    pub locality: Option<String>,
+    /// Name of the state or province
+    pub region: Option<String>,
+    /// Name of the county or district
+    pub sub_region: Option<String>,
+    /// Full name of the location
+    pub full_name: Option<String>,
+    /// Center of the location
+    pub geo: Option<Coordinates>,
+}
@@ -8,7 +8,7 @@ use crate::model::url::Url;
 use crate::model::user_mention::UserMention;
 
 /// Contains information on various parsed out pieces of tweets
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Entity {
     /// Collection of hashtags that were included in this tweet
     pub hashtags: Vec<Hashtag>,
@@ -0,0 +1,384 @@
+use serde_derive::{Deserialize, Serialize};
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::model::v2::Problem;
+
+impl FromStr for TwitterError {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// The body of a failed request. The variants are tried in order, so
+/// the v2 problem, which only needs a `title`, comes last.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum TwitterError {
+    /// The v1.1 error envelope
+    V1(ApiErrors),
+    /// The plain v1.1 error some endpoints send instead of the envelope
+    Message(ApiMessage),
+    /// A v2 problem details object
+    V2(Problem),
+}
+
+/// The v1.1 error envelope, `{"errors":[{"code":88,"message":"..."}]}`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ApiErrors {
+    /// Every error that occurred, usually just one
+    pub errors: Vec<ApiError>,
+}
+
+/// A single v1.1 error
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ApiError {
+    /// The error code, see `ErrorCode`
+    pub code: u32,
+    /// Explanation of the error
+    pub message: String,
+}
+
+/// A v1.1 error without a code, `{"request":"...","error":"Not authorized."}`,
+/// sent for example when reading the timeline of a protected user
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ApiMessage {
+    /// Explanation of the error
+    pub error: String,
+    /// Path of the request that failed
+    pub request: Option<String>,
+}
+
+/// The documented v1.1 error codes
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ErrorCode {
+    /// 3: The `place_id`, `lat` or `long` parameter is invalid
+    InvalidCoordinates,
+    /// 13: No location is associated with the coordinates
+    NoLocationFound,
+    /// 17: No user matches the given ids or screen names
+    NoUserMatches,
+    /// 32: The request could not be authenticated
+    CouldNotAuthenticate,
+    /// 34: The requested resource does not exist
+    PageDoesNotExist,
+    /// 44: The `attachment_url` parameter is invalid
+    InvalidAttachmentUrl,
+    /// 50: The user does not exist
+    UserNotFound,
+    /// 63: The user has been suspended
+    UserSuspended,
+    /// 64: The authenticating account has been suspended
+    AccountSuspended,
+    /// 68: The endpoint has been retired
+    ApiVersionRetired,
+    /// 87: The client may not perform this action
+    ClientNotPermitted,
+    /// 88: The rate limit of the endpoint has been reached
+    RateLimitExceeded,
+    /// 89: The access token is invalid or expired
+    InvalidOrExpiredToken,
+    /// 92: The request has to use HTTPS
+    SslRequired,
+    /// 93: The app may not access direct messages
+    DirectMessagesNotPermitted,
+    /// 99: Credentials for a bearer token could not be verified
+    UnableToVerifyCredentials,
+    /// 130: Twitter is temporarily over capacity
+    OverCapacity,
+    /// 131: An unknown internal error occurred
+    InternalError,
+    /// 135: The OAuth timestamp is too far from the server time
+    TimestampOutOfBounds,
+    /// 139: The tweet was already liked
+    AlreadyFavorited,
+    /// 144: No tweet exists with the id
+    NoStatusFound,
+    /// 150: Direct messages can only be sent to followers
+    CannotMessageNonFollower,
+    /// 160: A follow request was already sent
+    FollowRequestAlreadySent,
+    /// 161: The account may not follow more users right now
+    FollowLimitReached,
+    /// 179: The tweet belongs to a protected account
+    NotAuthorizedToSeeStatus,
+    /// 185: The daily limit of tweets has been reached
+    StatusUpdateLimit,
+    /// 186: The tweet is too long
+    TweetTooLong,
+    /// 187: The tweet is a duplicate
+    DuplicateStatus,
+    /// 215: The authentication data is malformed
+    BadAuthenticationData,
+    /// 220: The credentials may not access this resource
+    CredentialsNotAllowed,
+    /// 226: The request looks automated and was blocked as spam
+    AutomatedRequest,
+    /// 231: The user has to verify their login
+    VerifyLogin,
+    /// 251: The endpoint has been retired
+    EndpointRetired,
+    /// 261: The app may not perform write actions
+    CannotPerformWriteActions,
+    /// 326: The account is temporarily locked
+    AccountLocked,
+    /// 327: The tweet was already retweeted
+    AlreadyRetweeted,
+    /// 349: The user may not be sent direct messages
+    CannotSendMessage,
+    /// 385: The replied to tweet was deleted or is not visible
+    ReplyToUnavailableTweet,
+    /// 416: The app has been suspended
+    AppSuspended,
+    /// 421: The tweet is no longer available
+    TweetNoLongerAvailable,
+    /// 433: The author restricted who can reply
+    ReplyRestricted,
+    /// Any code this crate does not know about
+    Other(u32),
+}
+
+const CODES: &[(u32, ErrorCode)] = &[
+    (3, ErrorCode::InvalidCoordinates),
+    (13, ErrorCode::NoLocationFound),
+    (17, ErrorCode::NoUserMatches),
+    (32, ErrorCode::CouldNotAuthenticate),
+    (34, ErrorCode::PageDoesNotExist),
+    (44, ErrorCode::InvalidAttachmentUrl),
+    (50, ErrorCode::UserNotFound),
+    (63, ErrorCode::UserSuspended),
+    (64, ErrorCode::AccountSuspended),
+    (68, ErrorCode::ApiVersionRetired),
+    (87, ErrorCode::ClientNotPermitted),
+    (88, ErrorCode::RateLimitExceeded),
+    (89, ErrorCode::InvalidOrExpiredToken),
+    (92, ErrorCode::SslRequired),
+    (93, ErrorCode::DirectMessagesNotPermitted),
+    (99, ErrorCode::UnableToVerifyCredentials),
+    (130, ErrorCode::OverCapacity),
+    (131, ErrorCode::InternalError),
+    (135, ErrorCode::TimestampOutOfBounds),
+    (139, ErrorCode::AlreadyFavorited),
+    (144, ErrorCode::NoStatusFound),
+    (150, ErrorCode::CannotMessageNonFollower),
+    (160, ErrorCode::FollowRequestAlreadySent),
+    (161, ErrorCode::FollowLimitReached),
+    (179, ErrorCode::NotAuthorizedToSeeStatus),
+    (185, ErrorCode::StatusUpdateLimit),
+    (186, ErrorCode::TweetTooLong),
+    (187, ErrorCode::DuplicateStatus),
+    (215, ErrorCode::BadAuthenticationData),
+    (220, ErrorCode::CredentialsNotAllowed),
+    (226, ErrorCode::AutomatedRequest),
+    (231, ErrorCode::VerifyLogin),
+    (251, ErrorCode::EndpointRetired),
+    (261, ErrorCode::CannotPerformWriteActions),
+    (326, ErrorCode::AccountLocked),
+    (327, ErrorCode::AlreadyRetweeted),
+    (349, ErrorCode::CannotSendMessage),
+    (385, ErrorCode::ReplyToUnavailableTweet),
+    (416, ErrorCode::AppSuspended),
+    (421, ErrorCode::TweetNoLongerAvailable),
+    (433, ErrorCode::ReplyRestricted),
+];
+
+impl From<u32> for ErrorCode {
+    fn from(code: u32) -> Self {
+        CODES.iter()
+             .find(|(known, _)| *known == code)
+             .map(|(_, kind)| *kind)
+             .unwrap_or(ErrorCode::Other(code))
+    }
+}
+
+impl ErrorCode {
+    /// The numeric code
+    pub fn code(self) -> u32 {
+        match self {
+            ErrorCode::Other(code) => code,
+            kind => CODES.iter()
+                         .find(|(_, known)| *known == kind)
+                         .map(|(code, _)| *code)
+                         .unwrap_or_default(),
+        }
+    }
+
+    /// Whether the same request may succeed if it is sent again later
+    pub fn is_retryable(self) -> bool {
+        matches!(self, ErrorCode::RateLimitExceeded | ErrorCode::OverCapacity | ErrorCode::InternalError)
+    }
+
+    /// Whether the request failed because of the credentials or the
+    /// account or app they belong to
+    pub fn is_auth(self) -> bool {
+        matches!(self,
+                 ErrorCode::CouldNotAuthenticate
+                 | ErrorCode::AccountSuspended
+                 | ErrorCode::InvalidOrExpiredToken
+                 | ErrorCode::UnableToVerifyCredentials
+                 | ErrorCode::TimestampOutOfBounds
+                 | ErrorCode::BadAuthenticationData
+                 | ErrorCode::CredentialsNotAllowed
+                 | ErrorCode::CannotPerformWriteActions
+                 | ErrorCode::AccountLocked
+                 | ErrorCode::AppSuspended)
+    }
+}
+
+impl ApiError {
+    /// The typed error code
+    pub fn kind(&self) -> ErrorCode {
+        ErrorCode::from(self.code)
+    }
+}
+
+impl TwitterError {
+    /// The typed codes of a v1.1 error. Empty for errors without
+    /// codes and v2 problems.
+    pub fn codes(&self) -> Vec<ErrorCode> {
+        match self {
+            TwitterError::V1(errors) => errors.errors.iter().map(ApiError::kind).collect(),
+            TwitterError::Message(_) | TwitterError::V2(_) => Vec::new(),
+        }
+    }
+
+    /// Whether the same request may succeed if it is sent again later
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            TwitterError::V1(_) => self.codes().into_iter().any(ErrorCode::is_retryable),
+            TwitterError::Message(_) => false,
+            TwitterError::V2(problem) => {
+                problem.status.is_some_and(|status| status == 429 || status >= 500)
+                    || problem.kind.as_deref() == Some("https://api.twitter.com/2/problems/usage-capped")
+            }
+        }
+    }
+
+    /// Whether the request failed because of the credentials or the
+    /// account or app they belong to
+    pub fn is_auth(&self) -> bool {
+        match self {
+            TwitterError::V1(_) => self.codes().into_iter().any(ErrorCode::is_auth),
+            TwitterError::Message(_) => false,
+            TwitterError::V2(problem) => {
+                matches!(problem.status, Some(401) | Some(403))
+                    || matches!(problem.kind.as_deref(),
+                                Some("https://api.twitter.com/2/problems/client-forbidden")
+                                | Some("https://api.twitter.com/2/problems/unsupported-authentication"))
+            }
+        }
+    }
+}
+
+impl fmt::Display for TwitterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TwitterError::V1(errors) => {
+                let messages = errors.errors
+                                     .iter()
+                                     .map(|error| format!("{} (code {})", error.message, error.code))
+                                     .collect::<Vec<_>>();
+                write!(f, "{}", messages.join("; "))
+            }
+            TwitterError::Message(message) => write!(f, "{}", message.error),
+            TwitterError::V2(problem) => match &problem.detail {
+                Some(detail) => write!(f, "{}: {}", problem.title, detail),
+                None => write!(f, "{}", problem.title),
+            },
+        }
+    }
+}
+
+impl error::Error for TwitterError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TwitterResponse;
+
+    #[test]
+    fn v1_error_envelopes_parse() {
+        let error: TwitterError = r#"{"errors":[{"code":88,"message":"Rate limit exceeded"}]}"#.parse().unwrap();
+
+        assert!(matches!(error, TwitterError::V1(_)));
+        assert_eq!(error.codes(), vec![ErrorCode::RateLimitExceeded]);
+        assert!(error.is_retryable());
+        assert!(!error.is_auth());
+        assert_eq!(error.to_string(), "Rate limit exceeded (code 88)");
+    }
+
+    #[test]
+    fn v1_plain_errors_parse() {
+        let error: TwitterError = r#"{"request":"/1.1/statuses/user_timeline.json","error":"Not authorized."}"#.parse().unwrap();
+
+        match &error {
+            TwitterError::Message(message) => {
+                assert_eq!(message.request.as_deref(), Some("/1.1/statuses/user_timeline.json"));
+            }
+            other => panic!("parsed as {:?}", other),
+        }
+        assert!(error.codes().is_empty());
+        assert_eq!(error.to_string(), "Not authorized.");
+
+        let error: TwitterError = r#"{"error":"Not authorized."}"#.parse().unwrap();
+        assert!(matches!(error, TwitterError::Message(_)));
+    }
+
+    #[test]
+    fn v2_problems_only_need_a_title() {
+        let error: TwitterError = r#"{"title":"Too Many Requests","detail":"Too Many Requests","type":"about:blank","status":429}"#.parse().unwrap();
+        assert!(matches!(error, TwitterError::V2(_)));
+        assert!(error.is_retryable());
+        assert_eq!(error.to_string(), "Too Many Requests: Too Many Requests");
+
+        let error: TwitterError = r#"{"title":"Unauthorized"}"#.parse().unwrap();
+        assert!(matches!(error, TwitterError::V2(_)));
+        assert!(!error.is_auth());
+    }
+
+    #[test]
+    fn v2_invalid_requests_are_problems() {
+        //  The nested errors have no code, so this is not a v1.1 envelope
+        let body = r#"{
+            "errors": [{"parameters": {"ids": ["x"]}, "message": "The `ids` query parameter value [x] is not valid"}],
+            "title": "Invalid Request",
+            "detail": "One or more parameters to your request was invalid.",
+            "type": "https://api.twitter.com/2/problems/invalid-request"
+        }"#;
+
+        let error: TwitterError = body.parse().unwrap();
+        assert!(matches!(error, TwitterError::V2(ref problem) if problem.title == "Invalid Request"));
+    }
+
+    #[test]
+    fn error_bodies_are_stream_responses() {
+        for body in &[
+            r#"{"errors":[{"code":32,"message":"Could not authenticate you."}]}"#,
+            r#"{"error":"Not authorized."}"#,
+            r#"{"title":"ConnectionException","detail":"This stream is currently at the maximum allowed connection limit."}"#,
+        ] {
+            let response: TwitterResponse = body.parse().unwrap();
+            assert!(matches!(response, TwitterResponse::Error(_)), "{}", body);
+        }
+    }
+
+    #[test]
+    fn codes_are_classified() {
+        assert_eq!(ErrorCode::from(144), ErrorCode::NoStatusFound);
+        assert_eq!(ErrorCode::from(9999), ErrorCode::Other(9999));
+        assert_eq!(ErrorCode::NoStatusFound.code(), 144);
+        assert_eq!(ErrorCode::Other(9999).code(), 9999);
+
+        for (code, kind) in CODES {
+            assert_eq!(kind.code(), *code);
+        }
+
+        assert!(ErrorCode::OverCapacity.is_retryable());
+        assert!(!ErrorCode::DuplicateStatus.is_retryable());
+        assert!(ErrorCode::InvalidOrExpiredToken.is_auth());
+        assert!(!ErrorCode::RateLimitExceeded.is_auth());
+    }
+}
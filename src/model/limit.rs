@@ -1,3 +1,4 @@
+use chrono::{DateTime, TimeZone, Utc};
 use serde_derive::{Deserialize, Serialize};
 use std::str::FromStr;
 
@@ -24,7 +25,23 @@ pub struct Limit {
 pub struct LimitFields {
     /// Total count of all undelivered tweets since
     /// the connection was established.
-    track: u32,
+    track: u64,
     /// Timestamp of the payload
     timestamp_ms: String,
-}
\ No newline at end of file
+}
+
+impl Limit {
+    /// Total count of all undelivered tweets since the connection was established
+    pub fn track(&self) -> u64 {
+        self.limit.track
+    }
+
+    /// When the payload was sent. `None` if the timestamp is malformed.
+    pub fn timestamp(&self) -> Option<DateTime<Utc>> {
+        self.limit
+            .timestamp_ms
+            .parse()
+            .ok()
+            .and_then(|ms| Utc.timestamp_millis_opt(ms).single())
+    }
+}
@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::model::user::User;
+use crate::util::datetime::{datefmt_de, datefmt_ser};
+
+/// Represents a list of users
+#[derive(Debug, Deserialize, Serialize)]
+pub struct List {
+    /// The unique id for the list
+    pub id: u64,
+    /// String version of `id`
+    pub id_str: String,
+    /// The name of the list
+    pub name: String,
+    /// The name of the list prefixed with the owner, like `@user/name`
+    pub full_name: String,
+    /// The name of the list as used in urls
+    pub slug: String,
+    /// Path of the list, like `/user/lists/name`
+    pub uri: String,
+    /// User defined string describing the list
+    #[serde(default)]
+    pub description: String,
+    /// Either `public` or `private`
+    pub mode: String,
+    /// How many users are in the list
+    pub member_count: u32,
+    /// How many users subscribed to the list
+    pub subscriber_count: u32,
+    /// Whether the authenticated user subscribed to the list
+    #[serde(default)]
+    pub following: bool,
+    /// When the list was created
+    #[serde(deserialize_with="datefmt_de", serialize_with="datefmt_ser")]
+    pub created_at: DateTime<Utc>,
+    /// The owner of the list
+    pub user: User,
+}
@@ -1,6 +1,6 @@
 use serde_derive::{Deserialize, Serialize};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum MediaType {
     Photo,
     Gif,
@@ -8,6 +8,18 @@ pub enum MediaType {
     Unknown(String),
 }
 
+impl MediaType {
+    /// Returns the name Twitter uses for this type of media
+    pub fn as_str(&self) -> &str {
+        match *self {
+            MediaType::Photo => "photo",
+            MediaType::Gif => "gif",
+            MediaType::Video => "video",
+            MediaType::Unknown(ref other) => other,
+        }
+    }
+}
+
 /// Represents media that is associated with the tweet.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Media {
@@ -49,7 +61,7 @@ impl Media {
             }
         }
 
-        return None;
+        None
     }
 }
 
@@ -1,25 +1,92 @@
+mod account_activity;
 mod coordinates;
+mod cursor;
+mod delete;
+mod direct_message;
+mod disconnect;
+mod enrichment;
 mod entity;
+mod error;
 mod hashtag;
 mod limit;
+mod list;
 mod media;
 mod place;
 mod poll;
+mod premium;
+mod scrub_geo;
+mod search;
 mod symbol;
 mod tweet;
 mod url;
 mod user_mention;
 mod user;
+mod user_stream;
+mod warning;
+mod withheld;
+pub mod activity;
+pub mod v2;
 
+pub use account_activity::{
+    AccountActivityEvent,
+    ActivityDeletedStatus,
+    ActivityUser,
+    FavoriteEvent,
+    MarkReadEvent,
+    RelationshipEvent,
+    Revoke,
+    RevokeSource,
+    RevokeTarget,
+    TweetDeleteEvent,
+    TypingEvent,
+    UserEvent,
+};
 pub use coordinates::Coordinates;
+pub use cursor::{
+    BlockIds,
+    Cursored,
+    FollowerIds,
+    FollowersList,
+    FriendIds,
+    FriendsList,
+    Ids,
+    ListMembers,
+    Users,
+};
+pub use delete::{Delete, DeleteFields, DeletedStatus};
+pub use direct_message::{
+    App,
+    CallToAction,
+    DirectMessage,
+    DirectMessageEvent,
+    DirectMessageEventResponse,
+    DirectMessageEvents,
+    MessageAttachment,
+    MessageCreate,
+    MessageData,
+    MessageTarget,
+    QuickReply,
+    QuickReplyOption,
+    QuickReplyResponse,
+};
+pub use disconnect::{Disconnect, DisconnectCode, DisconnectFields};
+pub use enrichment::{Derived, DerivedLocation, MatchingRule};
 pub use entity::{Entity, ExtendedEntity};
+pub use error::{ApiError, ApiErrors, ApiMessage, ErrorCode, TwitterError};
 pub use hashtag::Hashtag;
 pub use limit::Limit;
+pub use list::List;
 pub use media::*;
 pub use place::{Place, BoundingBox};
 pub use poll::{Poll, PollOption};
+pub use premium::{CountPeriod, PremiumCounts, PremiumSearch, RequestParameters};
+pub use scrub_geo::{ScrubGeo, ScrubGeoFields};
+pub use search::{SearchMetadata, SearchResults};
 pub use symbol::Symbol;
 pub use tweet::{ExtendedTweet, Tweet};
 pub use url::{LegacyUrl, UnwoundUrl, Url};
 pub use user_mention::UserMention;
-pub use user::User;
\ No newline at end of file
+pub use user::User;
+pub use user_stream::{EventKind, Friends, TargetObject, UserStreamEvent};
+pub use warning::{Warning, WarningFields};
+pub use withheld::{StatusWithheld, StatusWithheldFields, UserWithheld, UserWithheldFields};
\ No newline at end of file
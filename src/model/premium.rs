@@ -0,0 +1,82 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::model::tweet::Tweet;
+
+impl FromStr for PremiumSearch {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+impl FromStr for PremiumCounts {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// A page of the premium and enterprise 30-day and full-archive search endpoints
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PremiumSearch {
+    /// The tweets on this page
+    #[serde(default)]
+    pub results: Vec<Tweet>,
+    /// Token to request the next page with. Not set on the last page.
+    pub next: Option<String>,
+    /// The parameters the page was requested with
+    pub request_parameters: Option<RequestParameters>,
+}
+
+/// A page of the premium and enterprise counts endpoints
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PremiumCounts {
+    /// How many tweets matched in each period
+    #[serde(default)]
+    pub results: Vec<CountPeriod>,
+    /// The sum of all counts on this page
+    pub total_count: u64,
+    /// Token to request the next page with. Not set on the last page.
+    pub next: Option<String>,
+    /// The parameters the page was requested with
+    pub request_parameters: Option<RequestParameters>,
+}
+
+/// The number of matching tweets in one period
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CountPeriod {
+    /// Start of the period, formatted as `yyyyMMddHHmm`
+    pub time_period: String,
+    /// How many tweets matched
+    pub count: u64,
+}
+
+/// The parameters a premium search or count was requested with
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestParameters {
+    /// Maximum number of results per page
+    pub max_results: Option<u32>,
+    /// Start of the searched period, formatted as `yyyyMMddHHmm`
+    pub from_date: Option<String>,
+    /// End of the searched period, formatted as `yyyyMMddHHmm`
+    pub to_date: Option<String>,
+    /// Length of each counted period: `minute`, `hour` or `day`
+    pub bucket: Option<String>,
+}
+
+impl CountPeriod {
+    /// Start of the period
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        NaiveDateTime::parse_from_str(&self.time_period, "%Y%m%d%H%M")
+            .ok()
+            .map(|time| time.and_utc())
+    }
+}
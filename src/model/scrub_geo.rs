@@ -0,0 +1,34 @@
+use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
+
+impl FromStr for ScrubGeo {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// Sent when a user removes the location information from
+/// their tweets. Geo data of stored tweets from this user
+/// up to and including `up_to_status_id` should be removed.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ScrubGeo {
+    /// Contains information about what to scrub
+    pub scrub_geo: ScrubGeoFields,
+}
+
+/// Holds information on a ScrubGeo payload
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ScrubGeoFields {
+    /// Id of the user whose geo data was removed
+    pub user_id: u64,
+    /// Same as `user_id`, but a String
+    pub user_id_str: String,
+    /// Id of the most recent tweet that should be scrubbed
+    pub up_to_status_id: u64,
+    /// Same as `up_to_status_id`, but a String
+    pub up_to_status_id_str: String,
+    /// Timestamp of the payload
+    pub timestamp_ms: Option<String>,
+}
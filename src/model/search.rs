@@ -0,0 +1,44 @@
+use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::model::tweet::Tweet;
+
+impl FromStr for SearchResults {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// A page of the v1.1 `search/tweets` endpoint
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SearchResults {
+    /// The tweets on this page, newest first
+    pub statuses: Vec<Tweet>,
+    /// Information about the search
+    pub search_metadata: SearchMetadata,
+}
+
+/// Information about a search and how to continue it
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SearchMetadata {
+    /// How many seconds the search took
+    pub completed_in: Option<f64>,
+    /// The `max_id` the page was requested with, or the newest id if none was given
+    pub max_id: u64,
+    /// Same as `max_id`, but a String
+    pub max_id_str: String,
+    /// Query string to request the next page with. Not set on the last page.
+    pub next_results: Option<String>,
+    /// The search query
+    pub query: String,
+    /// Query string to request tweets newer than this page with
+    pub refresh_url: Option<String>,
+    /// The number of tweets that were requested
+    pub count: u32,
+    /// The `since_id` the page was requested with
+    pub since_id: u64,
+    /// Same as `since_id`, but a String
+    pub since_id_str: String,
+}
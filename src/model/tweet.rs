@@ -3,6 +3,7 @@ use serde_derive::{Deserialize, Serialize};
 use std::str::FromStr;
 
 use crate::model::coordinates::Coordinates;
+use crate::model::enrichment::MatchingRule;
 use crate::model::entity::{Entity, ExtendedEntity};
 use crate::model::place::Place;
 use crate::model::url::LegacyUrl;
@@ -18,7 +19,7 @@ impl FromStr for Tweet {
 }
 
 /// Represents a tweet
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Tweet {
     /// When the tweet was posted
     #[serde(deserialize_with="datefmt_de", serialize_with="datefmt_ser")]
@@ -27,7 +28,9 @@ pub struct Tweet {
     pub id: u64,
     /// String version of `id`
     pub id_str: String,
-    /// The possibly truncated text of the tweet
+    /// The possibly truncated text of the tweet. REST responses requested
+    /// with `tweet_mode=extended` call this `full_text` and never truncate it.
+    #[serde(alias = "full_text")]
     pub text: String,
     /// The client that posted the tweet
     pub source: String,
@@ -63,7 +66,8 @@ pub struct Tweet {
     pub retweeted_status: Option<Box<Tweet>>,
     /// Approximate count of times tweet was quoted
     pub quote_count: Option<u32>,
-    /// How many times this tweet has been replied to
+    /// How many times this tweet has been replied to. Only sent in streams.
+    #[serde(default)]
     pub reply_count: u32,
     /// How many times this tweet has been retweeted
     pub retweet_count: u32,
@@ -80,10 +84,13 @@ pub struct Tweet {
     /// Whether a link in this tweet (including media) is potentially sensitive
     pub possibly_sensitive: Option<bool>,
     /// What filter level is associated with this tweet. Can be none, low, or medium.
+    /// Only sent in streams.
+    #[serde(default)]
     pub filter_level: String,
     /// BCP 47 language identifier corresponding to machine-detected language of tweet
     pub lang: Option<String>,
-    //  matching_rules: Vec<Rule>,
+    /// The PowerTrack rules that matched this tweet. Only sent by the enterprise APIs.
+    pub matching_rules: Option<Vec<MatchingRule>>,
     //  current_user_retweet: 
     //  scopes
     /// Indicates whether content was removed via DMCA
@@ -141,7 +148,7 @@ impl Tweet {
             }
         }
 
-        return false;
+        false
     }
 
     /// If it's a retweet, the original tweet id is returned. Otherwise
@@ -151,7 +158,7 @@ impl Tweet {
             return rt.id;
         }
 
-        return self.id;
+        self.id
     }
 
     /// Creates a direct URL to the status
@@ -232,7 +239,10 @@ impl Tweet {
 /// Represents a full tweet text and entities when going over 140 characters
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ExtendedTweet {
-    full_text: String,
-    display_text_range: (u32, u32),
-    entities: Entity,
+    /// The untruncated text of the tweet
+    pub full_text: String,
+    /// Start and end offsets of the displayable part of `full_text`
+    pub display_text_range: (u32, u32),
+    /// Entities that have been parsed from `full_text`
+    pub entities: Entity,
 }
\ No newline at end of file
@@ -1,13 +1,21 @@
 use serde_derive::{Deserialize, Serialize};
 
 /// Represents a link from a tweet
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Url {
     pub display_url: String,
     pub expanded_url: String,
     pub indices: Vec<u32>,
     pub url: String,
-    pub unwound: Option<UnwoundUrl>
+    pub unwound: Option<UnwoundUrl>,
+    /// HTTP status of `expanded_url`. Only sent by the enterprise
+    /// expanded URL enrichment, which replaces `expanded_url` with
+    /// the final destination of the link.
+    pub expanded_status: Option<u32>,
+    /// Title of the destination. Only sent by the enterprise APIs.
+    pub expanded_url_title: Option<String>,
+    /// Description of the destination. Only sent by the enterprise APIs.
+    pub expanded_url_description: Option<String>,
 }
 
 /// Holds an unshortened link and some metadata about
@@ -1,10 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde_derive::{Deserialize, Serialize};
 
+use crate::model::enrichment::Derived;
 use crate::util::datetime::{datefmt_de, datefmt_ser};
 
 /// Represents a twitter user
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct User {
     /// Unique identifier for the user
     pub id: u64,
@@ -16,7 +17,8 @@ pub struct User {
     pub screen_name: String,
     /// The user-defined location of the user
     pub location: Option<String>,
-    //  derived: Vec<Enrichment>,
+    /// Locations derived from `location` by the enterprise APIs
+    pub derived: Option<Derived>,
     /// User-provided URL associated with their profile
     pub url: Option<String>,
     /// User-provided description of their profile
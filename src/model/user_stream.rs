@@ -0,0 +1,112 @@
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::model::list::List;
+use crate::model::tweet::Tweet;
+use crate::model::user::User;
+use crate::util::datetime::{datefmt_de, datefmt_ser};
+use crate::util::id::lenient_ids_de;
+
+impl FromStr for UserStreamEvent {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+impl FromStr for Friends {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// The first message of a user stream, listing the ids of
+/// every account the user follows
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Friends {
+    /// Ids of the followed accounts. Streams opened with
+    /// `stringify_friend_ids=true` send these as `friends_str`.
+    #[serde(alias = "friends_str", deserialize_with="lenient_ids_de")]
+    pub friends: Vec<u64>,
+}
+
+/// Sent on a user stream when something happens to the user
+/// or to one of their tweets or lists
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserStreamEvent {
+    /// What happened
+    pub event: EventKind,
+    /// When it happened
+    #[serde(deserialize_with="datefmt_de", serialize_with="datefmt_ser")]
+    pub created_at: DateTime<Utc>,
+    /// The user who did it
+    pub source: User,
+    /// The user it was done to. Same as `source` for `user_update`.
+    pub target: User,
+    /// The tweet or list it was done to, if any
+    pub target_object: Option<TargetObject>,
+}
+
+/// The kinds of user stream events
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    AccessRevoked,
+    Block,
+    Unblock,
+    Favorite,
+    Unfavorite,
+    FavoritedRetweet,
+    RetweetedRetweet,
+    Follow,
+    Unfollow,
+    Mute,
+    Unmute,
+    ListCreated,
+    ListDestroyed,
+    ListUpdated,
+    ListMemberAdded,
+    ListMemberRemoved,
+    ListUserSubscribed,
+    ListUserUnsubscribed,
+    QuotedTweet,
+    UserUpdate,
+    /// An event this crate does not know about
+    #[serde(other)]
+    Unknown,
+}
+
+/// The object a user stream event was done to
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum TargetObject {
+    /// Set for favorite, retweet and quote events
+    Tweet(Tweet),
+    /// Set for `list_*` events
+    List(List),
+    /// Set for anything else, like the app of `access_revoked`
+    Other(serde_json::Value),
+}
+
+impl UserStreamEvent {
+    /// The tweet the event was done to, if any
+    pub fn target_tweet(&self) -> Option<&Tweet> {
+        match &self.target_object {
+            Some(TargetObject::Tweet(tweet)) => Some(tweet),
+            _ => None,
+        }
+    }
+
+    /// The list the event was done to, if any
+    pub fn target_list(&self) -> Option<&List> {
+        match &self.target_object {
+            Some(TargetObject::List(list)) => Some(list),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::model::v2::response::Includes;
+use crate::model::v2::tweet::{Attachments, Entities};
+use crate::model::DirectMessage;
+use crate::util::id::{id_de, id_ser, opt_id_de, opt_id_ser};
+
+/// Represents an event from the v2 `dm_events` endpoints. Events of a
+/// type this crate does not know about are kept with whichever of these
+/// fields they have and `DmEventType::Unknown`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DmEvent {
+    /// The unique id for the event
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub id: u64,
+    /// What happened
+    pub event_type: DmEventType,
+    /// Text of the message. Only set for `MessageCreate` events.
+    pub text: Option<String>,
+    /// Id of the user who sent the message or changed the participants
+    #[serde(default, deserialize_with="opt_id_de", serialize_with="opt_id_ser")]
+    pub sender_id: Option<u64>,
+    /// Id of the conversation the event belongs to
+    pub dm_conversation_id: Option<String>,
+    /// When the event happened
+    pub created_at: Option<DateTime<Utc>>,
+    /// Ids of the users who joined or left. Only set for participant events.
+    pub participant_ids: Option<Vec<String>>,
+    /// Tweets shared in the message
+    pub referenced_tweets: Option<Vec<DmReferencedTweet>>,
+    /// Entities that have been parsed from the text
+    pub entities: Option<Entities>,
+    /// Media attached to the message
+    pub attachments: Option<Attachments>,
+}
+
+/// The kinds of direct message events
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum DmEventType {
+    MessageCreate,
+    ParticipantsJoin,
+    ParticipantsLeave,
+    /// An event type this crate does not know about
+    #[serde(other)]
+    Unknown,
+}
+
+/// A tweet shared in a direct message
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DmReferencedTweet {
+    /// Id of the tweet
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub id: u64,
+}
+
+/// The response of creating a conversation or sending a message with
+/// the v2 `dm_conversations` endpoints
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DmConversationCreated {
+    /// Id of the conversation the message was sent to
+    pub dm_conversation_id: String,
+    /// Id of the event of the sent message
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub dm_event_id: u64,
+}
+
+impl DmEvent {
+    /// Converts a `MessageCreate` event into a `DirectMessage`. The
+    /// sender and attached media are looked up in `includes`.
+    pub fn to_direct_message(&self, includes: &Includes) -> Option<DirectMessage> {
+        if self.event_type != DmEventType::MessageCreate {
+            return None;
+        }
+
+        let sender_id = self.sender_id.unwrap_or_default();
+        let conversation_id = self.dm_conversation_id.clone().unwrap_or_default();
+
+        //  One to one conversations are named after both participants
+        let recipient_id = conversation_id.split_once('-')
+                                          .and_then(|(a, b)| Some((a.parse::<u64>().ok()?, b.parse::<u64>().ok()?)))
+                                          .map(|(a, b)| if a == sender_id { b } else { a });
+
+        Some(DirectMessage {
+            id: self.id,
+            conversation_id,
+            sender_id,
+            recipient_id,
+            created_at: self.created_at.unwrap_or_default(),
+            text: self.text.clone().unwrap_or_default(),
+            entities: self.entities.as_ref().map(Entities::to_v1).unwrap_or_default(),
+            media: self.attachments
+                       .as_ref()
+                       .map_or_else(Vec::new, |attachments| attachments.to_v1_media(self.entities.as_ref(), includes)),
+            sender: includes.user(sender_id).map(|user| user.to_v1()),
+            source_app_id: None,
+        })
+    }
+}
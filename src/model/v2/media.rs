@@ -0,0 +1,103 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::model::v2::tweet::UrlEntity;
+use crate::model::{MediaType, Size, Sizes, Variant, VideoInfo};
+
+/// Represents media as returned by the v2 API
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Media {
+    /// Unique key of the media, referenced from `Attachments::media_keys`
+    pub media_key: String,
+    /// The type of media
+    #[serde(rename = "type")]
+    pub kind: MediaType,
+    /// Location of the image for photos
+    pub url: Option<String>,
+    /// Location of a still image for videos and gifs
+    pub preview_image_url: Option<String>,
+    /// Width in pixels
+    pub width: Option<u32>,
+    /// Height in pixels
+    pub height: Option<u32>,
+    /// How long the video lasts in milliseconds
+    pub duration_ms: Option<u32>,
+    /// Description of the media for accessibility
+    pub alt_text: Option<String>,
+    /// Quality variants available for videos and gifs
+    pub variants: Option<Vec<MediaVariant>>,
+}
+
+/// Holds information about a video's quality and location
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MediaVariant {
+    /// What bitrate the video has
+    pub bit_rate: Option<u32>,
+    /// What type of content this variant contains
+    pub content_type: String,
+    /// The location of the video
+    pub url: String,
+}
+
+impl Media {
+    /// Converts this media into the v1.1 representation. `link` is the
+    /// url entity of the tweet or message pointing at this media, which
+    /// holds the t.co link and its position in the text.
+    pub fn to_v1(&self, link: Option<&UrlEntity>) -> crate::model::Media {
+        //  Keys are the numeric media id prefixed with the media category
+        let id = self.media_key
+                     .split_once('_')
+                     .and_then(|(_, id)| id.parse().ok())
+                     .unwrap_or_default();
+        let media_url = self.url.clone().or_else(|| self.preview_image_url.clone()).unwrap_or_default();
+        let (w, h) = (self.width.unwrap_or_default(), self.height.unwrap_or_default());
+        let size = |resize: &str| Size { w, h, resize: resize.to_string() };
+
+        let video_info = self.variants.as_ref().map(|variants| VideoInfo {
+            aspect_ratio: aspect_ratio(w, h),
+            duration_millis: self.duration_ms,
+            variants: variants.iter()
+                              .map(|variant| Variant {
+                                  bitrate: variant.bit_rate,
+                                  content_type: variant.content_type.clone(),
+                                  url: variant.url.clone(),
+                              })
+                              .collect(),
+        });
+
+        crate::model::Media {
+            display_url: link.and_then(|link| link.display_url.clone()).unwrap_or_default(),
+            expanded_url: link.and_then(|link| link.expanded_url.clone()).unwrap_or_default(),
+            id,
+            id_str: id.to_string(),
+            indices: link.map_or_else(Vec::new, |link| vec![link.start, link.end]),
+            media_url: media_url.replacen("https://", "http://", 1),
+            media_url_https: media_url,
+            sizes: Sizes {
+                thumb: size("crop"),
+                large: size("fit"),
+                medium: size("fit"),
+                small: size("fit"),
+            },
+            source_status_id: None,
+            source_status_id_str: None,
+            kind: self.kind.clone(),
+            url: link.map(|link| link.url.clone()).unwrap_or_default(),
+            video_info,
+            additional_media_info: None,
+        }
+    }
+}
+
+/// Reduces a width and height to the smallest ratio, like 16:9
+fn aspect_ratio(w: u32, h: u32) -> (u32, u32) {
+    let (mut a, mut b) = (w, h);
+
+    while b != 0 {
+        let rest = a % b;
+        a = b;
+        b = rest;
+    }
+
+    //  Unknown dimensions are kept as they are
+    w.checked_div(a).zip(h.checked_div(a)).unwrap_or((w, h))
+}
@@ -0,0 +1,19 @@
+//! Models for the v2 API.
+//!
+//! The v2 API returns much smaller objects than v1.1 and moves
+//! referenced objects into a separate `includes` section. Most objects
+//! can be converted into their v1.1 counterpart with `to_v1`.
+
+mod dm;
+mod media;
+mod place;
+mod response;
+mod tweet;
+mod user;
+
+pub use dm::{DmConversationCreated, DmEvent, DmEventType, DmReferencedTweet};
+pub use media::{Media, MediaVariant};
+pub use place::{Place, PlaceGeo};
+pub use response::{Includes, Meta, Problem, Response};
+pub use tweet::{Attachments, Entities, Geo, Mention, ReferenceType, ReferencedTweet, Tag, Tweet, TweetMetrics, UrlEntity};
+pub use user::{User, UserMetrics, Withheld};
@@ -0,0 +1,52 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::model::BoundingBox;
+
+/// Represents a specific named location as returned by the v2 API
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Place {
+    /// Unique id representing this place
+    pub id: String,
+    /// Full, human-readable place name
+    pub full_name: String,
+    /// Short, human-readable place name
+    pub name: Option<String>,
+    /// Country that this place is located in
+    pub country: Option<String>,
+    /// Country code that this place is located in
+    pub country_code: Option<String>,
+    /// Type of location represented by this place
+    pub place_type: Option<String>,
+    /// The area covered by this place
+    pub geo: Option<PlaceGeo>,
+}
+
+/// A GeoJSON feature describing the area of a place
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct PlaceGeo {
+    /// West, south, east and north edges of the area
+    pub bbox: Option<(f64, f64, f64, f64)>,
+}
+
+impl Place {
+    /// Converts this place into the v1.1 representation
+    pub fn to_v1(&self) -> crate::model::Place {
+        let coordinates = match self.geo.as_ref().and_then(|geo| geo.bbox) {
+            Some((west, south, east, north)) => {
+                vec![vec![(west, south), (east, south), (east, north), (west, north)]]
+            }
+            None => Vec::new(),
+        };
+
+        crate::model::Place {
+            id: self.id.clone(),
+            url: format!("https://api.twitter.com/1.1/geo/id/{}.json", self.id),
+            place_type: self.place_type.clone().unwrap_or_default(),
+            name: self.name.clone().unwrap_or_else(|| self.full_name.clone()),
+            full_name: self.full_name.clone(),
+            country_code: self.country_code.clone().unwrap_or_default(),
+            country: self.country.clone().unwrap_or_default(),
+            bounding_box: BoundingBox { coordinates },
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::model::v2::media::Media;
+use crate::model::v2::place::Place;
+use crate::model::v2::tweet::Tweet;
+use crate::model::v2::user::User;
+
+/// The envelope every v2 endpoint responds with
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Response<T> {
+    /// The requested object or objects
+    pub data: Option<T>,
+    /// Objects referenced from `data` that were expanded
+    #[serde(default)]
+    pub includes: Includes,
+    /// Problems with parts of the request. Lookups of several ids
+    /// report missing or inaccessible ones here.
+    #[serde(default)]
+    pub errors: Vec<Problem>,
+    /// Pagination information
+    pub meta: Option<Meta>,
+}
+
+/// Objects referenced from the data of a response
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Includes {
+    /// Referenced tweets
+    #[serde(default)]
+    pub tweets: Vec<Tweet>,
+    /// Referenced users
+    #[serde(default)]
+    pub users: Vec<User>,
+    /// Referenced media
+    #[serde(default)]
+    pub media: Vec<Media>,
+    /// Referenced places
+    #[serde(default)]
+    pub places: Vec<Place>,
+}
+
+impl Includes {
+    /// Finds an included tweet by id
+    pub fn tweet(&self, id: u64) -> Option<&Tweet> {
+        self.tweets.iter().find(|tweet| tweet.id == id)
+    }
+
+    /// Finds an included user by id
+    pub fn user(&self, id: u64) -> Option<&User> {
+        self.users.iter().find(|user| user.id == id)
+    }
+
+    /// Finds included media by key
+    pub fn media(&self, key: &str) -> Option<&Media> {
+        self.media.iter().find(|media| media.media_key == key)
+    }
+
+    /// Finds an included place by id
+    pub fn place(&self, id: &str) -> Option<&Place> {
+        self.places.iter().find(|place| place.id == id)
+    }
+}
+
+/// A problem details object describing why part of a request failed
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Problem {
+    /// Short summary of the problem
+    pub title: String,
+    /// Explanation specific to this occurrence of the problem
+    pub detail: Option<String>,
+    /// URI identifying the type of problem
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    /// The type of resource the problem is about, like "tweet" or "user"
+    pub resource_type: Option<String>,
+    /// Id of the resource the problem is about
+    pub resource_id: Option<String>,
+    /// The request parameter the problem is about
+    pub parameter: Option<String>,
+    /// The value of the request parameter
+    pub value: Option<Value>,
+    /// HTTP status code of the problem
+    pub status: Option<u16>,
+}
+
+impl Problem {
+    /// Whether the resource does not exist, for example a deleted tweet
+    pub fn is_not_found(&self) -> bool {
+        self.kind.as_deref() == Some("https://api.twitter.com/2/problems/resource-not-found")
+    }
+
+    /// Whether the resource exists but may not be accessed, for
+    /// example a tweet from a protected account
+    pub fn is_not_authorized(&self) -> bool {
+        self.kind.as_deref() == Some("https://api.twitter.com/2/problems/not-authorized-for-resource")
+    }
+}
+
+/// Pagination information of a response
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Meta {
+    /// How many objects are in `data`
+    pub result_count: Option<u32>,
+    /// Token to request the next page with
+    pub next_token: Option<String>,
+    /// Token to request the previous page with
+    pub previous_token: Option<String>,
+    /// Id of the newest object in `data`
+    pub newest_id: Option<String>,
+    /// Id of the oldest object in `data`
+    pub oldest_id: Option<String>,
+}
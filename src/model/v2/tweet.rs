@@ -0,0 +1,331 @@
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::model::v2::response::Includes;
+use crate::model::v2::user::Withheld;
+use crate::model::{Coordinates, Entity, ExtendedEntity, Hashtag, Media, Symbol, UnwoundUrl, Url, UserMention};
+use crate::util::id::{id_de, id_ser, opt_id_de, opt_id_ser};
+use crate::util::snowflake::snowflake_time;
+
+/// Represents a tweet as returned by the v2 API
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Tweet {
+    /// The unique id for the tweet
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub id: u64,
+    /// The full text of the tweet
+    pub text: String,
+    /// Id of the user who posted the tweet
+    #[serde(default, deserialize_with="opt_id_de", serialize_with="opt_id_ser")]
+    pub author_id: Option<u64>,
+    /// When the tweet was posted
+    pub created_at: Option<DateTime<Utc>>,
+    /// Id of the tweet that started the conversation this tweet belongs to
+    #[serde(default, deserialize_with="opt_id_de", serialize_with="opt_id_ser")]
+    pub conversation_id: Option<u64>,
+    /// If this tweet is a reply, this will contain the original author id
+    #[serde(default, deserialize_with="opt_id_de", serialize_with="opt_id_ser")]
+    pub in_reply_to_user_id: Option<u64>,
+    /// BCP 47 language identifier corresponding to machine-detected language of tweet
+    pub lang: Option<String>,
+    /// The client that posted the tweet
+    pub source: Option<String>,
+    /// Whether a link in this tweet (including media) is potentially sensitive
+    pub possibly_sensitive: Option<bool>,
+    /// Who can reply to this tweet
+    pub reply_settings: Option<String>,
+    /// Tweets that this tweet retweets, quotes or replies to
+    pub referenced_tweets: Option<Vec<ReferencedTweet>>,
+    /// Engagement counts of the tweet
+    pub public_metrics: Option<TweetMetrics>,
+    /// Entities that have been parsed from the tweet
+    pub entities: Option<Entities>,
+    /// Media and polls attached to the tweet
+    pub attachments: Option<Attachments>,
+    /// The place or location attached to the tweet
+    pub geo: Option<Geo>,
+    /// Withholding information of the tweet
+    pub withheld: Option<Withheld>,
+    /// Ids of every version of the tweet when it has been edited
+    pub edit_history_tweet_ids: Option<Vec<String>>,
+}
+
+/// A tweet that another tweet refers to
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReferencedTweet {
+    /// How the tweet is referenced
+    #[serde(rename = "type")]
+    pub kind: ReferenceType,
+    /// Id of the referenced tweet
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub id: u64,
+}
+
+/// The ways a tweet can refer to another tweet
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceType {
+    Retweeted,
+    Quoted,
+    RepliedTo,
+}
+
+/// Engagement counts of a tweet
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TweetMetrics {
+    /// How many times this tweet has been retweeted
+    pub retweet_count: u32,
+    /// How many times this tweet has been replied to
+    pub reply_count: u32,
+    /// How many times this tweet has been liked
+    pub like_count: u32,
+    /// How many times this tweet has been quoted
+    pub quote_count: u32,
+    /// How many times this tweet has been viewed
+    pub impression_count: Option<u64>,
+    /// How many times this tweet has been bookmarked
+    pub bookmark_count: Option<u32>,
+}
+
+/// Contains information on various parsed out pieces of tweets
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Entities {
+    /// Hashtags included in the tweet
+    pub hashtags: Option<Vec<Tag>>,
+    /// $cashtags included in the tweet
+    pub cashtags: Option<Vec<Tag>>,
+    /// Users mentioned in the tweet
+    pub mentions: Option<Vec<Mention>>,
+    /// Links included in the tweet
+    pub urls: Option<Vec<UrlEntity>>,
+}
+
+/// Represents a hashtag or cashtag
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Tag {
+    /// Where the tag begins in the text
+    pub start: u32,
+    /// Where the tag ends in the text
+    pub end: u32,
+    /// Text of the tag without the leading `#` or `$`
+    pub tag: String,
+}
+
+/// Represents a @mention of a user
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Mention {
+    /// Where the mention begins in the text
+    pub start: u32,
+    /// Where the mention ends in the text
+    pub end: u32,
+    /// Handle of the mentioned user
+    pub username: String,
+    /// Id of the mentioned user
+    #[serde(default, deserialize_with="opt_id_de", serialize_with="opt_id_ser")]
+    pub id: Option<u64>,
+}
+
+/// Represents a link from a tweet
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UrlEntity {
+    /// Where the link begins in the text
+    pub start: u32,
+    /// Where the link ends in the text
+    pub end: u32,
+    /// The t.co version of the link
+    pub url: String,
+    /// The original link
+    pub expanded_url: Option<String>,
+    /// The link as displayed in the tweet text
+    pub display_url: Option<String>,
+    /// Set when the link points to attached media
+    pub media_key: Option<String>,
+    /// HTTP status of the unwound link
+    pub status: Option<u32>,
+    /// Title of the destination
+    pub title: Option<String>,
+    /// Description of the destination
+    pub description: Option<String>,
+    /// Final destination of the link
+    pub unwound_url: Option<String>,
+}
+
+/// Media and polls attached to a tweet
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Attachments {
+    /// Keys of attached media, found in `Includes::media`
+    pub media_keys: Option<Vec<String>>,
+    /// Ids of attached polls
+    pub poll_ids: Option<Vec<String>>,
+}
+
+/// The location attached to a tweet
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Geo {
+    /// Id of the attached place, found in `Includes::places`
+    pub place_id: Option<String>,
+    /// Exact location of the tweet
+    pub coordinates: Option<Coordinates>,
+}
+
+impl Tweet {
+    /// Id of the tweet this one references in the given way
+    pub fn referenced(&self, kind: ReferenceType) -> Option<u64> {
+        self.referenced_tweets
+            .iter()
+            .flatten()
+            .find(|reference| reference.kind == kind)
+            .map(|reference| reference.id)
+    }
+
+    /// Converts this tweet into the v1.1 representation. The author,
+    /// media and any retweeted or quoted tweets are looked up in
+    /// `includes`. Media missing from `includes` are left out.
+    pub fn to_v1(&self, includes: &Includes) -> crate::model::Tweet {
+        let author_id = self.author_id.unwrap_or_default();
+        let user = includes.user(author_id)
+                           .map(|user| user.to_v1())
+                           .unwrap_or_else(|| crate::model::User {
+                               id: author_id,
+                               id_str: author_id.to_string(),
+                               ..Default::default()
+                           });
+
+        let embedded = |kind| {
+            self.referenced(kind)
+                .and_then(|id| includes.tweet(id))
+                .map(|tweet| Box::new(tweet.to_v1(includes)))
+        };
+
+        let media = || {
+            self.attachments
+                .as_ref()
+                .map_or_else(Vec::new, |attachments| attachments.to_v1_media(self.entities.as_ref(), includes))
+        };
+        let mut entities = self.entities.as_ref().map(Entities::to_v1).unwrap_or_default();
+        let extended_entities = Some(media()).filter(|media| !media.is_empty())
+                                             .map(|media| ExtendedEntity { media });
+        if extended_entities.is_some() {
+            entities.media = Some(media());
+        }
+
+        let metrics = self.public_metrics.as_ref();
+        let in_reply_to_status_id = self.referenced(ReferenceType::RepliedTo);
+        let quoted_status_id = self.referenced(ReferenceType::Quoted);
+        let in_reply_to_screen_name = self.in_reply_to_user_id
+                                          .and_then(|id| includes.user(id))
+                                          .map(|user| user.username.clone());
+
+        crate::model::Tweet {
+            created_at: self.created_at.or_else(|| snowflake_time(self.id)).unwrap_or_default(),
+            id: self.id,
+            id_str: self.id.to_string(),
+            text: self.text.clone(),
+            source: self.source.clone().unwrap_or_default(),
+            in_reply_to_status_id,
+            in_reply_to_status_id_str: in_reply_to_status_id.map(|id| id.to_string()),
+            in_reply_to_user_id: self.in_reply_to_user_id,
+            in_reply_to_user_id_str: self.in_reply_to_user_id.map(|id| id.to_string()),
+            in_reply_to_screen_name,
+            user,
+            coordinates: self.geo.as_ref().and_then(|geo| geo.coordinates.clone()),
+            place: self.geo
+                       .as_ref()
+                       .and_then(|geo| geo.place_id.as_ref())
+                       .and_then(|id| includes.place(id))
+                       .map(|place| place.to_v1()),
+            quoted_status_id,
+            quoted_status_id_str: quoted_status_id.map(|id| id.to_string()),
+            is_quote_status: quoted_status_id.is_some(),
+            quoted_status: embedded(ReferenceType::Quoted),
+            retweeted_status: embedded(ReferenceType::Retweeted),
+            quote_count: metrics.map(|m| m.quote_count),
+            reply_count: metrics.map_or(0, |m| m.reply_count),
+            retweet_count: metrics.map_or(0, |m| m.retweet_count),
+            favorite_count: metrics.map(|m| m.like_count),
+            entities: Some(entities),
+            extended_entities,
+            possibly_sensitive: self.possibly_sensitive,
+            lang: self.lang.clone(),
+            withheld_copyright: self.withheld.as_ref().and_then(|w| w.copyright),
+            withheld_in_countries: self.withheld.as_ref().map(|w| w.country_codes.clone()),
+            withheld_scope: self.withheld.as_ref().and_then(|w| w.scope.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+impl Attachments {
+    /// Converts the attached media into the v1.1 representation. The
+    /// media are looked up in `includes` and their links in `entities`.
+    /// Media missing from `includes` are left out.
+    pub fn to_v1_media(&self, entities: Option<&Entities>, includes: &Includes) -> Vec<Media> {
+        let link = |key: &str| {
+            entities.iter()
+                    .flat_map(|entities| entities.urls.iter().flatten())
+                    .find(|url| url.media_key.as_deref() == Some(key))
+        };
+
+        self.media_keys
+            .iter()
+            .flatten()
+            .filter_map(|key| includes.media(key))
+            .map(|media| media.to_v1(link(&media.media_key)))
+            .collect()
+    }
+}
+
+impl Entities {
+    /// Converts these entities into the v1.1 representation
+    pub fn to_v1(&self) -> Entity {
+        Entity {
+            hashtags: self.hashtags
+                          .iter()
+                          .flatten()
+                          .map(|tag| Hashtag {
+                              indices: vec![tag.start, tag.end],
+                              text: tag.tag.clone(),
+                          })
+                          .collect(),
+            symbols: self.cashtags
+                         .iter()
+                         .flatten()
+                         .map(|tag| Symbol {
+                             indices: vec![tag.start, tag.end],
+                             text: tag.tag.clone(),
+                         })
+                         .collect(),
+            user_mentions: self.mentions
+                               .iter()
+                               .flatten()
+                               .map(|mention| UserMention {
+                                   id: mention.id,
+                                   id_str: mention.id.map(|id| id.to_string()),
+                                   indices: vec![mention.start, mention.end],
+                                   name: None,
+                                   screen_name: mention.username.clone(),
+                               })
+                               .collect(),
+            urls: self.urls
+                      .iter()
+                      .flatten()
+                      .filter(|url| url.media_key.is_none())
+                      .map(|url| Url {
+                          display_url: url.display_url.clone().unwrap_or_default(),
+                          expanded_url: url.expanded_url.clone().unwrap_or_else(|| url.url.clone()),
+                          indices: vec![url.start, url.end],
+                          url: url.url.clone(),
+                          unwound: url.unwound_url.as_ref().map(|unwound| UnwoundUrl {
+                              url: unwound.clone(),
+                              status: url.status.unwrap_or_default(),
+                              title: url.title.clone().unwrap_or_default(),
+                              description: url.description.clone().unwrap_or_default(),
+                          }),
+                          ..Default::default()
+                      })
+                      .collect(),
+            media: None,
+            polls: None,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::util::id::{id_de, id_ser, opt_id_de, opt_id_ser};
+
+/// Represents a twitter user as returned by the v2 API
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct User {
+    /// Unique identifier for the user
+    #[serde(deserialize_with="id_de", serialize_with="id_ser")]
+    pub id: u64,
+    /// The user's display name
+    pub name: String,
+    /// The user's handle
+    pub username: String,
+    /// When the account was created
+    pub created_at: Option<DateTime<Utc>>,
+    /// User-provided description of their profile
+    pub description: Option<String>,
+    /// The user-defined location of the user
+    pub location: Option<String>,
+    /// User-provided URL associated with their profile
+    pub url: Option<String>,
+    /// Whether this user's tweets are protected or not
+    pub protected: Option<bool>,
+    /// Whether this user is verified or not
+    pub verified: Option<bool>,
+    /// User's uploaded profile image
+    pub profile_image_url: Option<String>,
+    /// Id of the tweet pinned to the user's profile
+    #[serde(default, deserialize_with="opt_id_de", serialize_with="opt_id_ser")]
+    pub pinned_tweet_id: Option<u64>,
+    /// Follower and activity counts of the user
+    pub public_metrics: Option<UserMetrics>,
+    /// Withholding information of the user
+    pub withheld: Option<Withheld>,
+}
+
+/// Follower and activity counts of a user
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct UserMetrics {
+    /// How many followers this user has
+    pub followers_count: u32,
+    /// How many users this user follows
+    pub following_count: u32,
+    /// How many tweets and retweets this user has
+    pub tweet_count: u32,
+    /// How many public lists this user is a member of
+    pub listed_count: u32,
+    /// How many tweets this user has liked
+    pub like_count: Option<u32>,
+}
+
+/// Withholding information of a tweet or user
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Withheld {
+    /// Whether content was withheld because of a DMCA complaint
+    pub copyright: Option<bool>,
+    /// Countries the content is withheld in
+    #[serde(default)]
+    pub country_codes: Vec<String>,
+    /// Whether the "tweet" or the "user" is withheld
+    pub scope: Option<String>,
+}
+
+impl User {
+    /// Converts this user into the v1.1 representation. Fields that
+    /// were not requested from the API are left at their defaults.
+    pub fn to_v1(&self) -> crate::model::User {
+        let metrics = self.public_metrics.as_ref();
+
+        crate::model::User {
+            id: self.id,
+            id_str: self.id.to_string(),
+            name: self.name.clone(),
+            screen_name: self.username.clone(),
+            location: self.location.clone(),
+            url: self.url.clone(),
+            description: self.description.clone(),
+            protected: self.protected.unwrap_or_default(),
+            verified: self.verified.unwrap_or_default(),
+            followers_count: metrics.map_or(0, |m| m.followers_count),
+            friends_count: metrics.map_or(0, |m| m.following_count),
+            listed_count: metrics.map_or(0, |m| m.listed_count),
+            favourites_count: metrics.and_then(|m| m.like_count).unwrap_or_default(),
+            statuses_count: metrics.map_or(0, |m| m.tweet_count),
+            created_at: self.created_at.unwrap_or_default(),
+            profile_image_url_https: self.profile_image_url.clone().unwrap_or_default(),
+            withheld_in_countries: self.withheld.as_ref().map(|w| w.country_codes.clone()),
+            withheld_scope: self.withheld.as_ref().and_then(|w| w.scope.clone()),
+            ..Default::default()
+        }
+    }
+}
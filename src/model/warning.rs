@@ -0,0 +1,40 @@
+use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
+
+impl FromStr for Warning {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// Sent on streams requested with `stall_warnings=true` when the
+/// client is falling behind, and on user streams that follow more
+/// users than they can deliver
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Warning {
+    /// Contains information about the warning
+    pub warning: WarningFields,
+}
+
+/// Holds information on a Warning payload
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WarningFields {
+    /// `FALLING_BEHIND` or `FOLLOWS_OVER_LIMIT`
+    pub code: String,
+    /// Explanation of the warning
+    pub message: String,
+    /// How full the queue of undelivered messages is, in percent.
+    /// The stream is disconnected at 100.
+    pub percent_full: Option<u32>,
+    /// The user that follows too many accounts
+    pub user_id: Option<u64>,
+}
+
+impl Warning {
+    /// Whether the client reads too slowly to keep up with the stream
+    pub fn is_falling_behind(&self) -> bool {
+        self.warning.code == "FALLING_BEHIND"
+    }
+}
@@ -0,0 +1,56 @@
+use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
+
+impl FromStr for StatusWithheld {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+impl FromStr for UserWithheld {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// Sent when a tweet has been withheld in certain countries
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StatusWithheld {
+    /// Contains information about the withheld tweet
+    pub status_withheld: StatusWithheldFields,
+}
+
+/// Holds information on a StatusWithheld payload
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StatusWithheldFields {
+    /// Id of the withheld tweet
+    pub id: u64,
+    /// Id of the user who posted the tweet
+    pub user_id: u64,
+    /// Countries the tweet is withheld in
+    pub withheld_in_countries: Vec<String>,
+    /// Timestamp of the payload
+    pub timestamp_ms: Option<String>,
+}
+
+/// Sent when a user has been withheld in certain countries
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserWithheld {
+    /// Contains information about the withheld user
+    pub user_withheld: UserWithheldFields,
+}
+
+/// Holds information on a UserWithheld payload
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserWithheldFields {
+    /// Id of the withheld user
+    pub id: u64,
+    /// Countries the user is withheld in
+    pub withheld_in_countries: Vec<String>,
+    /// Timestamp of the payload
+    pub timestamp_ms: Option<String>,
+}
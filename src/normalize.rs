@@ -0,0 +1,440 @@
+//! Flattens nested tweets into relational records.
+//!
+//! A single tweet can embed other full tweets and users through
+//! `retweeted_status` and `quoted_status`. The `Normalizer` walks
+//! every level of that nesting and emits one deduplicated row per
+//! tweet, user, media item and link, connected by their ids.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+
+use crate::model::{Media, MediaType, Tweet, Url, User};
+
+/// A flat representation of a single tweet
+#[derive(Clone, Debug, PartialEq)]
+pub struct TweetRecord {
+    /// The unique id for the tweet
+    pub id: u64,
+    /// When the tweet was posted
+    pub created_at: DateTime<Utc>,
+    /// Id of the user who posted the tweet
+    pub user_id: u64,
+    /// The full, untruncated text of the tweet
+    pub full_text: String,
+    /// The client that posted the tweet
+    pub source: String,
+    /// BCP 47 language identifier of the tweet
+    pub lang: Option<String>,
+    /// Id of the tweet this one replies to
+    pub in_reply_to_status_id: Option<u64>,
+    /// Id of the user this tweet replies to
+    pub in_reply_to_user_id: Option<u64>,
+    /// Id of the retweeted tweet when this is a retweet
+    pub retweeted_status_id: Option<u64>,
+    /// Id of the quoted tweet when this is a quote
+    pub quoted_status_id: Option<u64>,
+    /// Id of the place the tweet is associated with
+    pub place_id: Option<String>,
+    /// Coordinates reported for the tweet
+    pub coordinates: Option<(f64, f64)>,
+    /// Approximate count of times the tweet was quoted
+    pub quote_count: Option<u32>,
+    /// How many times the tweet has been replied to
+    pub reply_count: u32,
+    /// How many times the tweet has been retweeted
+    pub retweet_count: u32,
+    /// How many times the tweet has been favorited
+    pub favorite_count: Option<u32>,
+    /// Whether the tweet is potentially sensitive
+    pub possibly_sensitive: bool,
+}
+
+/// A flat representation of a user at the time a tweet was captured
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserRecord {
+    /// Unique identifier for the user
+    pub id: u64,
+    /// The user's handle
+    pub screen_name: String,
+    /// The user's display name
+    pub name: String,
+    /// The user-defined location of the user
+    pub location: Option<String>,
+    /// User-provided description of their profile
+    pub description: Option<String>,
+    /// User-provided URL associated with their profile
+    pub url: Option<String>,
+    /// Whether this user's tweets are protected or not
+    pub protected: bool,
+    /// Whether this user is verified or not
+    pub verified: bool,
+    /// How many followers this user has
+    pub followers_count: u32,
+    /// How many friends this user has
+    pub friends_count: u32,
+    /// How many public lists this user is a member of
+    pub listed_count: u32,
+    /// How many favorites this user has
+    pub favourites_count: u32,
+    /// How many tweets and retweets this user has
+    pub statuses_count: u32,
+    /// When the account was created
+    pub created_at: DateTime<Utc>,
+    /// User's uploaded profile image
+    pub profile_image_url_https: String,
+}
+
+/// A flat representation of a media item attached to a tweet
+#[derive(Clone, Debug, PartialEq)]
+pub struct MediaRecord {
+    /// Unique id of the media
+    pub id: u64,
+    /// Id of the tweet the media is attached to
+    pub tweet_id: u64,
+    /// The type of media
+    pub kind: MediaType,
+    /// Location of the image or video thumbnail
+    pub media_url_https: String,
+    /// Best available location of the media itself, see `Media::url`
+    pub url: Option<String>,
+    /// The t.co link that points to the media
+    pub short_url: String,
+    /// Link to the media page on twitter
+    pub expanded_url: String,
+    /// Id of the tweet the media was originally posted in
+    pub source_status_id: Option<u64>,
+}
+
+/// A flat representation of a link contained in a tweet
+#[derive(Clone, Debug, PartialEq)]
+pub struct UrlRecord {
+    /// Id of the tweet the link is contained in
+    pub tweet_id: u64,
+    /// The t.co version of the link
+    pub url: String,
+    /// The original link
+    pub expanded_url: String,
+    /// The link as displayed in the tweet text
+    pub display_url: String,
+    /// The final destination of the link, if it was unwound
+    pub unwound_url: Option<String>,
+}
+
+/// Deduplicated rows produced by a `Normalizer`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Records {
+    /// Every distinct tweet, with embedded tweets before the tweets embedding them
+    pub tweets: Vec<TweetRecord>,
+    /// Every distinct user
+    pub users: Vec<UserRecord>,
+    /// Every distinct media item
+    pub media: Vec<MediaRecord>,
+    /// Every distinct link
+    pub urls: Vec<UrlRecord>,
+}
+
+/// Accumulates deduplicated records across any number of tweets.
+///
+/// The first occurrence of a tweet or user wins, so feed tweets in
+/// the order they were captured if the earliest snapshot matters.
+#[derive(Debug, Default)]
+pub struct Normalizer {
+    records: Records,
+    seen_tweets: HashSet<u64>,
+    seen_users: HashSet<u64>,
+    seen_media: HashSet<(u64, u64)>,
+    seen_urls: HashSet<(u64, String)>,
+}
+
+impl Normalizer {
+    /// Creates an empty normalizer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a tweet and everything embedded in it
+    pub fn push(&mut self, tweet: &Tweet) {
+        //  Children first so that rows referenced through foreign
+        //  keys are always emitted before the rows referencing them.
+        if let Some(rt) = &tweet.retweeted_status {
+            self.push(rt);
+        }
+
+        if let Some(quote) = &tweet.quoted_status {
+            self.push(quote);
+        }
+
+        self.push_user(&tweet.user);
+
+        if !self.seen_tweets.insert(tweet.id) {
+            return;
+        }
+
+        self.records.tweets.push(TweetRecord {
+            id: tweet.id,
+            created_at: tweet.created_at,
+            user_id: tweet.user.id,
+            full_text: tweet.full_text(),
+            source: tweet.source.clone(),
+            lang: tweet.lang.clone(),
+            in_reply_to_status_id: tweet.in_reply_to_status_id,
+            in_reply_to_user_id: tweet.in_reply_to_user_id,
+            retweeted_status_id: tweet.retweeted_status.as_ref().map(|rt| rt.id),
+            quoted_status_id: tweet.quoted_status_id,
+            place_id: tweet.place.as_ref().map(|place| place.id.clone()),
+            coordinates: tweet.coordinates.as_ref().map(|c| c.coordinates),
+            quote_count: tweet.quote_count,
+            reply_count: tweet.reply_count,
+            retweet_count: tweet.retweet_count,
+            favorite_count: tweet.favorite_count,
+            possibly_sensitive: tweet.is_sensitive(),
+        });
+
+        //  The entities of a retweet are copies of the original's,
+        //  which have already been recorded against the original.
+        if tweet.is_retweet() {
+            return;
+        }
+
+        for media in tweet_media(tweet) {
+            self.push_media(tweet.id, media);
+        }
+
+        for url in tweet_urls(tweet) {
+            self.push_url(tweet.id, url);
+        }
+    }
+
+    /// Returns all the records gathered so far
+    pub fn finish(self) -> Records {
+        self.records
+    }
+
+    fn push_user(&mut self, user: &User) {
+        if !self.seen_users.insert(user.id) {
+            return;
+        }
+
+        self.records.users.push(UserRecord {
+            id: user.id,
+            screen_name: user.screen_name.clone(),
+            name: user.name.clone(),
+            location: user.location.clone(),
+            description: user.description.clone(),
+            url: user.url.clone(),
+            protected: user.protected,
+            verified: user.verified,
+            followers_count: user.followers_count,
+            friends_count: user.friends_count,
+            listed_count: user.listed_count,
+            favourites_count: user.favourites_count,
+            statuses_count: user.statuses_count,
+            created_at: user.created_at,
+            profile_image_url_https: user.profile_image_url_https.clone(),
+        });
+    }
+
+    fn push_media(&mut self, tweet_id: u64, media: &Media) {
+        if !self.seen_media.insert((tweet_id, media.id)) {
+            return;
+        }
+
+        self.records.media.push(MediaRecord {
+            id: media.id,
+            tweet_id,
+            kind: media.kind.clone(),
+            media_url_https: media.media_url_https.clone(),
+            url: media.url(),
+            short_url: media.url.clone(),
+            expanded_url: media.expanded_url.clone(),
+            source_status_id: media.source_status_id,
+        });
+    }
+
+    fn push_url(&mut self, tweet_id: u64, url: &Url) {
+        if !self.seen_urls.insert((tweet_id, url.url.clone())) {
+            return;
+        }
+
+        self.records.urls.push(UrlRecord {
+            tweet_id,
+            url: url.url.clone(),
+            expanded_url: url.expanded_url.clone(),
+            display_url: url.display_url.clone(),
+            unwound_url: url.unwound.as_ref().map(|unwound| unwound.url.clone()),
+        });
+    }
+}
+
+/// Normalizes a single tweet and everything embedded in it
+pub fn normalize(tweet: &Tweet) -> Records {
+    let mut normalizer = Normalizer::new();
+    normalizer.push(tweet);
+    normalizer.finish()
+}
+
+/// Gathers the distinct media of a tweet itself, preferring the extended
+/// versions since they contain every item rather than just the first.
+pub(crate) fn tweet_media(tweet: &Tweet) -> Vec<&Media> {
+    let mut media: Vec<&Media> = Vec::new();
+
+    if let Some(ext) = &tweet.extended_tweet {
+        if let Some(items) = &ext.entities.media {
+            media.extend(items);
+        }
+    }
+
+    if let Some(ent) = &tweet.extended_entities {
+        media.extend(&ent.media);
+    }
+
+    if let Some(items) = tweet.entities.as_ref().and_then(|ent| ent.media.as_ref()) {
+        media.extend(items);
+    }
+
+    let mut seen = HashSet::new();
+    media.retain(|item| seen.insert(item.id));
+    media
+}
+
+/// Gathers the links of a tweet itself, including the ones that only
+/// appear in the untruncated text.
+pub(crate) fn tweet_urls(tweet: &Tweet) -> Vec<&Url> {
+    let mut urls = Vec::new();
+
+    if let Some(ext) = &tweet.extended_tweet {
+        urls.extend(&ext.entities.urls);
+    }
+
+    if let Some(ent) = &tweet.entities {
+        urls.extend(&ent.urls);
+    }
+
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Entity, ExtendedEntity, ExtendedTweet};
+    use serde_json::json;
+
+    fn user(id: u64) -> User {
+        User {
+            id,
+            screen_name: format!("user{}", id),
+            ..Default::default()
+        }
+    }
+
+    fn tweet(id: u64, user_id: u64) -> Tweet {
+        Tweet {
+            id,
+            text: format!("tweet {}", id),
+            user: user(user_id),
+            ..Default::default()
+        }
+    }
+
+    fn media(id: u64) -> Media {
+        let size = json!({ "w": 1, "h": 1, "resize": "fit" });
+
+        serde_json::from_value(json!({
+            "display_url": "pic.twitter.com/media",
+            "expanded_url": "https://twitter.com/user/status/1/photo/1",
+            "id": id,
+            "id_str": id.to_string(),
+            "indices": [0, 23],
+            "media_url": format!("http://pbs.twimg.com/media/{}.jpg", id),
+            "media_url_https": format!("https://pbs.twimg.com/media/{}.jpg", id),
+            "sizes": { "thumb": size, "large": size, "medium": size, "small": size },
+            "type": "photo",
+            "url": "https://t.co/media",
+        }))
+        .unwrap()
+    }
+
+    fn url(short: &str) -> Url {
+        Url {
+            url: short.to_string(),
+            expanded_url: format!("https://example.com/{}", short),
+            display_url: "example.com".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn uses_extended_text_and_entities() {
+        let tweet = Tweet {
+            truncated: true,
+            entities: Some(Entity {
+                urls: vec![url("a")],
+                ..Default::default()
+            }),
+            extended_tweet: Some(ExtendedTweet {
+                full_text: "the whole text".to_string(),
+                display_text_range: (0, 14),
+                entities: Entity {
+                    urls: vec![url("a"), url("b")],
+                    media: Some(vec![media(7)]),
+                    ..Default::default()
+                },
+            }),
+            extended_entities: Some(ExtendedEntity { media: vec![media(7), media(8)] }),
+            ..tweet(1, 10)
+        };
+
+        let records = normalize(&tweet);
+
+        assert_eq!(records.tweets[0].full_text, "the whole text");
+        assert_eq!(records.urls.iter().map(|u| u.url.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(records.media.iter().map(|m| m.id).collect::<Vec<_>>(), [7, 8]);
+        assert!(records.media.iter().all(|m| m.tweet_id == 1));
+    }
+
+    #[test]
+    fn unwraps_retweets_and_quotes() {
+        let quoted = Tweet {
+            entities: Some(Entity {
+                urls: vec![url("q")],
+                ..Default::default()
+            }),
+            ..tweet(1, 10)
+        };
+        let quote = Tweet {
+            quoted_status_id: Some(1),
+            quoted_status: Some(Box::new(quoted)),
+            ..tweet(2, 20)
+        };
+        let retweet = Tweet {
+            retweeted_status: Some(Box::new(quote)),
+            ..tweet(3, 10)
+        };
+
+        let records = normalize(&retweet);
+
+        assert_eq!(records.tweets.iter().map(|t| t.id).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(records.tweets[1].quoted_status_id, Some(1));
+        assert_eq!(records.tweets[2].retweeted_status_id, Some(2));
+        assert_eq!(records.users.iter().map(|u| u.id).collect::<Vec<_>>(), [10, 20]);
+
+        //  Entities belong to the original tweet only
+        assert_eq!(records.urls.len(), 1);
+        assert_eq!(records.urls[0].tweet_id, 1);
+    }
+
+    #[test]
+    fn deduplicates_across_tweets() {
+        let mut normalizer = Normalizer::new();
+        normalizer.push(&Tweet {
+            retweeted_status: Some(Box::new(tweet(1, 10))),
+            ..tweet(2, 20)
+        });
+        normalizer.push(&tweet(1, 10));
+        normalizer.push(&tweet(4, 20));
+
+        let records = normalizer.finish();
+        assert_eq!(records.tweets.iter().map(|t| t.id).collect::<Vec<_>>(), [1, 2, 4]);
+        assert_eq!(records.users.len(), 2);
+    }
+}
@@ -0,0 +1,528 @@
+//! Iterators over paginated API responses.
+//!
+//! Newer endpoints link pages with a token, which `TokenPages` follows.
+//! Search and timelines in v1.1 are paged backwards through `max_id`,
+//! which `MaxIdPages` takes care of. Collections like followers are
+//! paged with cursors, which `CursorPages` follows.
+//!
+//! The crate does not make requests itself. Pages are requested
+//! through a fetcher, which can be implemented over any HTTP client,
+//! or as a closure returning canned responses in tests.
+
+use std::marker::PhantomData;
+
+use crate::model::v2;
+use crate::model::{Cursored, PremiumCounts, PremiumSearch, SearchResults, Tweet};
+
+/// A page that points to the next one with a token
+pub trait NextToken {
+    /// Token to request the next page with, or `None` on the last page
+    fn next_token(&self) -> Option<&str>;
+}
+
+impl NextToken for PremiumSearch {
+    fn next_token(&self) -> Option<&str> {
+        self.next.as_deref()
+    }
+}
+
+impl NextToken for PremiumCounts {
+    fn next_token(&self) -> Option<&str> {
+        self.next.as_deref()
+    }
+}
+
+impl<T> NextToken for v2::Response<T> {
+    fn next_token(&self) -> Option<&str> {
+        self.meta.as_ref().and_then(|meta| meta.next_token.as_deref())
+    }
+}
+
+/// Requests pages that are linked by a token
+pub trait TokenFetcher<P> {
+    /// Error returned when a page could not be requested
+    type Error;
+
+    /// Requests the page for `token`, or the first page when it is `None`
+    fn fetch(&mut self, token: Option<&str>) -> Result<P, Self::Error>;
+}
+
+impl<P, E, F> TokenFetcher<P> for F
+    where F: FnMut(Option<&str>) -> Result<P, E>
+{
+    type Error = E;
+
+    fn fetch(&mut self, token: Option<&str>) -> Result<P, E> {
+        self(token)
+    }
+}
+
+/// Iterates over pages by following their next token.
+/// Stops after the last page or the first error.
+pub struct TokenPages<F, P> {
+    fetcher: F,
+    token: Option<String>,
+    done: bool,
+    page: PhantomData<P>,
+}
+
+impl<F, P> TokenPages<F, P>
+    where F: TokenFetcher<P>,
+          P: NextToken
+{
+    /// Starts at the first page
+    pub fn new(fetcher: F) -> Self {
+        Self::starting_at(fetcher, None)
+    }
+
+    /// Starts at the page for `token`, for example to resume an
+    /// interrupted search
+    pub fn starting_at(fetcher: F, token: Option<String>) -> Self {
+        TokenPages {
+            fetcher,
+            token,
+            done: false,
+            page: PhantomData,
+        }
+    }
+
+    /// Token of the page that will be requested next. Save this to
+    /// resume later with `starting_at`.
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Returns the fetcher
+    pub fn into_inner(self) -> F {
+        self.fetcher
+    }
+}
+
+impl<F, P> Iterator for TokenPages<F, P>
+    where F: TokenFetcher<P>,
+          P: NextToken
+{
+    type Item = Result<P, F::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.fetcher.fetch(self.token.as_deref()) {
+            Ok(page) => {
+                self.token = page.next_token().map(String::from);
+                self.done = self.token.is_none();
+                Some(Ok(page))
+            }
+            Err(why) => {
+                self.done = true;
+                Some(Err(why))
+            }
+        }
+    }
+}
+
+/// A page of tweets ordered newest first, as returned by
+/// `search/tweets` and the v1.1 timelines
+pub trait TweetPage {
+    /// The tweets on this page
+    fn tweets(&self) -> &[Tweet];
+
+    /// The tweets on this page, to remove ones that were already seen
+    fn tweets_mut(&mut self) -> &mut Vec<Tweet>;
+}
+
+impl TweetPage for SearchResults {
+    fn tweets(&self) -> &[Tweet] {
+        &self.statuses
+    }
+
+    fn tweets_mut(&mut self) -> &mut Vec<Tweet> {
+        &mut self.statuses
+    }
+}
+
+impl TweetPage for Vec<Tweet> {
+    fn tweets(&self) -> &[Tweet] {
+        self
+    }
+
+    fn tweets_mut(&mut self) -> &mut Vec<Tweet> {
+        self
+    }
+}
+
+/// Requests pages of tweets with ids in a range
+pub trait MaxIdFetcher<P> {
+    /// Error returned when a page could not be requested
+    type Error;
+
+    /// Requests the page of tweets with ids up to and including
+    /// `max_id` and greater than `since_id`. Either is unbounded when `None`.
+    fn fetch(&mut self, max_id: Option<u64>, since_id: Option<u64>) -> Result<P, Self::Error>;
+}
+
+impl<P, E, F> MaxIdFetcher<P> for F
+    where F: FnMut(Option<u64>, Option<u64>) -> Result<P, E>
+{
+    type Error = E;
+
+    fn fetch(&mut self, max_id: Option<u64>, since_id: Option<u64>) -> Result<P, E> {
+        self(max_id, since_id)
+    }
+}
+
+/// Iterates backwards over pages of tweets by lowering `max_id`.
+///
+/// `max_id` is inclusive, so the next page is requested with one less
+/// than the smallest id seen. Iteration stops after an empty page, once
+/// `since_id` is reached, or after the first error. Tweets at or below
+/// `since_id` are removed from the last page, and tweets above `max_id`
+/// from endpoints that ignore it.
+pub struct MaxIdPages<F, P> {
+    fetcher: F,
+    max_id: Option<u64>,
+    since_id: Option<u64>,
+    done: bool,
+    page: PhantomData<P>,
+}
+
+impl<F, P> MaxIdPages<F, P>
+    where F: MaxIdFetcher<P>,
+          P: TweetPage
+{
+    /// Starts at the newest tweet and goes back until `since_id`, or
+    /// as far as the endpoint allows when it is `None`
+    pub fn new(fetcher: F, since_id: Option<u64>) -> Self {
+        Self::starting_at(fetcher, None, since_id)
+    }
+
+    /// Starts at `max_id`, for example to resume an interrupted backfill
+    pub fn starting_at(fetcher: F, max_id: Option<u64>, since_id: Option<u64>) -> Self {
+        MaxIdPages {
+            fetcher,
+            max_id,
+            since_id,
+            done: false,
+            page: PhantomData,
+        }
+    }
+
+    /// The `max_id` that will be requested next. Save this to resume
+    /// later with `starting_at`.
+    pub fn max_id(&self) -> Option<u64> {
+        self.max_id
+    }
+
+    /// Returns the fetcher
+    pub fn into_inner(self) -> F {
+        self.fetcher
+    }
+}
+
+impl<F, P> Iterator for MaxIdPages<F, P>
+    where F: MaxIdFetcher<P>,
+          P: TweetPage
+{
+    type Item = Result<P, F::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut page = match self.fetcher.fetch(self.max_id, self.since_id) {
+            Ok(page) => page,
+            Err(why) => {
+                self.done = true;
+                return Some(Err(why));
+            }
+        };
+
+        //  Endpoints that ignore max_id would repeat tweets that were already returned
+        if let Some(max_id) = self.max_id {
+            page.tweets_mut().retain(|tweet| tweet.id <= max_id);
+        }
+
+        let oldest = page.tweets().iter().map(|tweet| tweet.id).min();
+
+        if let Some(since_id) = self.since_id {
+            page.tweets_mut().retain(|tweet| tweet.id > since_id);
+        }
+
+        match oldest {
+            Some(oldest) if oldest > 0 => {
+                self.max_id = Some(oldest - 1);
+                self.done = self.since_id.is_some_and(|since_id| oldest - 1 <= since_id);
+            }
+            _ => self.done = true,
+        }
+
+        if self.done && page.tweets().is_empty() {
+            return None;
+        }
+
+        Some(Ok(page))
+    }
+}
+
+/// The cursor that requests the first page of a collection
+pub const FIRST_CURSOR: i64 = -1;
+
+/// A page that points to the next one with a cursor
+pub trait NextCursor {
+    /// Cursor to request the next page with, or `0` on the last page
+    fn next_cursor(&self) -> i64;
+}
+
+impl<T> NextCursor for Cursored<T> {
+    fn next_cursor(&self) -> i64 {
+        self.next_cursor
+    }
+}
+
+/// Requests pages of a collection by cursor
+pub trait CursorFetcher<P> {
+    /// Error returned when a page could not be requested
+    type Error;
+
+    /// Requests the page for `cursor`. The first page is `FIRST_CURSOR`.
+    fn fetch(&mut self, cursor: i64) -> Result<P, Self::Error>;
+}
+
+impl<P, E, F> CursorFetcher<P> for F
+    where F: FnMut(i64) -> Result<P, E>
+{
+    type Error = E;
+
+    fn fetch(&mut self, cursor: i64) -> Result<P, E> {
+        self(cursor)
+    }
+}
+
+/// Iterates over the pages of a collection by following cursors.
+/// Stops after the last page or the first error.
+pub struct CursorPages<F, P> {
+    fetcher: F,
+    cursor: i64,
+    done: bool,
+    page: PhantomData<P>,
+}
+
+impl<F, P> CursorPages<F, P>
+    where F: CursorFetcher<P>,
+          P: NextCursor
+{
+    /// Starts at the first page
+    pub fn new(fetcher: F) -> Self {
+        Self::starting_at(fetcher, FIRST_CURSOR)
+    }
+
+    /// Starts at the page for `cursor`, for example to resume an
+    /// interrupted crawl
+    pub fn starting_at(fetcher: F, cursor: i64) -> Self {
+        CursorPages {
+            fetcher,
+            cursor,
+            done: cursor == 0,
+            page: PhantomData,
+        }
+    }
+
+    /// Cursor of the page that will be requested next. Save this to
+    /// resume later with `starting_at`.
+    pub fn cursor(&self) -> i64 {
+        self.cursor
+    }
+
+    /// Returns the fetcher
+    pub fn into_inner(self) -> F {
+        self.fetcher
+    }
+}
+
+impl<F, P> Iterator for CursorPages<F, P>
+    where F: CursorFetcher<P>,
+          P: NextCursor
+{
+    type Item = Result<P, F::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.fetcher.fetch(self.cursor) {
+            Ok(page) => {
+                //  A cursor pointing back at itself would never end
+                let next = page.next_cursor();
+                self.done = next == 0 || next == self.cursor;
+                self.cursor = next;
+                Some(Ok(page))
+            }
+            Err(why) => {
+                self.done = true;
+                Some(Err(why))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Page = v2::Response<Vec<v2::Tweet>>;
+
+    fn page(json: &str) -> Page {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn token_pages_follow_next_token() {
+        let mut requested = Vec::new();
+        let fetcher = |token: Option<&str>| -> Result<Page, ()> {
+            requested.push(token.map(String::from));
+
+            Ok(page(match token {
+                None => r#"{"data":[{"id":"3","text":"c"}],"meta":{"result_count":1,"next_token":"b"}}"#,
+                Some("b") => r#"{"data":[{"id":"2","text":"b"}],"meta":{"result_count":1,"next_token":"a"}}"#,
+                //  The last page has no next_token
+                _ => r#"{"data":[{"id":"1","text":"a"}],"meta":{"result_count":1}}"#,
+            }))
+        };
+
+        let ids = TokenPages::new(fetcher)
+            .map(|page| page.unwrap().data.unwrap()[0].id)
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, vec![3, 2, 1]);
+        assert_eq!(requested, vec![None, Some("b".to_string()), Some("a".to_string())]);
+    }
+
+    #[test]
+    fn token_pages_resume_and_stop_after_errors() {
+        let mut calls = 0;
+        let fetcher = |token: Option<&str>| -> Result<Page, String> {
+            calls += 1;
+            assert_eq!(token, Some("b"));
+            Err("rate limited".to_string())
+        };
+
+        let mut pages = TokenPages::starting_at(fetcher, Some("b".to_string()));
+        assert_eq!(pages.token(), Some("b"));
+        assert_eq!(pages.next().unwrap().unwrap_err(), "rate limited");
+        assert!(pages.next().is_none());
+        assert_eq!(pages.token(), Some("b"));
+
+        drop(pages);
+        assert_eq!(calls, 1);
+    }
+
+    /// Serves tweets with the given ids newest first, `count` at a
+    /// time, honouring `max_id` and `since_id` like the API does
+    fn timeline<'a>(
+        ids: &[u64],
+        count: usize,
+        requested: &'a mut Vec<Option<u64>>,
+    ) -> impl FnMut(Option<u64>, Option<u64>) -> Result<Vec<Tweet>, ()> + 'a {
+        let mut ids = ids.to_vec();
+        ids.sort_unstable_by(|a, b| b.cmp(a));
+
+        move |max_id, since_id| {
+            requested.push(max_id);
+
+            Ok(ids.iter()
+                  .filter(|id| max_id.map_or(true, |max_id| **id <= max_id))
+                  .filter(|id| since_id.map_or(true, |since_id| **id > since_id))
+                  .take(count)
+                  .map(|id| Tweet { id: *id, ..Default::default() })
+                  .collect())
+        }
+    }
+
+    #[test]
+    fn max_id_pages_go_back_without_duplicates() {
+        let mut requested = Vec::new();
+        let pages = MaxIdPages::new(timeline(&[10, 20, 30, 40, 50], 2, &mut requested), None)
+            .map(|page| page.unwrap().iter().map(|tweet| tweet.id).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        assert_eq!(pages, vec![vec![50, 40], vec![30, 20], vec![10]]);
+        //  Each request starts below the oldest tweet seen, and the empty page ends it
+        assert_eq!(requested, vec![None, Some(39), Some(19), Some(9)]);
+    }
+
+    #[test]
+    fn max_id_pages_stop_at_since_id() {
+        let mut requested = Vec::new();
+        let mut pages = MaxIdPages::starting_at(timeline(&[10, 20, 30, 40, 50], 2, &mut requested), Some(45), Some(20));
+
+        assert_eq!(pages.next().unwrap().unwrap().iter().map(|t| t.id).collect::<Vec<_>>(), vec![40, 30]);
+        assert_eq!(pages.max_id(), Some(29));
+        assert!(pages.next().is_none());
+
+        drop(pages);
+        assert_eq!(requested, vec![Some(45), Some(29)]);
+    }
+
+    #[test]
+    fn max_id_pages_stop_when_max_id_is_ignored() {
+        let mut calls = 0;
+        let fetcher = |_: Option<u64>, _: Option<u64>| -> Result<Vec<Tweet>, ()> {
+            calls += 1;
+            Ok(vec![Tweet { id: 5, ..Default::default() }])
+        };
+
+        let pages = MaxIdPages::new(fetcher, None).map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(pages.iter().flatten().map(|tweet| tweet.id).collect::<Vec<_>>(), vec![5]);
+        assert_eq!(calls, 2);
+    }
+
+    fn ids_page(cursor: i64) -> Result<crate::model::FollowerIds, ()> {
+        let (ids, next) = match cursor {
+            FIRST_CURSOR => ("[1,2]", 1500),
+            1500 => (r#"["3","4"]"#, 1400),
+            _ => ("[5]", 0),
+        };
+
+        Ok(format!(
+            r#"{{"ids":{},"next_cursor":{},"next_cursor_str":"{}","previous_cursor":0,"previous_cursor_str":"0"}}"#,
+            ids, next, next
+        ).parse().unwrap())
+    }
+
+    #[test]
+    fn cursor_pages_end_at_zero() {
+        let mut requested = Vec::new();
+        let fetcher = |cursor| {
+            requested.push(cursor);
+            ids_page(cursor)
+        };
+
+        let ids = CursorPages::new(fetcher)
+            .flat_map(|page| page.unwrap().items.ids)
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+        assert_eq!(requested, vec![FIRST_CURSOR, 1500, 1400]);
+    }
+
+    #[test]
+    fn cursor_pages_resume_from_a_saved_cursor() {
+        let mut pages = CursorPages::new(ids_page);
+        pages.next().unwrap().unwrap();
+
+        let saved = pages.cursor();
+        assert_eq!(saved, 1500);
+
+        let ids = CursorPages::starting_at(ids_page, saved)
+            .flat_map(|page| page.unwrap().items.ids)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![3, 4, 5]);
+
+        //  A finished crawl saves `0`, which requests nothing
+        assert_eq!(CursorPages::starting_at(ids_page, 0).count(), 0);
+    }
+}
@@ -0,0 +1,268 @@
+//! Rate limit headers and a local budget of remaining calls.
+//!
+//! Every response carries `x-rate-limit-*` headers describing the
+//! 15 minute window of its endpoint. Some v2 endpoints add 24 hour
+//! limits per user (`x-user-limit-24hour-*`) and per app
+//! (`x-app-limit-24hour-*`). `RateLimitTracker` remembers the latest
+//! headers per endpoint and tells how long to wait before the next call.
+
+use chrono::{DateTime, TimeZone, Utc};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// One rate limit window
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    /// How many calls are allowed in the window
+    pub limit: u32,
+    /// How many calls are left in the window
+    pub remaining: u32,
+    /// When the window resets
+    pub reset: DateTime<Utc>,
+}
+
+/// Every rate limit a response reported
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RateLimits {
+    /// The 15 minute window of the endpoint, from `x-rate-limit-*`
+    pub window: Option<RateLimit>,
+    /// The 24 hour limit of the user, from `x-user-limit-24hour-*`
+    pub user_24hour: Option<RateLimit>,
+    /// The 24 hour limit of the app, from `x-app-limit-24hour-*`
+    pub app_24hour: Option<RateLimit>,
+}
+
+impl RateLimit {
+    /// Reads the headers starting with `prefix`, like `x-rate-limit`.
+    /// Header names are matched case insensitively.
+    pub fn from_headers<'a, I>(prefix: &str, headers: I) -> Option<Self>
+        where I: IntoIterator<Item = (&'a str, &'a str)>
+    {
+        let mut limit = None;
+        let mut remaining = None;
+        let mut reset = None;
+
+        for (name, value) in headers {
+            let name = name.to_ascii_lowercase();
+            let suffix = match name.strip_prefix(prefix).and_then(|rest| rest.strip_prefix('-')) {
+                Some(suffix) => suffix,
+                None => continue,
+            };
+
+            let value = value.trim();
+
+            match suffix {
+                "limit" => limit = value.parse().ok(),
+                "remaining" => remaining = value.parse().ok(),
+                "reset" => reset = value.parse().ok().and_then(|secs| Utc.timestamp_opt(secs, 0).single()),
+                _ => {}
+            }
+        }
+
+        Some(RateLimit {
+            limit: limit?,
+            remaining: remaining?,
+            reset: reset?,
+        })
+    }
+
+    /// Whether no calls are left before `reset`
+    pub fn is_exhausted(&self, now: DateTime<Utc>) -> bool {
+        self.remaining == 0 && now < self.reset
+    }
+
+    /// How long to wait at `now` before the next call is allowed
+    pub fn wait_time(&self, now: DateTime<Utc>) -> Duration {
+        if self.is_exhausted(now) {
+            (self.reset - now).to_std().unwrap_or_default()
+        } else {
+            Duration::from_secs(0)
+        }
+    }
+}
+
+impl RateLimits {
+    /// Reads every known rate limit header
+    pub fn from_headers<'a, I>(headers: I) -> Self
+        where I: IntoIterator<Item = (&'a str, &'a str)>
+    {
+        let headers = headers.into_iter().collect::<Vec<_>>();
+
+        RateLimits {
+            window: RateLimit::from_headers("x-rate-limit", headers.iter().cloned()),
+            user_24hour: RateLimit::from_headers("x-user-limit-24hour", headers.iter().cloned()),
+            app_24hour: RateLimit::from_headers("x-app-limit-24hour", headers.iter().cloned()),
+        }
+    }
+
+    /// Whether the response carried no rate limit headers
+    pub fn is_empty(&self) -> bool {
+        self.window.is_none() && self.user_24hour.is_none() && self.app_24hour.is_none()
+    }
+
+    /// How long to wait at `now` before the next call is allowed by every limit
+    pub fn wait_time(&self, now: DateTime<Utc>) -> Duration {
+        self.limits()
+            .map(|limit| limit.wait_time(now))
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn limits(&self) -> impl Iterator<Item = &RateLimit> {
+        self.window.iter().chain(self.user_24hour.iter()).chain(self.app_24hour.iter())
+    }
+
+    fn limits_mut(&mut self) -> impl Iterator<Item = &mut RateLimit> {
+        self.window.iter_mut().chain(self.user_24hour.iter_mut()).chain(self.app_24hour.iter_mut())
+    }
+}
+
+/// Keeps the latest rate limits of each endpoint
+#[derive(Debug, Default)]
+pub struct RateLimitTracker {
+    endpoints: HashMap<String, RateLimits>,
+}
+
+impl RateLimitTracker {
+    /// Creates a tracker that knows no limits yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the limits reported by a response from `endpoint`
+    pub fn update(&mut self, endpoint: &str, limits: RateLimits) {
+        if !limits.is_empty() {
+            self.endpoints.insert(endpoint.to_string(), limits);
+        }
+    }
+
+    /// Records the rate limit headers of a response from `endpoint`
+    pub fn update_from_headers<'a, I>(&mut self, endpoint: &str, headers: I)
+        where I: IntoIterator<Item = (&'a str, &'a str)>
+    {
+        self.update(endpoint, RateLimits::from_headers(headers));
+    }
+
+    /// Counts a call to `endpoint` against the known limits, for calls
+    /// whose response headers are not available
+    pub fn record_call(&mut self, endpoint: &str) {
+        if let Some(limits) = self.endpoints.get_mut(endpoint) {
+            for limit in limits.limits_mut() {
+                limit.remaining = limit.remaining.saturating_sub(1);
+            }
+        }
+    }
+
+    /// The latest limits of `endpoint`
+    pub fn get(&self, endpoint: &str) -> Option<&RateLimits> {
+        self.endpoints.get(endpoint)
+    }
+
+    /// How many calls to `endpoint` are left in the current window, if known
+    pub fn remaining(&self, endpoint: &str) -> Option<u32> {
+        self.get(endpoint).and_then(|limits| limits.limits().map(|limit| limit.remaining).min())
+    }
+
+    /// How long to wait at `now` before calling `endpoint`. Unknown
+    /// endpoints do not have to wait.
+    pub fn wait_time(&self, endpoint: &str, now: DateTime<Utc>) -> Duration {
+        self.get(endpoint)
+            .map(|limits| limits.wait_time(now))
+            .unwrap_or_default()
+    }
+
+    /// How long to wait before calling `endpoint`
+    pub fn wait_time_now(&self, endpoint: &str) -> Duration {
+        self.wait_time(endpoint, Utc::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESET: i64 = 1_600_000_900;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(secs, 0).unwrap()
+    }
+
+    fn headers(remaining: &str) -> Vec<(&'static str, String)> {
+        vec![
+            ("X-Rate-Limit-Limit", "900".to_string()),
+            ("x-rate-limit-remaining", remaining.to_string()),
+            ("x-rate-limit-reset", format!(" {} ", RESET)),
+            ("x-user-limit-24hour-limit", "100".to_string()),
+            ("x-user-limit-24hour-remaining", "99".to_string()),
+            ("x-user-limit-24hour-reset", (RESET + 3600).to_string()),
+            ("content-type", "application/json".to_string()),
+        ]
+    }
+
+    fn pairs<'a>(headers: &'a [(&'static str, String)]) -> impl Iterator<Item = (&'a str, &'a str)> {
+        headers.iter().map(|(name, value)| (*name, value.as_str()))
+    }
+
+    #[test]
+    fn headers_are_parsed() {
+        let headers = headers("899");
+        let limits = RateLimits::from_headers(pairs(&headers));
+
+        assert_eq!(limits.window, Some(RateLimit { limit: 900, remaining: 899, reset: at(RESET) }));
+        assert_eq!(limits.user_24hour, Some(RateLimit { limit: 100, remaining: 99, reset: at(RESET + 3600) }));
+        assert_eq!(limits.app_24hour, None);
+        assert!(!limits.is_empty());
+    }
+
+    #[test]
+    fn incomplete_headers_are_ignored() {
+        let limit = RateLimit::from_headers("x-rate-limit", vec![
+            ("x-rate-limit-limit", "900"),
+            ("x-rate-limit-remaining", "soon"),
+            ("x-rate-limit-reset", "1600000900"),
+        ]);
+        assert_eq!(limit, None);
+
+        assert!(RateLimits::from_headers(vec![("content-type", "text/html")]).is_empty());
+    }
+
+    #[test]
+    fn exhausted_limits_wait_until_reset() {
+        let headers = headers("0");
+        let limits = RateLimits::from_headers(pairs(&headers));
+        let window = limits.window.unwrap();
+
+        assert!(window.is_exhausted(at(RESET - 60)));
+        assert_eq!(limits.wait_time(at(RESET - 60)), Duration::from_secs(60));
+
+        //  Once the window has reset the stale `remaining` no longer applies
+        assert!(!window.is_exhausted(at(RESET)));
+        assert_eq!(limits.wait_time(at(RESET + 1)), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn endpoints_are_tracked_separately() {
+        let mut tracker = RateLimitTracker::new();
+        let exhausted = headers("0");
+        let available = headers("2");
+
+        tracker.update_from_headers("statuses/user_timeline", pairs(&exhausted));
+        tracker.update_from_headers("search/tweets", pairs(&available));
+        //  Responses without headers keep what was known
+        tracker.update_from_headers("search/tweets", vec![]);
+
+        let now = at(RESET - 30);
+        assert_eq!(tracker.wait_time("statuses/user_timeline", now), Duration::from_secs(30));
+        assert_eq!(tracker.wait_time("search/tweets", now), Duration::from_secs(0));
+        assert_eq!(tracker.wait_time("friends/ids", now), Duration::from_secs(0));
+        assert_eq!(tracker.remaining("friends/ids"), None);
+
+        tracker.record_call("search/tweets");
+        assert_eq!(tracker.remaining("search/tweets"), Some(1));
+        tracker.record_call("search/tweets");
+        tracker.record_call("search/tweets");
+        assert_eq!(tracker.remaining("search/tweets"), Some(0));
+        assert_eq!(tracker.wait_time("search/tweets", now), Duration::from_secs(30));
+        assert_eq!(tracker.get("search/tweets").unwrap().user_24hour.unwrap().remaining, 96);
+    }
+}
@@ -0,0 +1,389 @@
+//! Anonymization of tweets for sharing datasets.
+//!
+//! A `Redactor` replaces user ids and screen names with pseudonyms
+//! derived from a secret salt, so the same user gets the same pseudonym
+//! across an entire corpus without the original being recoverable. It
+//! can also strip location data and personal profile fields. Redacted
+//! tweets keep their original shape and serialize to JSON that the
+//! deserializers in this crate accept.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::model::{Entity, Tweet, User};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Rewrites tweets and users to remove personal data
+#[derive(Clone)]
+pub struct Redactor {
+    salt: Vec<u8>,
+    ids: bool,
+    screen_names: bool,
+    geo: bool,
+    profiles: bool,
+}
+
+impl Redactor {
+    /// Creates a redactor with every redaction enabled. The salt
+    /// should be kept secret and reused for every part of a corpus.
+    pub fn new<S: AsRef<[u8]>>(salt: S) -> Self {
+        Redactor {
+            salt: salt.as_ref().to_vec(),
+            ids: true,
+            screen_names: true,
+            geo: true,
+            profiles: true,
+        }
+    }
+
+    /// Whether user ids are replaced with pseudonyms
+    pub fn ids(mut self, enabled: bool) -> Self {
+        self.ids = enabled;
+        self
+    }
+
+    /// Whether screen names and display names are replaced with
+    /// pseudonyms, including mentions in entities and tweet text
+    pub fn screen_names(mut self, enabled: bool) -> Self {
+        self.screen_names = enabled;
+        self
+    }
+
+    /// Whether `coordinates`, `place` and `geo` are removed
+    pub fn geo(mut self, enabled: bool) -> Self {
+        self.geo = enabled;
+        self
+    }
+
+    /// Whether the description, location, derived locations, url and
+    /// images of users are blanked
+    pub fn profiles(mut self, enabled: bool) -> Self {
+        self.profiles = enabled;
+        self
+    }
+
+    /// Returns the pseudonym of a user id. It always fits in 63 bits
+    /// so that it survives a round trip through signed integer storage.
+    pub fn pseudonymize_id(&self, id: u64) -> u64 {
+        let digest = self.digest("id", &id.to_string());
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&digest[..8]);
+        u64::from_be_bytes(bytes) & 0x7fff_ffff_ffff_ffff
+    }
+
+    /// Returns the pseudonym of a screen name. Screen names are not
+    /// case sensitive, so neither is the pseudonym. The result is a
+    /// valid 15 character screen name.
+    pub fn pseudonymize_screen_name(&self, screen_name: &str) -> String {
+        let digest = self.digest("screen_name", &screen_name.to_lowercase());
+        let hex = digest[..7].iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        format!("u{}", hex)
+    }
+
+    /// Redacts a tweet and every tweet embedded in it
+    #[allow(deprecated)]
+    pub fn redact_tweet(&self, tweet: &mut Tweet) {
+        self.redact_user(&mut tweet.user);
+
+        if self.ids {
+            if let Some(id) = tweet.in_reply_to_user_id {
+                let id = self.pseudonymize_id(id);
+                tweet.in_reply_to_user_id = Some(id);
+                tweet.in_reply_to_user_id_str = Some(id.to_string());
+            }
+        }
+
+        if self.screen_names {
+            if let Some(name) = &tweet.in_reply_to_screen_name {
+                tweet.in_reply_to_screen_name = Some(self.pseudonymize_screen_name(name));
+            }
+
+            //  The permalink contains the screen name of the quoted user
+            tweet.quoted_status_permalink = None;
+        }
+
+        if let Some(entities) = &mut tweet.entities {
+            let edits = self.redact_text(&mut tweet.text, entities, tweet.display_text_range.as_mut());
+
+            //  Extended entities share their indices with the base text
+            if let Some(ext) = &mut tweet.extended_entities {
+                for media in &mut ext.media {
+                    shift_indices(&edits, &mut media.indices);
+                }
+            }
+        }
+
+        if let Some(ext) = &mut tweet.extended_tweet {
+            self.redact_text(&mut ext.full_text, &mut ext.entities, Some(&mut ext.display_text_range));
+        }
+
+        if let Some(ext) = &mut tweet.extended_entities {
+            for media in &mut ext.media {
+                media.expanded_url = self.redact_status_link(&media.expanded_url);
+            }
+        }
+
+        if self.geo {
+            tweet.coordinates = None;
+            tweet.place = None;
+            tweet.geo = None;
+        }
+
+        if let Some(rt) = &mut tweet.retweeted_status {
+            self.redact_tweet(rt);
+        }
+
+        if let Some(quote) = &mut tweet.quoted_status {
+            self.redact_tweet(quote);
+        }
+    }
+
+    /// Redacts a user
+    pub fn redact_user(&self, user: &mut User) {
+        if self.ids {
+            user.id = self.pseudonymize_id(user.id);
+            user.id_str = user.id.to_string();
+        }
+
+        if self.screen_names {
+            user.screen_name = self.pseudonymize_screen_name(&user.screen_name);
+            user.name = user.screen_name.clone();
+        }
+
+        if self.profiles {
+            user.description = None;
+            user.location = None;
+            user.derived = None;
+            user.url = None;
+            user.profile_banner_url = None;
+            user.profile_image_url_https = String::new();
+        }
+    }
+
+    /// Parses a tweet, redacts it and serializes it again
+    pub fn redact_json(&self, json: &str) -> Result<String, serde_json::Error> {
+        let mut tweet: Tweet = serde_json::from_str(json)?;
+        self.redact_tweet(&mut tweet);
+        serde_json::to_string(&tweet)
+    }
+
+    fn digest(&self, kind: &str, value: &str) -> Vec<u8> {
+        let mut mac = HmacSha256::new_from_slice(&self.salt).expect("HMAC accepts keys of any size");
+        mac.update(kind.as_bytes());
+        mac.update(b":");
+        mac.update(value.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    /// Replaces the screen name in links like `https://twitter.com/name/status/1`
+    fn redact_status_link(&self, link: &str) -> String {
+        if !self.screen_names {
+            return link.to_string();
+        }
+
+        for prefix in &["https://twitter.com/", "http://twitter.com/", "https://x.com/"] {
+            if let Some(rest) = link.strip_prefix(prefix) {
+                if let Some((name, path)) = rest.split_once('/') {
+                    if path.starts_with("status") {
+                        return format!("{}{}/{}", prefix, self.pseudonymize_screen_name(name), path);
+                    }
+                }
+            }
+        }
+
+        link.to_string()
+    }
+
+    /// Redacts mentions inside a text and its entities. Since pseudonyms
+    /// can differ in length from the original screen name, the indices
+    /// of every entity after a mention are shifted to match the new text.
+    /// Returns the edits that were made to the text.
+    fn redact_text(&self, text: &mut String, entities: &mut Entity, range: Option<&mut (u32, u32)>) -> Vec<Edit> {
+        for mention in &mut entities.user_mentions {
+            if self.ids {
+                if let Some(id) = mention.id {
+                    let id = self.pseudonymize_id(id);
+                    mention.id = Some(id);
+                    mention.id_str = Some(id.to_string());
+                }
+            }
+        }
+
+        for url in &mut entities.urls {
+            url.expanded_url = self.redact_status_link(&url.expanded_url);
+        }
+
+        for media in entities.media.iter_mut().flatten() {
+            media.expanded_url = self.redact_status_link(&media.expanded_url);
+        }
+
+        if !self.screen_names {
+            return Vec::new();
+        }
+
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut edits = Vec::new();
+
+        for mention in &mut entities.user_mentions {
+            let pseudonym = self.pseudonymize_screen_name(&mention.screen_name);
+
+            if let [start, end] = mention.indices[..] {
+                let (start, end) = (start as usize, end as usize);
+
+                if start < end && end <= chars.len() && matches!(chars[start], '@' | '＠') {
+                    edits.push(Edit {
+                        start,
+                        end,
+                        replacement: format!("{}{}", chars[start], pseudonym),
+                    });
+                }
+            }
+
+            mention.screen_name = pseudonym.clone();
+            if mention.name.is_some() {
+                mention.name = Some(pseudonym);
+            }
+        }
+
+        //  Garbled indices can make mentions overlap, keep the first one
+        edits.sort_by_key(|edit| edit.start);
+        let mut end = 0;
+        edits.retain(|edit| {
+            let keep = edit.start >= end;
+            if keep {
+                end = edit.end;
+            }
+            keep
+        });
+
+        if edits.is_empty() {
+            return edits;
+        }
+
+        let mut rewritten = String::with_capacity(text.len());
+        let mut last = 0;
+
+        for edit in &edits {
+            rewritten.extend(&chars[last..edit.start]);
+            rewritten.push_str(&edit.replacement);
+            last = edit.end;
+        }
+
+        rewritten.extend(&chars[last..]);
+        *text = rewritten;
+
+        for hashtag in &mut entities.hashtags {
+            shift_indices(&edits, &mut hashtag.indices);
+        }
+
+        for url in &mut entities.urls {
+            shift_indices(&edits, &mut url.indices);
+        }
+
+        for symbol in &mut entities.symbols {
+            shift_indices(&edits, &mut symbol.indices);
+        }
+
+        for media in entities.media.iter_mut().flatten() {
+            shift_indices(&edits, &mut media.indices);
+        }
+
+        for mention in &mut entities.user_mentions {
+            shift_indices(&edits, &mut mention.indices);
+        }
+
+        if let Some(range) = range {
+            range.0 = shift(&edits, range.0);
+            range.1 = shift(&edits, range.1);
+        }
+
+        edits
+    }
+}
+
+/// A replacement of the characters between two offsets of a text
+struct Edit {
+    start: usize,
+    end: usize,
+    replacement: String,
+}
+
+/// Moves an offset of the original text to the same place in the edited text
+fn shift(edits: &[Edit], offset: u32) -> u32 {
+    let mut moved = offset as i64;
+
+    for edit in edits.iter().filter(|edit| edit.end <= offset as usize) {
+        moved += edit.replacement.chars().count() as i64 - (edit.end - edit.start) as i64;
+    }
+
+    moved.max(0) as u32
+}
+
+fn shift_indices(edits: &[Edit], indices: &mut [u32]) {
+    for index in indices {
+        *index = shift(edits, *index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Derived, UserMention};
+
+    fn mention(screen_name: &str, start: u32, end: u32) -> UserMention {
+        UserMention {
+            id: None,
+            id_str: None,
+            indices: vec![start, end],
+            name: None,
+            screen_name: screen_name.to_string(),
+        }
+    }
+
+    #[test]
+    fn redacts_derived_locations() {
+        let mut user = User {
+            location: Some("San Francisco".to_string()),
+            derived: Some(Derived::default()),
+            ..Default::default()
+        };
+
+        Redactor::new("salt").redact_user(&mut user);
+
+        assert!(user.location.is_none());
+        assert!(user.derived.is_none());
+    }
+
+    #[test]
+    fn redacts_mentions_in_text() {
+        let redactor = Redactor::new("salt");
+        let mut text = "hi @alice and @bob".to_string();
+        let mut entities = Entity {
+            user_mentions: vec![mention("alice", 3, 9), mention("bob", 14, 18)],
+            ..Default::default()
+        };
+
+        redactor.redact_text(&mut text, &mut entities, None);
+
+        let alice = redactor.pseudonymize_screen_name("alice");
+        let bob = redactor.pseudonymize_screen_name("bob");
+        assert_eq!(text, format!("hi @{} and @{}", alice, bob));
+        assert_eq!(entities.user_mentions[1].indices, vec![24, 40]);
+    }
+
+    #[test]
+    fn ignores_overlapping_mentions() {
+        let redactor = Redactor::new("salt");
+        let mut text = "@alice @bob hello".to_string();
+        let mut entities = Entity {
+            user_mentions: vec![mention("alice", 0, 11), mention("bob", 7, 11)],
+            ..Default::default()
+        };
+
+        redactor.redact_text(&mut text, &mut entities, None);
+
+        let alice = redactor.pseudonymize_screen_name("alice");
+        assert_eq!(text, format!("@{} hello", alice));
+    }
+}
@@ -0,0 +1,328 @@
+//! Replays recorded streams, for example for load tests.
+//!
+//! A capture is the raw body of a stream saved to disk. `Replayer`
+//! reads it with a `LineReader` and sends each message to a `Sink` at
+//! the pace it was originally sent, faster or slower, or as fast as the
+//! sink accepts it. The time of a message is taken from its
+//! `timestamp_ms` or `created_at`. Messages without either are sent
+//! right after the previous one.
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::Value;
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{Sender, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::stream::LineReader;
+use crate::util::datetime::FORMAT as TWITTER_DATE_FORMAT;
+
+/// How fast messages are replayed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Speed {
+    /// At the pace they were recorded
+    Original,
+    /// This many times faster than recorded, slower below 1
+    Multiplied(f64),
+    /// Without waiting between messages
+    Unlimited,
+}
+
+impl Speed {
+    /// When a message recorded `offset` after the first one is due,
+    /// counted from the start of the replay
+    fn due(self, offset: Duration) -> Option<Duration> {
+        match self {
+            Speed::Original => Some(offset),
+            Speed::Multiplied(factor) if factor > 0.0 => Some(offset.div_f64(factor)),
+            Speed::Multiplied(_) | Speed::Unlimited => None,
+        }
+    }
+}
+
+/// Receives replayed messages
+pub trait Sink {
+    /// Error returned when a message could not be delivered
+    type Error;
+
+    /// Delivers one message, without the line separator
+    fn send(&mut self, message: &str) -> Result<(), Self::Error>;
+
+    /// Called once the capture has been replayed
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Writes messages separated by `\r\n`, like a stream does
+#[derive(Debug)]
+pub struct WriteSink<W>(pub W);
+
+impl<W: Write> Sink for WriteSink<W> {
+    type Error = io::Error;
+
+    fn send(&mut self, message: &str) -> io::Result<()> {
+        write!(self.0, "{}\r\n", message)?;
+        self.0.flush()
+    }
+}
+
+impl Sink for Sender<String> {
+    type Error = String;
+
+    fn send(&mut self, message: &str) -> Result<(), String> {
+        Sender::send(self, message.to_string()).map_err(|why| why.0)
+    }
+}
+
+impl Sink for SyncSender<String> {
+    type Error = String;
+
+    fn send(&mut self, message: &str) -> Result<(), String> {
+        SyncSender::send(self, message.to_string()).map_err(|why| why.0)
+    }
+}
+
+/// Sends messages as the chunked body of an HTTP response, like the
+/// streaming endpoints do
+#[derive(Debug)]
+pub struct ChunkedSink<W: Write> {
+    out: W,
+    started: bool,
+}
+
+impl<W: Write> ChunkedSink<W> {
+    /// Writes the response to `out`. The status line and headers are
+    /// written before the first message.
+    pub fn new(out: W) -> Self {
+        ChunkedSink {
+            out,
+            started: false,
+        }
+    }
+
+    /// Returns the output
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            write!(self.out, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ChunkedSink<TcpStream> {
+    /// Waits for a client to connect to `listener` and send its request,
+    /// then streams the response to it
+    pub fn accept(listener: &TcpListener) -> io::Result<Self> {
+        let (connection, _) = listener.accept()?;
+        let mut reader = BufReader::new(connection.try_clone()?);
+
+        //  The request itself does not matter, only its end
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+        }
+
+        Ok(ChunkedSink::new(connection))
+    }
+}
+
+impl<W: Write> Sink for ChunkedSink<W> {
+    type Error = io::Error;
+
+    fn send(&mut self, message: &str) -> io::Result<()> {
+        self.start()?;
+        write!(self.out, "{:x}\r\n{}\r\n\r\n", message.len() + 2, message)?;
+        self.out.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.start()?;
+        write!(self.out, "0\r\n\r\n")?;
+        self.out.flush()
+    }
+}
+
+/// Errors that can occur while replaying
+#[derive(Debug)]
+pub enum Error<E> {
+    /// The capture could not be read
+    Io(io::Error),
+    /// The sink did not accept a message
+    Sink(E),
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(why) => write!(f, "io error: {}", why),
+            Error::Sink(why) => write!(f, "sink error: {}", why),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> error::Error for Error<E> {}
+
+/// What was replayed
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReplayStats {
+    /// How many messages were sent
+    pub messages: u64,
+    /// How many of them had no time
+    pub untimed: u64,
+    /// How many keep-alives of the capture were skipped
+    pub keep_alives: u64,
+    /// Time between the first and the last message of the capture
+    pub recorded: Duration,
+    /// How long the replay took
+    pub elapsed: Duration,
+}
+
+/// Replays a capture to a sink
+pub struct Replayer<R> {
+    reader: LineReader<R>,
+    speed: Speed,
+}
+
+impl<R: BufRead> Replayer<R> {
+    /// Replays the capture read from `input`
+    pub fn new(input: R, speed: Speed) -> Self {
+        Replayer {
+            reader: LineReader::new(input),
+            speed,
+        }
+    }
+
+    /// Sends every message of the capture to `sink`, waiting between
+    /// messages as the speed requires
+    pub fn run<S: Sink>(mut self, sink: &mut S) -> Result<ReplayStats, Error<S::Error>> {
+        let started = Instant::now();
+        let mut stats = ReplayStats::default();
+        let mut first = None;
+
+        for message in &mut self.reader {
+            let message = message.map_err(Error::Io)?;
+
+            match message_time(&message) {
+                Some(time) => {
+                    let first = *first.get_or_insert(time);
+                    let offset = (time - first).to_std().unwrap_or_default();
+                    stats.recorded = stats.recorded.max(offset);
+
+                    if let Some(due) = self.speed.due(offset) {
+                        let elapsed = started.elapsed();
+
+                        if due > elapsed {
+                            thread::sleep(due - elapsed);
+                        }
+                    }
+                }
+                None => stats.untimed += 1,
+            }
+
+            sink.send(&message).map_err(Error::Sink)?;
+            stats.messages += 1;
+        }
+
+        sink.finish().map_err(Error::Sink)?;
+        stats.keep_alives = self.reader.keep_alives();
+        stats.elapsed = started.elapsed();
+        Ok(stats)
+    }
+}
+
+/// When a message was sent, from its `timestamp_ms` or the one of the
+/// notice it wraps, or from the `created_at` of a v1.1 or v2 tweet
+pub fn message_time(message: &str) -> Option<DateTime<Utc>> {
+    let value: Value = serde_json::from_str(message).ok()?;
+
+    let timestamp = value.get("timestamp_ms")
+                         .or_else(|| value.as_object()?.values().find_map(|inner| inner.get("timestamp_ms")));
+
+    if let Some(ms) = timestamp.and_then(|ms| ms.as_str().and_then(|ms| ms.parse().ok()).or_else(|| ms.as_i64())) {
+        return Utc.timestamp_millis_opt(ms).single();
+    }
+
+    let created_at = value.get("created_at")
+                          .or_else(|| value.get("data").and_then(|data| data.get("created_at")))
+                          .and_then(Value::as_str)?;
+
+    DateTime::parse_from_str(created_at, TWITTER_DATE_FORMAT)
+        .or_else(|_| DateTime::parse_from_rfc3339(created_at))
+        .map(|time| time.with_timezone(&Utc))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(ms: i64) -> Option<DateTime<Utc>> {
+        Utc.timestamp_millis_opt(ms).single()
+    }
+
+    #[test]
+    fn time_is_read_from_timestamp_ms() {
+        assert_eq!(message_time(r#"{"id":1,"timestamp_ms":"1600000000123"}"#), millis(1_600_000_000_123));
+        assert_eq!(message_time(r#"{"limit":{"track":5,"timestamp_ms":1600000000123}}"#), millis(1_600_000_000_123));
+    }
+
+    #[test]
+    fn time_is_read_from_created_at() {
+        assert_eq!(message_time(r#"{"id":1,"created_at":"Sun Sep 13 12:26:40 +0000 2020"}"#), millis(1_600_000_000_000));
+        assert_eq!(message_time(r#"{"data":{"id":"1","created_at":"2020-09-13T12:26:40.000Z"}}"#),
+                   millis(1_600_000_000_000));
+        assert_eq!(message_time(r#"{"id":1}"#), None);
+        assert_eq!(message_time("not json"), None);
+    }
+
+    #[test]
+    fn speed_scales_offsets() {
+        let offset = Duration::from_secs(10);
+
+        assert_eq!(Speed::Original.due(offset), Some(offset));
+        assert_eq!(Speed::Multiplied(2.0).due(offset), Some(Duration::from_secs(5)));
+        assert_eq!(Speed::Multiplied(0.5).due(offset), Some(Duration::from_secs(20)));
+        assert_eq!(Speed::Multiplied(0.0).due(offset), None);
+        assert_eq!(Speed::Unlimited.due(offset), None);
+    }
+
+    #[test]
+    fn chunked_sink_frames_messages() {
+        let mut sink = ChunkedSink::new(Vec::new());
+        sink.send(r#"{"id":1}"#).unwrap();
+        sink.finish().unwrap();
+
+        let body = String::from_utf8(sink.into_inner()).unwrap();
+        let (head, chunks) = body.split_once("\r\n\r\n").unwrap();
+
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains("Transfer-Encoding: chunked"));
+        assert_eq!(chunks, "a\r\n{\"id\":1}\r\n\r\n0\r\n\r\n");
+    }
+
+    #[test]
+    fn replay_sends_every_message() {
+        let capture = "{\"id\":1,\"timestamp_ms\":\"1000\"}\r\n\r\n{\"id\":2}\r\n{\"id\":3,\"timestamp_ms\":\"4000\"}\r\n";
+        let mut sink = WriteSink(Vec::new());
+        let stats = Replayer::new(capture.as_bytes(), Speed::Unlimited).run(&mut sink).unwrap();
+
+        assert_eq!(String::from_utf8(sink.0).unwrap(),
+                   "{\"id\":1,\"timestamp_ms\":\"1000\"}\r\n{\"id\":2}\r\n{\"id\":3,\"timestamp_ms\":\"4000\"}\r\n");
+        assert_eq!(stats.messages, 3);
+        assert_eq!(stats.untimed, 1);
+        assert_eq!(stats.keep_alives, 1);
+        assert_eq!(stats.recorded, Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,923 @@
+//! Embedded SQLite storage for captured tweets.
+//!
+//! Every tweet is stored once, including the ones embedded as retweets
+//! or quotes, together with its author, media, links, hashtags and
+//! mentions. Embedded tweets are stored as their own rows and joined
+//! back together when reading, so compliance events only ever need to
+//! touch a single row. Deletions, location scrubbing and withholding are
+//! remembered and applied again whenever a copy of an affected tweet is stored
+//! later on. `SqliteStore` implements `ComplianceStore`, so it
+//! can be used with a `ComplianceEngine` to keep an audit log.
+
+use chrono::{SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde_json::Value;
+use std::error;
+use std::fmt;
+use std::path::Path;
+
+use crate::compliance::{ComplianceEngine, ComplianceEvent, ComplianceStore};
+use crate::model::{Delete, ScrubGeo, Tweet, User};
+use crate::export::entities;
+use crate::normalize::{tweet_media, tweet_urls};
+use crate::TwitterResponse;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY,
+    captured_at TEXT NOT NULL,
+    screen_name TEXT NOT NULL,
+    name TEXT NOT NULL,
+    location TEXT,
+    description TEXT,
+    url TEXT,
+    protected INTEGER NOT NULL,
+    verified INTEGER NOT NULL,
+    followers_count INTEGER NOT NULL,
+    friends_count INTEGER NOT NULL,
+    listed_count INTEGER NOT NULL,
+    favourites_count INTEGER NOT NULL,
+    statuses_count INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    profile_image_url_https TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS user_snapshots (
+    user_id INTEGER NOT NULL,
+    captured_at TEXT NOT NULL,
+    screen_name TEXT NOT NULL,
+    name TEXT NOT NULL,
+    location TEXT,
+    description TEXT,
+    url TEXT,
+    protected INTEGER NOT NULL,
+    verified INTEGER NOT NULL,
+    followers_count INTEGER NOT NULL,
+    friends_count INTEGER NOT NULL,
+    listed_count INTEGER NOT NULL,
+    favourites_count INTEGER NOT NULL,
+    statuses_count INTEGER NOT NULL,
+    PRIMARY KEY (user_id, captured_at)
+);
+
+CREATE TABLE IF NOT EXISTS tweets (
+    id INTEGER PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    full_text TEXT NOT NULL,
+    source TEXT NOT NULL,
+    lang TEXT,
+    in_reply_to_status_id INTEGER,
+    in_reply_to_user_id INTEGER,
+    retweeted_status_id INTEGER,
+    quoted_status_id INTEGER,
+    place_id TEXT,
+    place_full_name TEXT,
+    longitude REAL,
+    latitude REAL,
+    retweet_count INTEGER NOT NULL,
+    reply_count INTEGER NOT NULL,
+    quote_count INTEGER,
+    favorite_count INTEGER,
+    possibly_sensitive INTEGER NOT NULL,
+    json TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS tweets_user_id ON tweets (user_id, id);
+CREATE INDEX IF NOT EXISTS tweets_retweeted_status_id ON tweets (retweeted_status_id);
+
+CREATE TABLE IF NOT EXISTS media (
+    tweet_id INTEGER NOT NULL,
+    id INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    media_url_https TEXT NOT NULL,
+    url TEXT,
+    short_url TEXT NOT NULL,
+    expanded_url TEXT NOT NULL,
+    source_status_id INTEGER,
+    PRIMARY KEY (tweet_id, id)
+);
+
+CREATE TABLE IF NOT EXISTS urls (
+    tweet_id INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    expanded_url TEXT NOT NULL,
+    display_url TEXT NOT NULL,
+    unwound_url TEXT,
+    PRIMARY KEY (tweet_id, url)
+);
+
+CREATE TABLE IF NOT EXISTS hashtags (
+    tweet_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    PRIMARY KEY (tweet_id, position)
+);
+
+CREATE TABLE IF NOT EXISTS mentions (
+    tweet_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    user_id INTEGER,
+    screen_name TEXT NOT NULL,
+    PRIMARY KEY (tweet_id, position)
+);
+
+CREATE TABLE IF NOT EXISTS deleted_tweets (
+    id INTEGER PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS scrubbed_geo (
+    user_id INTEGER PRIMARY KEY,
+    up_to_status_id INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS withheld_tweets (
+    id INTEGER PRIMARY KEY,
+    countries TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS withheld_users (
+    user_id INTEGER PRIMARY KEY,
+    countries TEXT NOT NULL
+);
+";
+
+/// Tables holding rows that belong to a single tweet
+const CHILD_TABLES: &[&str] = &["media", "urls", "hashtags", "mentions"];
+
+/// Errors that can occur while storing or loading tweets
+#[derive(Debug)]
+pub enum Error {
+    /// The database returned an error
+    Sqlite(rusqlite::Error),
+    /// A stored tweet could not be converted to or from JSON
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Sqlite(why) => write!(f, "sqlite error: {}", why),
+            Error::Json(why) => write!(f, "json error: {}", why),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Sqlite(why) => Some(why),
+            Error::Json(why) => Some(why),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(why: rusqlite::Error) -> Self {
+        Error::Sqlite(why)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(why: serde_json::Error) -> Self {
+        Error::Json(why)
+    }
+}
+
+/// A tweet store backed by a SQLite database
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    /// Opens or creates a database file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Creates a database that only lives in memory
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Uses an existing connection, creating the schema if needed
+    pub fn from_connection(conn: Connection) -> Result<Self, Error> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(SqliteStore { conn })
+    }
+
+    /// The underlying connection, for running custom queries
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Inserts or updates a tweet and everything embedded in it
+    pub fn upsert(&mut self, tweet: &Tweet) -> Result<(), Error> {
+        let tx = self.conn.transaction()?;
+        upsert_tweet(&tx, tweet)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Inserts or updates many tweets in a single transaction
+    pub fn upsert_all<'a, I>(&mut self, tweets: I) -> Result<(), Error>
+        where I: IntoIterator<Item = &'a Tweet>
+    {
+        let tx = self.conn.transaction()?;
+
+        for tweet in tweets {
+            upsert_tweet(&tx, tweet)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Stores tweets and applies compliance events from a stream.
+    /// Anything else is ignored. No audit log is kept; wrap the store
+    /// in a `ComplianceEngine` to find out which tweets were changed.
+    pub fn apply(&mut self, response: &TwitterResponse) -> Result<(), Error> {
+        if let TwitterResponse::Tweet(tweet) = response {
+            return self.upsert(tweet);
+        }
+
+        if let Some(event) = ComplianceEvent::from_response(response) {
+            ComplianceEngine::new(self).apply(&event)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes a deleted tweet along with any retweets of it.
+    /// Returns how many tweets were removed.
+    pub fn apply_delete(&mut self, delete: &Delete) -> Result<usize, Error> {
+        self.delete_tweet(delete.delete.status.id)
+    }
+
+    /// Removes a tweet along with any retweets of it.
+    /// Returns how many tweets were removed.
+    pub fn delete_tweet(&mut self, id: u64) -> Result<usize, Error> {
+        ComplianceStore::delete_tweet(self, id).map(|ids| ids.len())
+    }
+
+    /// Removes location information from tweets of a user up to
+    /// and including a given tweet. Returns how many tweets changed.
+    pub fn apply_scrub_geo(&mut self, scrub: &ScrubGeo) -> Result<usize, Error> {
+        self.scrub_geo(scrub.scrub_geo.user_id, scrub.scrub_geo.up_to_status_id)
+    }
+
+    /// Removes location information from tweets of a user up to
+    /// and including a given tweet. Returns how many tweets changed.
+    pub fn scrub_geo(&mut self, user_id: u64, up_to_status_id: u64) -> Result<usize, Error> {
+        ComplianceStore::scrub_geo(self, user_id, up_to_status_id).map(|ids| ids.len())
+    }
+
+    /// Loads a tweet, including the tweets it retweets or quotes
+    /// when they are stored as well.
+    pub fn get(&self, id: u64) -> Result<Option<Tweet>, Error> {
+        let row = self.conn
+            .query_row(
+                "SELECT json, retweeted_status_id, quoted_status_id FROM tweets WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<u64>>(1)?, row.get::<_, Option<u64>>(2)?)),
+            )
+            .optional()?;
+
+        let (json, retweeted_status_id, quoted_status_id) = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let mut tweet: Tweet = serde_json::from_str(&json)?;
+
+        if let Some(rt) = retweeted_status_id {
+            tweet.retweeted_status = self.get(rt)?.map(Box::new);
+        }
+
+        if let Some(quote) = quoted_status_id {
+            tweet.quoted_status = self.get(quote)?.map(Box::new);
+        }
+
+        Ok(Some(tweet))
+    }
+
+    /// Loads all stored tweets of a user, oldest first
+    pub fn tweets_by_user(&self, user_id: u64) -> Result<Vec<Tweet>, Error> {
+        self.query_ids("SELECT id FROM tweets WHERE user_id = ?1 ORDER BY id", params![user_id])
+    }
+
+    /// Loads all stored tweets with a hashtag, oldest first. The
+    /// hashtag is matched without the leading `#` and ignoring case.
+    pub fn tweets_with_hashtag(&self, hashtag: &str) -> Result<Vec<Tweet>, Error> {
+        self.query_ids(
+            "SELECT DISTINCT tweet_id FROM hashtags WHERE text = ?1 COLLATE NOCASE ORDER BY tweet_id",
+            params![hashtag],
+        )
+    }
+
+    /// How many tweets are stored
+    pub fn len(&self) -> Result<u64, Error> {
+        Ok(self.conn.query_row("SELECT COUNT(*) FROM tweets", [], |row| row.get(0))?)
+    }
+
+    /// Whether no tweets are stored
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+
+    fn query_ids<P: rusqlite::Params>(&self, sql: &str, params: P) -> Result<Vec<Tweet>, Error> {
+        let ids = select_ids(&self.conn, sql, params)?;
+        let mut tweets = Vec::with_capacity(ids.len());
+
+        for id in ids {
+            if let Some(tweet) = self.get(id)? {
+                tweets.push(tweet);
+            }
+        }
+
+        Ok(tweets)
+    }
+}
+
+impl ComplianceStore for SqliteStore {
+    type Error = Error;
+
+    /// Removes a tweet along with any retweets of it
+    fn delete_tweet(&mut self, status_id: u64) -> Result<Vec<u64>, Error> {
+        let tx = self.conn.transaction()?;
+
+        let mut ids = vec![status_id];
+        ids.extend(select_ids(&tx, "SELECT id FROM tweets WHERE retweeted_status_id = ?1 ORDER BY id", params![status_id])?);
+
+        tx.execute("INSERT OR IGNORE INTO deleted_tweets (id) VALUES (?1)", params![status_id])?;
+
+        let mut removed = Vec::new();
+
+        for id in ids {
+            for table in CHILD_TABLES {
+                tx.execute(&format!("DELETE FROM {} WHERE tweet_id = ?1", table), params![id])?;
+            }
+
+            if tx.execute("DELETE FROM tweets WHERE id = ?1", params![id])? > 0 {
+                removed.push(id);
+            }
+        }
+
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    fn scrub_geo(&mut self, user_id: u64, up_to_status_id: u64) -> Result<Vec<u64>, Error> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO scrubbed_geo (user_id, up_to_status_id) VALUES (?1, ?2)
+             ON CONFLICT (user_id) DO UPDATE SET
+                up_to_status_id = max(up_to_status_id, excluded.up_to_status_id)",
+            params![user_id, up_to_status_id],
+        )?;
+
+        let ids = select_ids(
+            &tx,
+            "SELECT id FROM tweets
+             WHERE user_id = ?1 AND id <= ?2
+               AND (place_id IS NOT NULL OR longitude IS NOT NULL
+                    OR json_extract(json, '$.geo') IS NOT NULL)
+             ORDER BY id",
+            params![user_id, up_to_status_id],
+        )?;
+
+        for id in &ids {
+            tx.execute(
+                "UPDATE tweets
+                 SET place_id = NULL, place_full_name = NULL, longitude = NULL, latitude = NULL,
+                     json = json_set(json, '$.coordinates', NULL, '$.place', NULL, '$.geo', NULL)
+                 WHERE id = ?1",
+                params![id],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(ids)
+    }
+
+    fn withhold_tweet(&mut self, status_id: u64, countries: &[String]) -> Result<Vec<u64>, Error> {
+        let tx = self.conn.transaction()?;
+        let countries = serde_json::to_string(countries)?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO withheld_tweets (id, countries) VALUES (?1, ?2)",
+            params![status_id, countries],
+        )?;
+
+        let changed = tx.execute(
+            "UPDATE tweets SET json = json_set(json, '$.withheld_in_countries', json(?2)) WHERE id = ?1",
+            params![status_id, countries],
+        )?;
+
+        tx.commit()?;
+        Ok(if changed > 0 { vec![status_id] } else { Vec::new() })
+    }
+
+    fn withhold_user(&mut self, user_id: u64, countries: &[String]) -> Result<Vec<u64>, Error> {
+        let tx = self.conn.transaction()?;
+        let ids = select_ids(&tx, "SELECT id FROM tweets WHERE user_id = ?1 ORDER BY id", params![user_id])?;
+        let countries = serde_json::to_string(countries)?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO withheld_users (user_id, countries) VALUES (?1, ?2)",
+            params![user_id, countries],
+        )?;
+
+        tx.execute(
+            "UPDATE tweets SET json = json_set(json, '$.user.withheld_in_countries', json(?2)) WHERE user_id = ?1",
+            params![user_id, countries],
+        )?;
+
+        tx.commit()?;
+        Ok(ids)
+    }
+}
+
+fn select_ids<P: rusqlite::Params>(conn: &Connection, sql: &str, params: P) -> Result<Vec<u64>, Error> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(params, |row| row.get::<_, u64>(0))?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Whether a tweet was deleted by a compliance event
+fn is_deleted(tx: &Transaction, id: u64) -> Result<bool, Error> {
+    Ok(tx.query_row("SELECT 1 FROM deleted_tweets WHERE id = ?1", params![id], |_| Ok(())).optional()?.is_some())
+}
+
+/// Whether the location of a tweet was scrubbed by a compliance event
+fn is_geo_scrubbed(tx: &Transaction, tweet: &Tweet) -> Result<bool, Error> {
+    let up_to: Option<u64> = tx
+        .query_row(
+            "SELECT up_to_status_id FROM scrubbed_geo WHERE user_id = ?1",
+            params![tweet.user.id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(matches!(up_to, Some(up_to) if tweet.id <= up_to))
+}
+
+/// Countries a tweet or user was withheld in by a compliance event
+fn withheld_countries(tx: &Transaction, sql: &str, id: u64) -> Result<Option<Value>, Error> {
+    let countries: Option<String> = tx.query_row(sql, params![id], |row| row.get(0)).optional()?;
+
+    match countries {
+        Some(countries) => Ok(Some(serde_json::from_str(&countries)?)),
+        None => Ok(None),
+    }
+}
+
+/// Stores a tweet, skipping it when it or the tweet it retweets was
+/// deleted and re-applying scrubbing and withholding to it, so a copy seen
+/// again later cannot undo a compliance event.
+fn upsert_tweet(tx: &Transaction, tweet: &Tweet) -> Result<(), Error> {
+    if is_deleted(tx, tweet.id)? || is_deleted(tx, tweet.base_id())? {
+        return Ok(());
+    }
+
+    if let Some(rt) = &tweet.retweeted_status {
+        upsert_tweet(tx, rt)?;
+    }
+
+    if let Some(quote) = &tweet.quoted_status {
+        upsert_tweet(tx, quote)?;
+    }
+
+    let captured_at = Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true);
+    upsert_user(tx, &tweet.user, &captured_at)?;
+
+    //  Embedded tweets live in their own rows and are joined back in `get`
+    let mut json = serde_json::to_value(tweet)?;
+    if let Value::Object(map) = &mut json {
+        map.remove("retweeted_status");
+        map.remove("quoted_status");
+    }
+
+    let scrubbed = is_geo_scrubbed(tx, tweet)?;
+    let place = tweet.place.as_ref().filter(|_| !scrubbed);
+    let coordinates = tweet.coordinates.as_ref().filter(|_| !scrubbed).map(|c| c.coordinates);
+
+    let withheld = withheld_countries(tx, "SELECT countries FROM withheld_tweets WHERE id = ?1", tweet.id)?;
+    let user_withheld =
+        withheld_countries(tx, "SELECT countries FROM withheld_users WHERE user_id = ?1", tweet.user.id)?;
+
+    if let Value::Object(map) = &mut json {
+        if scrubbed {
+            for key in &["coordinates", "place", "geo"] {
+                map.insert(key.to_string(), Value::Null);
+            }
+        }
+
+        if let Some(countries) = withheld {
+            map.insert("withheld_in_countries".to_string(), countries);
+        }
+
+        if let (Some(countries), Some(Value::Object(user))) = (user_withheld, map.get_mut("user")) {
+            user.insert("withheld_in_countries".to_string(), countries);
+        }
+    }
+
+    tx.execute(
+        "INSERT INTO tweets (
+            id, user_id, created_at, full_text, source, lang, in_reply_to_status_id,
+            in_reply_to_user_id, retweeted_status_id, quoted_status_id, place_id, place_full_name,
+            longitude, latitude, retweet_count, reply_count, quote_count, favorite_count,
+            possibly_sensitive, json
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)
+        ON CONFLICT (id) DO UPDATE SET
+            retweet_count = excluded.retweet_count,
+            reply_count = excluded.reply_count,
+            quote_count = excluded.quote_count,
+            favorite_count = excluded.favorite_count,
+            place_id = excluded.place_id,
+            place_full_name = excluded.place_full_name,
+            longitude = excluded.longitude,
+            latitude = excluded.latitude,
+            json = excluded.json",
+        params![
+            tweet.id,
+            tweet.user.id,
+            tweet.created_at.to_rfc3339(),
+            tweet.full_text(),
+            tweet.source,
+            tweet.lang,
+            tweet.in_reply_to_status_id,
+            tweet.in_reply_to_user_id,
+            tweet.retweeted_status.as_ref().map(|rt| rt.id),
+            tweet.quoted_status_id,
+            place.map(|p| &p.id),
+            place.map(|p| &p.full_name),
+            coordinates.map(|c| c.0),
+            coordinates.map(|c| c.1),
+            tweet.retweet_count,
+            tweet.reply_count,
+            tweet.quote_count,
+            tweet.favorite_count,
+            tweet.is_sensitive(),
+            json.to_string(),
+        ],
+    )?;
+
+    //  Rows of an earlier version of the tweet may no longer apply
+    for table in CHILD_TABLES {
+        tx.execute(&format!("DELETE FROM {} WHERE tweet_id = ?1", table), params![tweet.id])?;
+    }
+
+    //  The entities of a retweet are copies of the original's
+    if tweet.is_retweet() {
+        return Ok(());
+    }
+
+    for media in tweet_media(tweet) {
+        tx.execute(
+            "INSERT OR REPLACE INTO media (
+                tweet_id, id, type, media_url_https, url, short_url, expanded_url, source_status_id
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                tweet.id,
+                media.id,
+                media.kind.as_str(),
+                media.media_url_https,
+                media.url(),
+                media.url,
+                media.expanded_url,
+                media.source_status_id,
+            ],
+        )?;
+    }
+
+    for url in tweet_urls(tweet) {
+        tx.execute(
+            "INSERT OR REPLACE INTO urls (tweet_id, url, expanded_url, display_url, unwound_url)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                tweet.id,
+                url.url,
+                url.expanded_url,
+                url.display_url,
+                url.unwound.as_ref().map(|u| &u.url),
+            ],
+        )?;
+    }
+
+    if let Some(entities) = entities(tweet) {
+        for (position, hashtag) in entities.hashtags.iter().enumerate() {
+            tx.execute(
+                "INSERT OR REPLACE INTO hashtags (tweet_id, position, text) VALUES (?1, ?2, ?3)",
+                params![tweet.id, position, hashtag.text],
+            )?;
+        }
+
+        for (position, mention) in entities.user_mentions.iter().enumerate() {
+            tx.execute(
+                "INSERT OR REPLACE INTO mentions (tweet_id, position, user_id, screen_name)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![tweet.id, position, mention.id, mention.screen_name],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Records a snapshot of the user as seen in a tweet, and updates the
+/// current user row if this is the most recent snapshot.
+fn upsert_user(tx: &Transaction, user: &User, captured_at: &str) -> Result<(), Error> {
+    tx.execute(
+        "INSERT OR IGNORE INTO user_snapshots (
+            user_id, captured_at, screen_name, name, location, description, url, protected,
+            verified, followers_count, friends_count, listed_count, favourites_count, statuses_count
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            user.id,
+            captured_at,
+            user.screen_name,
+            user.name,
+            user.location,
+            user.description,
+            user.url,
+            user.protected,
+            user.verified,
+            user.followers_count,
+            user.friends_count,
+            user.listed_count,
+            user.favourites_count,
+            user.statuses_count,
+        ],
+    )?;
+
+    tx.execute(
+        "INSERT INTO users (
+            id, captured_at, screen_name, name, location, description, url, protected, verified,
+            followers_count, friends_count, listed_count, favourites_count, statuses_count,
+            created_at, profile_image_url_https
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+        ON CONFLICT (id) DO UPDATE SET
+            captured_at = excluded.captured_at,
+            screen_name = excluded.screen_name,
+            name = excluded.name,
+            location = excluded.location,
+            description = excluded.description,
+            url = excluded.url,
+            protected = excluded.protected,
+            verified = excluded.verified,
+            followers_count = excluded.followers_count,
+            friends_count = excluded.friends_count,
+            listed_count = excluded.listed_count,
+            favourites_count = excluded.favourites_count,
+            statuses_count = excluded.statuses_count,
+            profile_image_url_https = excluded.profile_image_url_https
+        WHERE excluded.captured_at >= users.captured_at",
+        params![
+            user.id,
+            captured_at,
+            user.screen_name,
+            user.name,
+            user.location,
+            user.description,
+            user.url,
+            user.protected,
+            user.verified,
+            user.followers_count,
+            user.friends_count,
+            user.listed_count,
+            user.favourites_count,
+            user.statuses_count,
+            user.created_at.to_rfc3339(),
+            user.profile_image_url_https,
+        ],
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+    use crate::model::{BoundingBox, Coordinates, Entity, Hashtag, Place};
+
+    fn tweet(id: u64, user_id: u64) -> Tweet {
+        Tweet {
+            id,
+            id_str: id.to_string(),
+            text: format!("tweet {}", id),
+            user: User {
+                id: user_id,
+                id_str: user_id.to_string(),
+                screen_name: format!("user{}", user_id),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn geo_tweet(id: u64, user_id: u64) -> Tweet {
+        Tweet {
+            coordinates: Some(Coordinates {
+                coordinates: (-122.4, 37.8),
+                kind: "Point".to_string(),
+            }),
+            place: Some(Place {
+                id: "5a110d312052166f".to_string(),
+                url: String::new(),
+                place_type: "city".to_string(),
+                name: "San Francisco".to_string(),
+                full_name: "San Francisco, CA".to_string(),
+                country_code: "US".to_string(),
+                country: "United States".to_string(),
+                bounding_box: BoundingBox { coordinates: Vec::new() },
+            }),
+            ..tweet(id, user_id)
+        }
+    }
+
+    fn retweet(id: u64, user_id: u64, original: Tweet) -> Tweet {
+        Tweet {
+            retweeted_status: Some(Box::new(original)),
+            ..tweet(id, user_id)
+        }
+    }
+
+    fn with_hashtags(mut tweet: Tweet, hashtags: &[&str]) -> Tweet {
+        tweet.entities = Some(Entity {
+            hashtags: hashtags
+                .iter()
+                .map(|text| Hashtag { indices: vec![0, 0], text: text.to_string() })
+                .collect(),
+            ..Default::default()
+        });
+        tweet
+    }
+
+    fn event(json: &str) -> TwitterResponse {
+        json.parse().unwrap()
+    }
+
+    fn delete(id: u64, user_id: u64) -> TwitterResponse {
+        event(&format!(
+            r#"{{"delete":{{"status":{{"id":{0},"id_str":"{0}","user_id":{1},"user_id_str":"{1}"}}}}}}"#,
+            id, user_id
+        ))
+    }
+
+    fn scrub_geo(user_id: u64, up_to: u64) -> TwitterResponse {
+        event(&format!(
+            r#"{{"scrub_geo":{{"user_id":{0},"user_id_str":"{0}","up_to_status_id":{1},"up_to_status_id_str":"{1}"}}}}"#,
+            user_id, up_to
+        ))
+    }
+
+    fn geo_columns(store: &SqliteStore, id: u64) -> (Option<String>, Option<f64>) {
+        store
+            .connection()
+            .query_row("SELECT place_id, longitude FROM tweets WHERE id = ?1", params![id], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn upsert_stores_embedded_tweets() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store.upsert(&retweet(2, 20, with_hashtags(tweet(1, 10), &["rust"]))).unwrap();
+
+        assert_eq!(store.len().unwrap(), 2);
+
+        let rt = store.get(2).unwrap().unwrap();
+        assert_eq!(rt.retweeted_status.unwrap().id, 1);
+        assert_eq!(store.tweets_by_user(10).unwrap().len(), 1);
+        assert_eq!(store.tweets_with_hashtag("RUST").unwrap()[0].id, 1);
+    }
+
+    #[test]
+    fn upsert_replaces_child_rows() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store.upsert(&with_hashtags(tweet(1, 10), &["one", "two"])).unwrap();
+        store.upsert(&with_hashtags(tweet(1, 10), &["three"])).unwrap();
+
+        assert!(store.tweets_with_hashtag("two").unwrap().is_empty());
+        assert_eq!(store.tweets_with_hashtag("three").unwrap().len(), 1);
+
+        let rows: u64 = store
+            .connection()
+            .query_row("SELECT COUNT(*) FROM hashtags WHERE tweet_id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn upsert_updates_location() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store.upsert(&tweet(1, 10)).unwrap();
+        assert_eq!(geo_columns(&store, 1), (None, None));
+
+        store.upsert(&geo_tweet(1, 10)).unwrap();
+        assert_eq!(geo_columns(&store, 1), (Some("5a110d312052166f".to_string()), Some(-122.4)));
+
+        store.upsert(&tweet(1, 10)).unwrap();
+        assert_eq!(geo_columns(&store, 1), (None, None));
+    }
+
+    #[test]
+    fn users_are_captured_when_stored() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        let before = Utc::now();
+        store.upsert(&tweet(1, 10)).unwrap();
+
+        let (captured_at, created_at): (String, String) = store
+            .connection()
+            .query_row(
+                "SELECT users.captured_at, tweets.created_at FROM users JOIN tweets ON tweets.user_id = users.id",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+
+        assert!(captured_at.parse::<DateTime<Utc>>().unwrap() >= before);
+        assert_eq!(created_at, tweet(1, 10).created_at.to_rfc3339());
+    }
+
+    #[test]
+    fn delete_removes_retweets() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store.upsert(&retweet(2, 20, tweet(1, 10))).unwrap();
+        store.upsert(&tweet(3, 10)).unwrap();
+
+        store.apply(&delete(1, 10)).unwrap();
+
+        assert!(store.get(1).unwrap().is_none());
+        assert!(store.get(2).unwrap().is_none());
+        assert!(store.get(3).unwrap().is_some());
+    }
+
+    #[test]
+    fn deleted_tweets_are_not_stored_again() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store.upsert(&tweet(1, 10)).unwrap();
+        store.apply(&delete(1, 10)).unwrap();
+
+        store.upsert(&tweet(1, 10)).unwrap();
+        store.upsert(&retweet(2, 20, tweet(1, 10))).unwrap();
+
+        assert!(store.is_empty().unwrap());
+    }
+
+    #[test]
+    fn scrub_geo_clears_location() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store.upsert_all(&[geo_tweet(1, 10), geo_tweet(2, 10), geo_tweet(3, 20)]).unwrap();
+
+        store.apply(&scrub_geo(10, 1)).unwrap();
+
+        let scrubbed = store.get(1).unwrap().unwrap();
+        assert!(scrubbed.coordinates.is_none());
+        assert!(scrubbed.place.is_none());
+        assert_eq!(geo_columns(&store, 1), (None, None));
+
+        assert!(store.get(2).unwrap().unwrap().coordinates.is_some());
+        assert!(store.get(3).unwrap().unwrap().place.is_some());
+    }
+
+    #[test]
+    fn upsert_after_scrub_keeps_location_removed() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store.upsert(&geo_tweet(1, 10)).unwrap();
+        store.apply(&scrub_geo(10, 1)).unwrap();
+
+        //  The same tweet seen again inside a retweet
+        store.upsert(&retweet(5, 20, geo_tweet(1, 10))).unwrap();
+        //  A tweet captured before the event but stored after it
+        store.upsert(&geo_tweet(0, 10)).unwrap();
+
+        for id in &[0, 1] {
+            let tweet = store.get(*id).unwrap().unwrap();
+            assert!(tweet.coordinates.is_none());
+            assert!(tweet.place.is_none());
+            assert_eq!(geo_columns(&store, *id), (None, None));
+        }
+
+        store.upsert(&geo_tweet(2, 10)).unwrap();
+        assert!(store.get(2).unwrap().unwrap().coordinates.is_some());
+    }
+
+    #[test]
+    fn upsert_after_withhold_keeps_countries() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        let countries = vec!["DE".to_string()];
+        store.withhold_tweet(1, &countries).unwrap();
+        store.withhold_user(20, &countries).unwrap();
+
+        store.upsert(&retweet(2, 20, tweet(1, 10))).unwrap();
+
+        let rt = store.get(2).unwrap().unwrap();
+        assert_eq!(rt.user.withheld_in_countries.as_ref(), Some(&countries));
+        assert_eq!(rt.retweeted_status.unwrap().withheld_in_countries, Some(countries));
+    }
+}
@@ -0,0 +1,606 @@
+//! Helpers for consuming streaming connections.
+//!
+//! `LimitTracker` turns the cumulative counts of `limit` notices into
+//! the number of tweets dropped between notices, and keeps totals
+//! across reconnects to alert on filters that match too much.
+//!
+//! `ReconnectPolicy` decides when to reconnect after a connection
+//! failed, following the backoff rules of the streaming endpoints.
+//!
+//! `LineReader` splits the raw body of a stream, or a capture of one,
+//! into messages.
+
+use chrono::{DateTime, Utc};
+use std::io::{self, BufRead};
+use std::time::{Duration, Instant};
+
+use crate::model::Limit;
+use crate::TwitterResponse;
+
+/// Statistics about tweets that were not delivered
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LimitStats {
+    /// How many connections have been tracked
+    pub connections: u32,
+    /// How many limit notices were received
+    pub notices: u64,
+    /// How many tweets were not delivered, over all connections
+    pub dropped: u64,
+    /// How many of `dropped` were reported after the first notice.
+    /// The counts of the first notice cover an unknown time before it.
+    pub dropped_since_first_notice: u64,
+    /// How many tweets were delivered, over all connections
+    pub delivered: u64,
+    /// When the first limit notice was sent
+    pub first_notice: Option<DateTime<Utc>>,
+    /// When the latest limit notice was sent
+    pub last_notice: Option<DateTime<Utc>>,
+}
+
+impl LimitStats {
+    /// The fraction of matching tweets that were not delivered, from 0 to 1
+    pub fn drop_ratio(&self) -> f64 {
+        let total = self.dropped + self.delivered;
+
+        if total == 0 {
+            0.0
+        } else {
+            self.dropped as f64 / total as f64
+        }
+    }
+
+    /// How many tweets were dropped per minute between the first and
+    /// latest notice. `None` until two notices with different times
+    /// were received.
+    pub fn drops_per_minute(&self) -> Option<f64> {
+        let elapsed = self.last_notice? - self.first_notice?;
+        let minutes = elapsed.num_milliseconds() as f64 / 60_000.0;
+
+        if minutes > 0.0 {
+            Some(self.dropped_since_first_notice as f64 / minutes)
+        } else {
+            None
+        }
+    }
+}
+
+/// Computes how many tweets were dropped between limit notices.
+///
+/// The `track` of a limit notice counts every undelivered tweet since
+/// the connection was established, so the tracker has to be told about
+/// reconnects with `reconnected`. A count lower than the previous one
+/// is also taken as a new connection.
+#[derive(Debug, Default)]
+pub struct LimitTracker {
+    last_track: Option<u64>,
+    stats: LimitStats,
+}
+
+impl LimitTracker {
+    /// Creates a tracker for a new connection
+    pub fn new() -> Self {
+        let mut tracker = Self::default();
+        tracker.stats.connections = 1;
+        tracker
+    }
+
+    /// Records a limit notice and returns how many tweets were dropped
+    /// since the previous notice of this connection
+    pub fn observe(&mut self, limit: &Limit) -> u64 {
+        let track = limit.track();
+        let delta = match self.last_track {
+            Some(last) if track >= last => track - last,
+            Some(_) => {
+                self.stats.connections += 1;
+                track
+            }
+            None => track,
+        };
+
+        self.last_track = Some(track);
+        self.stats.notices += 1;
+        self.stats.dropped += delta;
+
+        if self.stats.first_notice.is_some() {
+            self.stats.dropped_since_first_notice += delta;
+        }
+
+        //  A malformed timestamp would throw off `drops_per_minute`
+        if let Some(timestamp) = limit.timestamp() {
+            self.stats.first_notice = self.stats.first_notice.or(Some(timestamp));
+            self.stats.last_notice = Some(timestamp);
+        }
+
+        delta
+    }
+
+    /// Records a stream message. Tweets count as delivered and limit
+    /// notices as dropped tweets. Returns the drop count of a notice.
+    pub fn observe_response(&mut self, response: &TwitterResponse) -> Option<u64> {
+        match response {
+            TwitterResponse::Tweet(_) => {
+                self.record_delivered(1);
+                None
+            }
+            TwitterResponse::Limit(limit) => Some(self.observe(limit)),
+            _ => None,
+        }
+    }
+
+    /// Counts tweets that were delivered, to compute the drop ratio
+    pub fn record_delivered(&mut self, count: u64) {
+        self.stats.delivered += count;
+    }
+
+    /// Starts counting a new connection, keeping the totals
+    pub fn reconnected(&mut self) {
+        self.last_track = None;
+        self.stats.connections += 1;
+    }
+
+    /// The totals so far
+    pub fn stats(&self) -> LimitStats {
+        self.stats
+    }
+
+    /// Returns the totals so far and starts over, for example at the
+    /// end of a reporting interval. The current connection is kept.
+    pub fn take_stats(&mut self) -> LimitStats {
+        std::mem::replace(&mut self.stats, LimitStats {
+            connections: 1,
+            ..Default::default()
+        })
+    }
+}
+
+/// Splits a stream into messages.
+///
+/// Messages are separated by `\r\n`. Blank lines are keep-alives and
+/// are skipped, as are the length prefixes of streams requested with
+/// `delimited=length`.
+#[derive(Debug)]
+pub struct LineReader<R> {
+    input: R,
+    keep_alives: u64,
+}
+
+impl<R: BufRead> LineReader<R> {
+    /// Reads messages from `input`
+    pub fn new(input: R) -> Self {
+        LineReader {
+            input,
+            keep_alives: 0,
+        }
+    }
+
+    /// How many keep-alives were skipped so far
+    pub fn keep_alives(&self) -> u64 {
+        self.keep_alives
+    }
+
+    /// Returns the input
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+}
+
+impl<R: BufRead> Iterator for LineReader<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut line = String::new();
+
+            match self.input.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(why) => return Some(Err(why)),
+            }
+
+            let message = line.trim();
+
+            if message.is_empty() {
+                self.keep_alives += 1;
+            } else if !message.bytes().all(|byte| byte.is_ascii_digit()) {
+                return Some(Ok(message.to_string()));
+            }
+        }
+    }
+}
+
+/// First delay after a network error, increased by as much on each attempt
+const NETWORK_STEP: Duration = Duration::from_millis(250);
+/// Longest delay after network errors
+const NETWORK_MAX: Duration = Duration::from_secs(16);
+/// First delay after an HTTP error, doubled on each attempt
+const HTTP_START: Duration = Duration::from_secs(5);
+/// Longest delay after HTTP errors
+const HTTP_MAX: Duration = Duration::from_secs(320);
+/// First delay after being rate limited, doubled on each attempt
+const RATE_LIMIT_START: Duration = Duration::from_secs(60);
+/// Longest delay after being rate limited, unless configured otherwise
+const RATE_LIMIT_MAX: Duration = Duration::from_secs(960);
+/// How long a connection may go without data. Keep-alive newlines are
+/// sent every 30 seconds.
+const STALL_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Something that happened to a streaming connection
+#[derive(Clone, Copy, Debug)]
+pub enum ConnectionEvent<'a> {
+    /// The stream answered with HTTP 200
+    Connected,
+    /// Any data arrived, including keep-alive newlines
+    Data,
+    /// A message arrived. Disconnect and warning messages are acted on.
+    Message(&'a TwitterResponse),
+    /// The connection failed or was closed at the TCP/IP level
+    NetworkError,
+    /// The stream answered with an HTTP error status
+    HttpError(u16),
+}
+
+/// Which backoff schedule a failure falls under
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Backoff {
+    /// Linear, starting at 250 milliseconds, up to 16 seconds
+    Network,
+    /// Exponential, starting at 5 seconds, up to 320 seconds
+    Http,
+    /// Exponential, starting at 1 minute, after HTTP 420 or 429
+    RateLimited,
+}
+
+/// What the client should do after an event
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action {
+    /// Keep reading from the connection
+    Continue,
+    /// Close the connection and open a new one after the delay
+    Reconnect(Duration),
+    /// Do not reconnect, the failure will not go away by retrying
+    Stop,
+}
+
+/// The state of a connection as seen by `ReconnectPolicy`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConnectionState {
+    /// No connection was made yet
+    Idle,
+    /// Connected and reading
+    Connected,
+    /// Waiting to reconnect
+    Backoff(Backoff),
+    /// Gave up after a fatal failure
+    Stopped,
+}
+
+/// Decides when to reconnect to a stream.
+///
+/// The policy does no IO and does not read the clock. It is driven by
+/// `handle` with the events of the connection and the current time, and
+/// `check_stall` has to be called periodically to detect connections
+/// that stopped sending data. Backoff starts over once a connection
+/// succeeds.
+#[derive(Debug)]
+pub struct ReconnectPolicy {
+    state: ConnectionState,
+    attempts: u32,
+    last_data: Option<Instant>,
+    stall_timeout: Duration,
+    rate_limit_max: Duration,
+    percent_full: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            state: ConnectionState::Idle,
+            attempts: 0,
+            last_data: None,
+            stall_timeout: STALL_TIMEOUT,
+            rate_limit_max: RATE_LIMIT_MAX,
+            percent_full: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Creates a policy with the documented delays
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how long a connection may go without data before it is
+    /// considered stalled
+    pub fn stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = timeout;
+        self
+    }
+
+    /// Sets the longest delay after being rate limited
+    pub fn max_rate_limit_delay(mut self, delay: Duration) -> Self {
+        self.rate_limit_max = delay;
+        self
+    }
+
+    /// The current state
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// How many reconnects were attempted since the last success
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// How full the queue of undelivered messages was in the latest
+    /// stall warning of this connection, in percent
+    pub fn percent_full(&self) -> Option<u32> {
+        self.percent_full
+    }
+
+    /// When the connection counts as stalled if no data arrives before
+    pub fn stall_deadline(&self) -> Option<Instant> {
+        match self.state {
+            ConnectionState::Connected => self.last_data.map(|last| last + self.stall_timeout),
+            _ => None,
+        }
+    }
+
+    /// Records an event that happened at `now`
+    pub fn handle(&mut self, event: ConnectionEvent, now: Instant) -> Action {
+        if self.state == ConnectionState::Stopped {
+            return Action::Stop;
+        }
+
+        match event {
+            ConnectionEvent::Connected => {
+                self.state = ConnectionState::Connected;
+                self.attempts = 0;
+                self.last_data = Some(now);
+                self.percent_full = None;
+                Action::Continue
+            }
+            ConnectionEvent::Data => {
+                self.last_data = Some(now);
+                Action::Continue
+            }
+            ConnectionEvent::Message(response) => {
+                self.last_data = Some(now);
+
+                match response {
+                    TwitterResponse::Disconnect(disconnect) if disconnect.kind().is_fatal() => self.stop(),
+                    TwitterResponse::Disconnect(_) => self.back_off(Backoff::Network),
+                    TwitterResponse::Warning(warning) => {
+                        if warning.is_falling_behind() {
+                            self.percent_full = warning.warning.percent_full;
+                        }
+                        Action::Continue
+                    }
+                    _ => Action::Continue,
+                }
+            }
+            ConnectionEvent::NetworkError => self.back_off(Backoff::Network),
+            ConnectionEvent::HttpError(420) | ConnectionEvent::HttpError(429) => self.back_off(Backoff::RateLimited),
+            ConnectionEvent::HttpError(status) if status >= 500 => self.back_off(Backoff::Http),
+            ConnectionEvent::HttpError(_) => self.stop(),
+        }
+    }
+
+    /// Checks at `now` whether the connection went without data for
+    /// too long, and if so, treats it like a network error
+    pub fn check_stall(&mut self, now: Instant) -> Action {
+        match self.stall_deadline() {
+            Some(deadline) if now >= deadline => self.back_off(Backoff::Network),
+            _ if self.state == ConnectionState::Stopped => Action::Stop,
+            _ => Action::Continue,
+        }
+    }
+
+    /// Starts over after a fatal failure was dealt with, for example
+    /// once new credentials are in place
+    pub fn reset(&mut self) {
+        *self = ReconnectPolicy {
+            stall_timeout: self.stall_timeout,
+            rate_limit_max: self.rate_limit_max,
+            ..Default::default()
+        };
+    }
+
+    fn back_off(&mut self, backoff: Backoff) -> Action {
+        //  Each schedule starts from its first delay
+        if self.state != ConnectionState::Backoff(backoff) {
+            self.attempts = 0;
+        }
+
+        self.state = ConnectionState::Backoff(backoff);
+        self.attempts += 1;
+        self.last_data = None;
+
+        let doublings = (self.attempts - 1).min(16);
+        let delay = match backoff {
+            Backoff::Network => (NETWORK_STEP * self.attempts).min(NETWORK_MAX),
+            Backoff::Http => (HTTP_START * (1 << doublings)).min(HTTP_MAX),
+            Backoff::RateLimited => (RATE_LIMIT_START * (1 << doublings)).min(self.rate_limit_max),
+        };
+
+        Action::Reconnect(delay)
+    }
+
+    fn stop(&mut self) -> Action {
+        self.state = ConnectionState::Stopped;
+        self.last_data = None;
+        Action::Stop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn disconnect(code: u32) -> TwitterResponse {
+        format!(r#"{{"disconnect":{{"code":{},"stream_name":"test","reason":"test"}}}}"#, code)
+            .parse()
+            .unwrap()
+    }
+
+    fn limit(track: u64, timestamp_ms: &str) -> Limit {
+        format!(r#"{{"limit":{{"track":{},"timestamp_ms":"{}"}}}}"#, track, timestamp_ms).parse().unwrap()
+    }
+
+    fn delays(policy: &mut ReconnectPolicy, event: ConnectionEvent, times: usize, now: Instant) -> Vec<Duration> {
+        (0..times)
+            .map(|_| match policy.handle(event, now) {
+                Action::Reconnect(delay) => delay,
+                action => panic!("expected a reconnect, got {:?}", action),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn limit_tracker_counts_drops_between_notices() {
+        let mut tracker = LimitTracker::new();
+
+        assert_eq!(tracker.observe(&limit(10, "1600000000000")), 10);
+        assert_eq!(tracker.observe(&limit(25, "not a timestamp")), 15);
+        assert_eq!(tracker.observe(&limit(40, "1600000060000")), 15);
+
+        let stats = tracker.stats();
+        assert_eq!(stats.dropped, 40);
+        assert_eq!(stats.notices, 3);
+        assert_eq!(stats.first_notice.map(|at| at.timestamp()), Some(1_600_000_000));
+        //  The 10 drops of the first notice happened before the measured minute
+        assert_eq!(stats.dropped_since_first_notice, 30);
+        assert_eq!(stats.drops_per_minute(), Some(30.0));
+
+        //  A lower count means the stream reconnected
+        assert_eq!(tracker.observe(&limit(5, "1600000120000")), 5);
+        assert_eq!(tracker.stats().connections, 2);
+    }
+
+    #[test]
+    fn malformed_limit_timestamps_are_skipped() {
+        let mut tracker = LimitTracker::new();
+        tracker.observe(&limit(10, ""));
+
+        assert_eq!(tracker.stats().first_notice, None);
+        assert_eq!(tracker.stats().drops_per_minute(), None);
+
+        //  Drops only count towards the rate once a notice had a time
+        tracker.observe(&limit(20, "1600000000000"));
+        tracker.observe(&limit(26, "1600000030000"));
+        assert_eq!(tracker.stats().dropped, 26);
+        assert_eq!(tracker.stats().drops_per_minute(), Some(12.0));
+    }
+
+    #[test]
+    fn network_errors_back_off_linearly() {
+        let mut policy = ReconnectPolicy::new();
+        let delays = delays(&mut policy, ConnectionEvent::NetworkError, 66, Instant::now());
+
+        assert_eq!(delays[..3], [Duration::from_millis(250), Duration::from_millis(500), Duration::from_millis(750)]);
+        assert_eq!(delays[63], secs(16));
+        assert_eq!(delays[65], secs(16));
+        assert_eq!(policy.state(), ConnectionState::Backoff(Backoff::Network));
+    }
+
+    #[test]
+    fn http_errors_back_off_exponentially() {
+        let mut policy = ReconnectPolicy::new();
+        let delays = delays(&mut policy, ConnectionEvent::HttpError(503), 8, Instant::now());
+
+        assert_eq!(delays, [5, 10, 20, 40, 80, 160, 320, 320].iter().map(|s| secs(*s)).collect::<Vec<_>>());
+        assert_eq!(policy.attempts(), 8);
+    }
+
+    #[test]
+    fn rate_limits_back_off_from_a_minute() {
+        for status in &[420, 429] {
+            let mut policy = ReconnectPolicy::new();
+            let delays = delays(&mut policy, ConnectionEvent::HttpError(*status), 6, Instant::now());
+
+            assert_eq!(delays, [60, 120, 240, 480, 960, 960].iter().map(|s| secs(*s)).collect::<Vec<_>>());
+        }
+
+        let mut policy = ReconnectPolicy::new().max_rate_limit_delay(secs(100));
+        let delays = delays(&mut policy, ConnectionEvent::HttpError(429), 2, Instant::now());
+        assert_eq!(delays, [secs(60), secs(100)]);
+    }
+
+    #[test]
+    fn stalls_after_ninety_seconds_without_data() {
+        let start = Instant::now();
+        let mut policy = ReconnectPolicy::new();
+
+        assert_eq!(policy.handle(ConnectionEvent::Connected, start), Action::Continue);
+        assert_eq!(policy.stall_deadline(), Some(start + secs(90)));
+        assert_eq!(policy.check_stall(start + secs(89)), Action::Continue);
+
+        //  Keep-alives push the deadline back
+        assert_eq!(policy.handle(ConnectionEvent::Data, start + secs(60)), Action::Continue);
+        assert_eq!(policy.check_stall(start + secs(149)), Action::Continue);
+        assert_eq!(policy.check_stall(start + secs(150)), Action::Reconnect(Duration::from_millis(250)));
+        assert_eq!(policy.state(), ConnectionState::Backoff(Backoff::Network));
+        assert_eq!(policy.stall_deadline(), None);
+    }
+
+    #[test]
+    fn connecting_resets_backoff() {
+        let now = Instant::now();
+        let mut policy = ReconnectPolicy::new();
+        delays(&mut policy, ConnectionEvent::HttpError(500), 3, now);
+
+        policy.handle(ConnectionEvent::Connected, now);
+        assert_eq!(policy.state(), ConnectionState::Connected);
+        assert_eq!(policy.attempts(), 0);
+        assert_eq!(delays(&mut policy, ConnectionEvent::HttpError(500), 1, now), [secs(5)]);
+
+        //  Switching to another schedule starts it from its first delay
+        assert_eq!(delays(&mut policy, ConnectionEvent::NetworkError, 1, now), [Duration::from_millis(250)]);
+    }
+
+    #[test]
+    fn fatal_disconnects_stop() {
+        let now = Instant::now();
+
+        for code in &[2, 6, 7] {
+            let mut policy = ReconnectPolicy::new();
+            policy.handle(ConnectionEvent::Connected, now);
+
+            let message = disconnect(*code);
+            assert_eq!(policy.handle(ConnectionEvent::Message(&message), now), Action::Stop);
+            assert_eq!(policy.state(), ConnectionState::Stopped);
+            assert_eq!(policy.handle(ConnectionEvent::NetworkError, now), Action::Stop);
+            assert_eq!(policy.check_stall(now + secs(1000)), Action::Stop);
+
+            policy.reset();
+            assert_eq!(policy.state(), ConnectionState::Idle);
+        }
+
+        let mut policy = ReconnectPolicy::new();
+        let message = disconnect(4);
+        assert_eq!(
+            policy.handle(ConnectionEvent::Message(&message), now),
+            Action::Reconnect(Duration::from_millis(250))
+        );
+
+        assert_eq!(policy.handle(ConnectionEvent::HttpError(401), now), Action::Stop);
+    }
+
+    #[test]
+    fn line_reader_skips_and_counts_keep_alives() {
+        let input = "\r\n{\"id\":1}\r\n\r\n\n12\r\n{\"id\":2}\r\n\r\n";
+        let mut reader = LineReader::new(input.as_bytes());
+
+        assert_eq!(reader.next().unwrap().unwrap(), r#"{"id":1}"#);
+        assert_eq!(reader.keep_alives(), 1);
+        assert_eq!(reader.next().unwrap().unwrap(), r#"{"id":2}"#);
+        assert_eq!(reader.keep_alives(), 3);
+        assert!(reader.next().is_none());
+        assert_eq!(reader.keep_alives(), 4);
+    }
+}
@@ -1,7 +1,8 @@
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Deserializer, Serializer};
 
-const FORMAT: &str = "%a %b %e %T %z %Y";
+/// How Twitter formats dates in its payloads
+pub const FORMAT: &str = "%a %b %e %T %z %Y";
 
 pub fn datefmt_ser<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
     where S: Serializer
@@ -14,6 +15,7 @@ where
     D: Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
-    Utc.datetime_from_str(&s, FORMAT)
+    DateTime::parse_from_str(&s, FORMAT)
+        .map(|date| date.with_timezone(&Utc))
         .map_err(serde::de::Error::custom)
 }
\ No newline at end of file
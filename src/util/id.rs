@@ -0,0 +1,91 @@
+//! The v2 API sends ids as strings. These helpers keep them as `u64`
+//! in the models while still reading and writing them as strings.
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub fn id_ser<S>(id: &u64, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    serializer.serialize_str(&id.to_string())
+}
+
+pub fn id_de<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(serde::de::Error::custom)
+}
+
+pub fn opt_id_ser<S>(id: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    match id {
+        Some(id) => serializer.serialize_some(&id.to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn opt_id_de<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(s) => s.parse().map(Some).map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+pub fn ids_ser<S>(ids: &[u64], serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    serializer.collect_seq(ids.iter().map(|id| id.to_string()))
+}
+
+pub fn ids_de<'de, D>(deserializer: D) -> Result<Vec<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+/// An id sent as either a number or a string
+#[derive(serde_derive::Deserialize)]
+#[serde(untagged)]
+enum LenientId {
+    Number(u64),
+    String(String),
+}
+
+impl LenientId {
+    fn parse<E: serde::de::Error>(self) -> Result<u64, E> {
+        match self {
+            LenientId::Number(id) => Ok(id),
+            LenientId::String(id) => id.parse().map_err(E::custom),
+        }
+    }
+}
+
+/// Reads an id that may be sent as either a number or a string, as
+/// with the users of Account Activity API events
+pub fn lenient_id_de<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    LenientId::deserialize(deserializer)?.parse()
+}
+
+/// Reads ids that may be sent as either numbers or strings, as
+/// with the `stringify_ids` parameter of v1.1 endpoints
+pub fn lenient_ids_de<'de, D>(deserializer: D) -> Result<Vec<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<LenientId>::deserialize(deserializer)?
+        .into_iter()
+        .map(LenientId::parse)
+        .collect()
+}
@@ -5,12 +5,7 @@ impl Serialize for MediaType {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where S: Serializer
     {
-        serializer.serialize_str(match *self {
-            MediaType::Photo => "photo",
-            MediaType::Gif => "gif",
-            MediaType::Video => "video",
-            MediaType::Unknown(ref other) => other,
-        })
+        serializer.serialize_str(self.as_str())
     }
 }
 
@@ -1,2 +1,4 @@
 pub mod datetime;
-pub mod media;
\ No newline at end of file
+pub mod id;
+pub mod media;
+pub mod snowflake;
@@ -0,0 +1,25 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+/// Milliseconds between the Unix epoch and Twitter's snowflake epoch
+const TWITTER_EPOCH: i64 = 1_288_834_974_657;
+
+/// The first tweet id generated by snowflake
+const FIRST_SNOWFLAKE: u64 = 29_700_859_247;
+
+/// Ids created after November 2010 embed the time they were
+/// created at. Older ids do not and return `None`.
+pub fn snowflake_time(id: u64) -> Option<DateTime<Utc>> {
+    if id < FIRST_SNOWFLAKE {
+        return None;
+    }
+
+    Utc.timestamp_millis_opt((id >> 22) as i64 + TWITTER_EPOCH).single()
+}
+
+/// Creates an id that embeds `time`. `sequence` tells apart ids
+/// created in the same millisecond and wraps at 4096.
+#[cfg(feature = "mock")]
+pub fn snowflake_id(time: DateTime<Utc>, sequence: u64) -> u64 {
+    let millis = (time.timestamp_millis() - TWITTER_EPOCH).max(0) as u64;
+    (millis << 22) | (sequence & 0xfff)
+}
@@ -0,0 +1,82 @@
+//! Helpers for receiving Account Activity API webhooks.
+//!
+//! Twitter periodically sends a `GET` request with a `crc_token` that
+//! has to be answered with `crc_response`, and signs the body of every
+//! `POST` request in the `x-twitter-webhooks-signature` header, which
+//! can be checked with `verify_signature`. The body itself parses into
+//! an `AccountActivityEvent`.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Name of the header that carries the signature of a webhook request
+pub const SIGNATURE_HEADER: &str = "x-twitter-webhooks-signature";
+
+/// Computes the `sha256=<base64>` response token for a challenge
+/// or the signature of a request body
+pub fn sign(consumer_secret: &str, message: &[u8]) -> String {
+    let mut mac = mac(consumer_secret);
+    mac.update(message);
+    format!("sha256={}", STANDARD.encode(mac.finalize().into_bytes()))
+}
+
+/// Computes the response token for a `crc_token` challenge
+pub fn crc_response(consumer_secret: &str, crc_token: &str) -> String {
+    sign(consumer_secret, crc_token.as_bytes())
+}
+
+/// Builds the JSON body to answer a `crc_token` challenge with
+pub fn crc_response_json(consumer_secret: &str, crc_token: &str) -> String {
+    serde_json::json!({ "response_token": crc_response(consumer_secret, crc_token) }).to_string()
+}
+
+/// Checks the `x-twitter-webhooks-signature` header of a request
+/// against its body. The comparison takes constant time.
+pub fn verify_signature(consumer_secret: &str, body: &[u8], signature: &str) -> bool {
+    let expected = match signature.trim().strip_prefix("sha256=").map(|s| STANDARD.decode(s)) {
+        Some(Ok(expected)) => expected,
+        _ => return false,
+    };
+
+    let mut mac = mac(consumer_secret);
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn mac(consumer_secret: &str) -> HmacSha256 {
+    HmacSha256::new_from_slice(consumer_secret.as_bytes()).expect("HMAC accepts keys of any size")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "my_consumer_secret";
+    const BODY: &[u8] = br#"{"for_user_id":"2244994945"}"#;
+    const BODY_SIGNATURE: &str = "sha256=fCaDxm7ehSAujgjQURyuSrDMJMObOHPvXo49BFNsd38=";
+
+    #[test]
+    fn crc_response_matches_known_vector() {
+        assert_eq!(crc_response(SECRET, "crc_token_value"), "sha256=XUJv3MH6ICj7aN5WMwMjrPFX4Cl2LTVyVa4yVzh/rwI=");
+        assert_eq!(
+            crc_response_json(SECRET, "crc_token_value"),
+            r#"{"response_token":"sha256=XUJv3MH6ICj7aN5WMwMjrPFX4Cl2LTVyVa4yVzh/rwI="}"#
+        );
+    }
+
+    #[test]
+    fn signatures_are_verified() {
+        assert_eq!(sign(SECRET, BODY), BODY_SIGNATURE);
+        assert!(verify_signature(SECRET, BODY, BODY_SIGNATURE));
+        assert!(verify_signature(SECRET, BODY, &format!(" {}\n", BODY_SIGNATURE)));
+
+        assert!(!verify_signature("other_secret", BODY, BODY_SIGNATURE));
+        assert!(!verify_signature(SECRET, br#"{"for_user_id":"1"}"#, BODY_SIGNATURE));
+        assert!(!verify_signature(SECRET, BODY, "fCaDxm7ehSAujgjQURyuSrDMJMObOHPvXo49BFNsd38="));
+        assert!(!verify_signature(SECRET, BODY, "sha256=not base64"));
+    }
+}